@@ -0,0 +1,28 @@
+//! Arbitrary bytes through `codec::decode` and then `Validator::validate`.
+//!
+//! Invariant: neither call may panic, no input may make them allocate without bound, and
+//! the validator's tables must still pass `Validator::check_invariants` afterwards.
+//! libFuzzer enforces the second part by default: any single allocation or total RSS above
+//! `-rss_limit_mb` (2048 MB) is reported as a crash, so an allocation bomb such as a huge
+//! length prefix fails the run instead of exhausting the machine.
+#![no_main]
+
+use gossipsub_score_sim::codec::decode;
+use gossipsub_score_sim::testing::deterministic_peer_id;
+use gossipsub_score_sim::validator::{Validator, ValidatorConfig};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = decode(data);
+
+    // Both attributions: the author's own message, and one relayed without a source.
+    // Fixed ids, so a saved crash replays the same eviction and tie-break order.
+    let mut v = Validator::new(ValidatorConfig::default());
+    let forwarder = deterministic_peer_id(0);
+    let author = deterministic_peer_id(1);
+    let _ = v.validate(&forwarder, Some(&author), data);
+    let _ = v.validate(&forwarder, None, data);
+    if let Err(e) = v.check_invariants() {
+        panic!("validator invariant broken: {e}");
+    }
+});
@@ -0,0 +1,84 @@
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use libp2p::PeerId;
+use serde::Serialize;
+use tokio::fs::OpenOptions;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::validator::{acceptance_str, Decision, RejectReason};
+
+/// One line of the audit log: a single validation decision and its effect on scores.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditRecord {
+    pub timestamp_ms: u128,
+    pub node: usize,
+    pub propagation_source: String,
+    pub author: Option<String>,
+    pub reason: RejectReason,
+    pub acceptance: &'static str,
+    pub score_delta: f64,
+    /// Score of the peer the message is attributed to, after the decision.
+    pub score: f64,
+    pub forwarder_score: f64,
+}
+
+impl AuditRecord {
+    pub fn new(
+        node: usize,
+        propagation_source: &PeerId,
+        author: Option<&PeerId>,
+        decision: &Decision,
+        score: f64,
+        forwarder_score: f64,
+    ) -> Self {
+        Self {
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis())
+                .unwrap_or(0),
+            node,
+            propagation_source: propagation_source.to_string(),
+            author: author.map(|a| a.to_string()),
+            reason: decision.reason,
+            acceptance: acceptance_str(&decision.acceptance),
+            score_delta: decision.score_delta,
+            score,
+            forwarder_score,
+        }
+    }
+}
+
+/// Handle for appending to the audit log. Cheap to clone; every node gets one.
+/// Records go through a channel to a single writer task so lines never interleave.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    tx: mpsc::Sender<AuditRecord>,
+}
+
+impl AuditLog {
+    /// Open (or create) `path` for appending and spawn the writer task. The task finishes,
+    /// flushing everything, once every `AuditLog` clone has been dropped.
+    pub async fn open(path: &Path) -> anyhow::Result<(Self, JoinHandle<std::io::Result<()>>)> {
+        let file = OpenOptions::new().create(true).append(true).open(path).await?;
+        let (tx, mut rx) = mpsc::channel::<AuditRecord>(4096);
+        let writer = tokio::spawn(async move {
+            let mut out = BufWriter::new(file);
+            while let Some(record) = rx.recv().await {
+                let mut line = serde_json::to_vec(&record).map_err(std::io::Error::other)?;
+                line.push(b'\n');
+                out.write_all(&line).await?;
+            }
+            out.flush().await
+        });
+        Ok((Self { tx }, writer))
+    }
+
+    pub async fn record(&self, record: AuditRecord) {
+        if self.tx.send(record).await.is_err() {
+            tracing::warn!("audit log writer has stopped; dropping record");
+        }
+    }
+}
@@ -1,9 +1,13 @@
 use libp2p::gossipsub;
 use libp2p::identity::Keypair;
 use libp2p::swarm::NetworkBehaviour;
-use sha2::{Digest, Sha256};
 use hex;
 
+use crate::codec::content_hash;
+
+/// Composite gossipsub score below which a peer's RPCs are ignored entirely.
+pub const GRAYLIST_THRESHOLD: f64 = -80.0;
+
 #[derive(NetworkBehaviour)]
 #[behaviour(to_swarm = "Event")]
 pub struct Behaviour {
@@ -20,53 +24,137 @@ impl From<gossipsub::Event> for Event {
     }
 }
 
+/// How published messages are authenticated. `Anonymous` publishes without a source,
+/// signature or sequence number, so receivers only know the forwarding peer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Authenticity {
+    #[default]
+    Signed,
+    Anonymous,
+}
+
+impl Authenticity {
+    /// The validation mode that matches how we publish: strict for signed, anonymous otherwise.
+    pub fn default_validation_mode(self) -> ValidationMode {
+        match self {
+            Authenticity::Signed => ValidationMode::Strict,
+            Authenticity::Anonymous => ValidationMode::Anonymous,
+        }
+    }
+}
+
+/// Which signature/author checks gossipsub applies to incoming messages before they reach
+/// our validator. Mirrors `gossipsub::ValidationMode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ValidationMode {
+    /// Require a signature, author and sequence number.
+    Strict,
+    /// Verify signatures when present; unsigned messages are allowed.
+    Permissive,
+    /// Drop messages that carry a signature, author or sequence number.
+    Anonymous,
+    /// No checks; pass everything through.
+    None,
+}
+
+impl From<ValidationMode> for gossipsub::ValidationMode {
+    fn from(mode: ValidationMode) -> Self {
+        match mode {
+            ValidationMode::Strict => gossipsub::ValidationMode::Strict,
+            ValidationMode::Permissive => gossipsub::ValidationMode::Permissive,
+            ValidationMode::Anonymous => gossipsub::ValidationMode::Anonymous,
+            ValidationMode::None => gossipsub::ValidationMode::None,
+        }
+    }
+}
+
+/// Gossipsub message cache and gossip tuning. Defaults match gossipsub's own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeshConfig {
+    /// Heartbeats a message stays in the cache (and can be served via IWANT).
+    pub history_length: usize,
+    /// Of those, how many recent heartbeats are advertised in IHAVE gossip; at most
+    /// `history_length`.
+    pub history_gossip: usize,
+    /// Peers outside the mesh gossiped to each heartbeat.
+    pub gossip_lazy: usize,
+}
+
+impl Default for MeshConfig {
+    fn default() -> Self {
+        Self {
+            history_length: 5,
+            history_gossip: 3,
+            gossip_lazy: 6,
+        }
+    }
+}
+
+/// The gossipsub message id for `data` under `domain` (hex of its content hash).
+pub fn message_id(domain: &str, data: &[u8]) -> gossipsub::MessageId {
+    gossipsub::MessageId::from(hex::encode(content_hash(domain, data)))
+}
+
 impl Behaviour {
     /// Build the gossipsub behaviour with manual validation and enabled peer scoring.
     /// `topic` parameter is unused here but kept for symmetry with the rest of the codebase.
-    pub fn new(key: Keypair, _topic: &str) -> Self {
-        // message id function: content-addressed by sha256(payload)
-        let message_id_fn = |message: &gossipsub::Message| {
-            let mut hasher = Sha256::new();
-            hasher.update(b"gossipsub-v1.1:");
-            hasher.update(&message.data);
-            let h = hasher.finalize();
-            let mut id = [0u8; 32];
-            id.copy_from_slice(&h);
-            gossipsub::MessageId::from(hex::encode(id))
+    /// Fails if `validation_mode` contradicts `authenticity` (e.g. strict validation while
+    /// publishing anonymously). `message_domain` must match the validator's so message ids and
+    /// dedupe keys agree. Also fails if `mesh` is rejected by gossipsub (e.g. `history_gossip`
+    /// longer than `history_length`).
+    pub fn new(
+        key: Keypair,
+        _topic: &str,
+        authenticity: Authenticity,
+        validation_mode: ValidationMode,
+        message_domain: &str,
+        mesh: &MeshConfig,
+    ) -> Result<Self, &'static str> {
+        // message id function: content-addressed by sha256(domain || payload)
+        let domain = message_domain.to_owned();
+        let message_id_fn = move |message: &gossipsub::Message| message_id(&domain, &message.data);
+
+        let message_authenticity = match authenticity {
+            Authenticity::Signed => gossipsub::MessageAuthenticity::Signed(key.clone()),
+            // gossipsub refuses anonymous publishing under strict validation.
+            Authenticity::Anonymous => gossipsub::MessageAuthenticity::Anonymous,
         };
 
         let config = gossipsub::ConfigBuilder::default()
             .validate_messages()
+            .validation_mode(validation_mode.into())
             .message_id_fn(message_id_fn)
+            .history_length(mesh.history_length)
+            .history_gossip(mesh.history_gossip)
+            .gossip_lazy(mesh.gossip_lazy)
             .build()
-            .expect("valid gossipsub config");
+            .map_err(|_| "invalid gossipsub mesh config")?;
 
-        let mut gossipsub =
-            gossipsub::Behaviour::new(gossipsub::MessageAuthenticity::Signed(key.clone()), config)
-                .expect("gossipsub behaviour");
+        let mut gossipsub = gossipsub::Behaviour::new(message_authenticity, config)?;
 
         // Enable peer scoring and set reasonable defaults for simulation.
         use libp2p::gossipsub::{PeerScoreParams, PeerScoreThresholds};
-        let mut params = PeerScoreParams::default();
-        // Make application-specific weight non-zero so set_application_score matters.
-        // Make application-specific score (set_application_score) have stronger influence.
-        params.app_specific_weight = 5.0;
-        // Disable aggressive IP-colocation penalties in localhost simulations.
-        params.ip_colocation_factor_threshold = 1_000_000.0;
+        let params = PeerScoreParams {
+            // Make application-specific weight non-zero so set_application_score matters.
+            // Make application-specific score (set_application_score) have stronger influence.
+            app_specific_weight: 5.0,
+            // Disable aggressive IP-colocation penalties in localhost simulations.
+            ip_colocation_factor_threshold: 1_000_000.0,
+            ..Default::default()
+        };
 
         let thresholds = PeerScoreThresholds {
             gossip_threshold: -15.0,
             publish_threshold: -40.0,
-            graylist_threshold: -80.0,
+            graylist_threshold: GRAYLIST_THRESHOLD,
             accept_px_threshold: 5.0,
             opportunistic_graft_threshold: 10.0,
-            ..Default::default()
         };
 
         gossipsub
             .with_peer_score(params, thresholds)
             .expect("enable peer scoring");
 
-        Self { gossipsub }
+        Ok(Self { gossipsub })
     }
 }
@@ -1,5 +1,16 @@
+use std::path::PathBuf;
+
 use clap::Parser;
 
+use crate::behaviour::{Authenticity, ValidationMode};
+use crate::codec::DEFAULT_MESSAGE_DOMAIN;
+use crate::p2p::{AppScoreUpdate, ReportAs, DEFAULT_MAX_PENDING_VALIDATIONS};
+use crate::sim::{
+    BadSelection, Scenario, DEFAULT_SLA_MAX_FALSE_POSITIVE_RATE, DEFAULT_SLA_MAX_HONEST_QUARANTINES,
+    DEFAULT_SLA_MIN_HONEST_SUCCESS, DEFAULT_SLA_MIN_REJECTION_RATE,
+};
+use crate::validator::RejectReason;
+
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
@@ -23,4 +34,195 @@ pub struct Cli {
 
     #[arg(long, default_value_t = 0)]
     pub seed: u64,
+
+    /// Replace --seed with a fresh random one, logged (and shown in the report) so a
+    /// surprising run can be reproduced with --seed.
+    #[arg(long)]
+    pub random_seed: bool,
+
+    /// Interface every node listens on, e.g. `/ip6/::1/tcp/0` or `/ip4/0.0.0.0/tcp/0`.
+    #[arg(long, default_value = "/ip4/127.0.0.1/tcp/0")]
+    pub listen_addr: libp2p::Multiaddr,
+
+    /// Listen on `base_port + i` for node `i` instead of ephemeral ports.
+    #[arg(long)]
+    pub base_port: Option<u16>,
+
+    /// Print a live tally of all nodes every N seconds while the simulation runs.
+    #[arg(long)]
+    pub summary_interval: Option<u64>,
+
+    /// Sign published messages, or publish anonymously (no source/author on messages).
+    #[arg(long, value_enum, default_value_t = Authenticity::Signed)]
+    pub authenticity: Authenticity,
+
+    /// Each node subscribes on its first connection; one still unconnected after this many
+    /// milliseconds subscribes anyway.
+    #[arg(long, default_value_t = 2000)]
+    pub subscribe_fallback_ms: u64,
+
+    /// How long to wait for every node to subscribe before publishing starts.
+    #[arg(long, default_value_t = 5)]
+    pub ready_timeout_secs: u64,
+
+    /// Abort the run instead of continuing with a partially-ready network.
+    #[arg(long)]
+    pub strict_ready: bool,
+
+    /// Append one JSON line per validation decision (from every node) to this file.
+    #[arg(long)]
+    pub audit_log: Option<PathBuf>,
+
+    /// At the end of the run, print node N's validator state (peers, scores, offences,
+    /// quarantine and last sequence numbers) as JSON.
+    #[arg(long)]
+    pub dump_node: Option<usize>,
+
+    /// Write the --dump-node JSON to this file instead of stdout.
+    #[arg(long)]
+    pub dump_node_out: Option<PathBuf>,
+
+    /// Pause a node's publishing while fewer than this many mesh peers remain (0 = never).
+    #[arg(long, default_value_t = 0)]
+    pub min_publish_peers: usize,
+
+    /// The first N nodes act as bootstraps; the rest dial a seeded random half of them.
+    #[arg(long, default_value_t = 1)]
+    pub bootstrap_count: usize,
+
+    /// Disable the validator's content-hash dedupe, to measure how much it suppresses.
+    #[arg(long)]
+    pub no_dedupe: bool,
+
+    /// Gossipsub signature checks on incoming messages; defaults to the one matching --authenticity.
+    #[arg(long, value_enum)]
+    pub validation_mode: Option<ValidationMode>,
+
+    /// Which nodes are malicious: the first --bad-peers, or a seeded random sample.
+    #[arg(long, value_enum, default_value_t = BadSelection::Prefix)]
+    pub bad_selection: BadSelection,
+
+    /// With --bad-selection random, never pick a bootstrap node as malicious.
+    #[arg(long)]
+    pub bad_excludes_bootstraps: bool,
+
+    /// Push app scores into gossipsub after every message, or batched once per heartbeat.
+    #[arg(long, value_enum, default_value_t = AppScoreUpdate::PerMessage)]
+    pub app_score_update: AppScoreUpdate,
+
+    /// Domain separator hashed into message ids and dedupe keys.
+    #[arg(long, default_value = DEFAULT_MESSAGE_DOMAIN)]
+    pub message_domain: String,
+
+    /// `recovery`: bad peers spam for the first half of the run, then go silent.
+    /// `adaptive`: they switch to stealthy well-formed traffic instead.
+    #[arg(long, value_enum, default_value_t = Scenario::Steady)]
+    pub scenario: Scenario,
+
+    /// When `adaptive` attackers assume they are quarantined and go stealthy; defaults to
+    /// halfway through the run.
+    #[arg(long)]
+    pub adapt_after_secs: Option<u64>,
+
+    /// Every second, multiply validator scores by this factor and forgive one offence per peer.
+    #[arg(long)]
+    pub score_decay: Option<f64>,
+
+    /// Honest acceptance rate (0-1) each node steers its penalties toward, re-tuned every second.
+    #[arg(long)]
+    pub target_acceptance: Option<f64>,
+
+    /// Every second, relay each honest node's score changes to the other honest nodes as
+    /// reputation reports.
+    #[arg(long)]
+    pub reputation_gossip: bool,
+
+    /// Probability (0-1) that each publish is lost before reaching its node, drawn from a
+    /// seeded RNG, to model lossy links.
+    #[arg(long, default_value_t = 0.0)]
+    pub drop_rate: f64,
+
+    /// SLA: minimum percent of honest messages accepted.
+    #[arg(long, default_value_t = DEFAULT_SLA_MIN_HONEST_SUCCESS)]
+    pub sla_min_honest_success: f64,
+
+    /// SLA: minimum percent of all messages rejected.
+    #[arg(long, default_value_t = DEFAULT_SLA_MIN_REJECTION_RATE)]
+    pub sla_min_rejection_rate: f64,
+
+    /// SLA: maximum (honest node, honest peer) quarantine pairs at the end of the run.
+    #[arg(long, default_value_t = DEFAULT_SLA_MAX_HONEST_QUARANTINES)]
+    pub sla_max_honest_quarantines: usize,
+
+    /// SLA: maximum percent of honest peers blind detection flags as malicious.
+    #[arg(long, default_value_t = DEFAULT_SLA_MAX_FALSE_POSITIVE_RATE)]
+    pub sla_max_false_positive_rate: f64,
+
+    /// Honest nodes that must accept a distinct honest message for it to count as delivered
+    /// in the unique-delivery rate.
+    #[arg(long, default_value_t = 1)]
+    pub delivery_quorum: usize,
+
+    /// Run a shadow validator on every node with all penalties multiplied by this factor,
+    /// and report how its decisions differ from the primary's.
+    #[arg(long)]
+    pub shadow_penalty_scale: Option<f64>,
+
+    /// Lowest validator score passed to gossipsub as the application score (before its 5x
+    /// weight), so our signal can't drown out gossipsub's own scoring.
+    #[arg(long, allow_hyphen_values = true)]
+    pub app_score_floor: Option<f64>,
+
+    /// Heartbeats gossipsub keeps messages in its cache.
+    #[arg(long, default_value_t = 5)]
+    pub history_length: usize,
+
+    /// Heartbeats of cached messages advertised in gossip (at most --history-length).
+    #[arg(long, default_value_t = 3)]
+    pub history_gossip: usize,
+
+    /// Non-mesh peers gossiped to each heartbeat.
+    #[arg(long, default_value_t = 6)]
+    pub gossip_lazy: usize,
+
+    /// Dials in flight at once while wiring up the network; each batch waits for its
+    /// connections before the next is sent. Unset fires every dial at once.
+    #[arg(long)]
+    pub dial_concurrency: Option<usize>,
+
+    /// Validate messages on a dedicated blocking worker instead of each node's swarm task.
+    #[arg(long)]
+    pub validation_worker: bool,
+
+    /// Validations allowed in flight per node; messages past the cap are reported as
+    /// `Ignore` without being validated. 0 disables the cap.
+    #[arg(long, default_value_t = DEFAULT_MAX_PENDING_VALIDATIONS)]
+    pub max_pending_validations: usize,
+
+    /// Report a decision reason to gossipsub as another verdict, e.g. `oversize=ignore` to
+    /// leave oversize messages to our app score alone. Repeatable.
+    #[arg(long, value_parser = parse_report_as)]
+    pub report_as: Vec<(RejectReason, ReportAs)>,
+
+    /// Don't simulate: replay this recorded trace (see `trace::write_trace`) through one
+    /// validator and print its decisions as JSON lines.
+    #[arg(long)]
+    pub replay_trace: Option<PathBuf>,
+
+    /// Skip libp2p: feed the generated traffic straight into in-process validators on a
+    /// simulated clock, for fast A/B runs of scoring changes.
+    #[arg(long)]
+    pub no_network: bool,
+}
+
+/// Parse a `reason=verdict` pair for `--report-as`. The reason must be one the validator
+/// can refuse a message for; remapping `ok` would only hide honest traffic from gossipsub.
+fn parse_report_as(s: &str) -> Result<(RejectReason, ReportAs), String> {
+    let (reason, verdict) = s.split_once('=').ok_or("expected REASON=VERDICT")?;
+    let reason: RejectReason = reason.parse()?;
+    if !reason.refuses() {
+        return Err(format!("`{reason}` never refuses a message, so there is no verdict to replace"));
+    }
+    let verdict = <ReportAs as clap::ValueEnum>::from_str(verdict, true)?;
+    Ok((reason, verdict))
 }
@@ -1,16 +1,168 @@
+use std::borrow::Cow;
+
 use bincode;
+use bincode::Options;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Domain separator prefixed to message bytes before hashing them into a message id.
+pub const DEFAULT_MESSAGE_DOMAIN: &str = "gossipsub-v1.1:";
+
+/// Leading byte of every encoded message; bump it when `WireMessage`'s layout changes.
+pub const WIRE_VERSION: u8 = 1;
+
+/// Set on the leading version byte when the rest of the frame is run-length compressed; the
+/// decompressed bytes are an ordinary `[WIRE_VERSION][bincode]` frame. Only
+/// `WIRE_VERSION | COMPRESSED_FLAG` marks a compressed frame; any other high byte is just an
+/// unknown version.
+pub const COMPRESSED_FLAG: u8 = 0x80;
+
+/// Largest payload a `Good` message may declare. A longer length prefix is refused up front,
+/// however few bytes actually follow it.
+pub const MAX_PAYLOAD_BYTES: u64 = 1 << 20;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum WireMessage {
     Good { seq: u64, payload: Vec<u8> },
-    Bad,
+    /// Explicit attack marker published by the simulation's bad peers; always rejected.
+    Malicious,
+}
+
+/// Why bytes didn't decode into a `WireMessage`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodecError {
+    /// The input ended before the message did.
+    Truncated,
+    /// A version byte other than `WIRE_VERSION`.
+    UnknownVersion(u8),
+    /// Not a `WireMessage` at all (e.g. an unknown variant tag).
+    InvalidEncoding,
+    /// The payload's length prefix exceeds `MAX_PAYLOAD_BYTES`.
+    TooLarge,
+    /// A valid message followed by leftover bytes (`decode_strict` only).
+    TrailingBytes,
+}
+
+impl std::fmt::Display for CodecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CodecError::Truncated => write!(f, "message is truncated"),
+            CodecError::UnknownVersion(v) => write!(f, "unknown wire version {v}"),
+            CodecError::InvalidEncoding => write!(f, "invalid message encoding"),
+            CodecError::TooLarge => write!(f, "declared payload exceeds {MAX_PAYLOAD_BYTES} bytes"),
+            CodecError::TrailingBytes => write!(f, "bytes left over after the message"),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+impl From<bincode::Error> for CodecError {
+    fn from(e: bincode::Error) -> Self {
+        match *e {
+            bincode::ErrorKind::Io(ref io) if io.kind() == std::io::ErrorKind::UnexpectedEof => CodecError::Truncated,
+            bincode::ErrorKind::SizeLimit => CodecError::TooLarge,
+            _ => CodecError::InvalidEncoding,
+        }
+    }
 }
 
 pub fn encode(msg: &WireMessage) -> Vec<u8> {
-    bincode::serialize(msg).expect("encode")
+    let mut out = vec![WIRE_VERSION];
+    bincode::serialize_into(&mut out, msg).expect("encode");
+    out
+}
+
+/// Length of `encode(msg)`, without building the frame.
+pub fn encoded_len(msg: &WireMessage) -> usize {
+    1 + bincode::serialized_size(msg).expect("encode") as usize
+}
+
+/// Like `encode`, with the frame run-length compressed behind a `COMPRESSED_FLAG` byte.
+pub fn encode_compressed(msg: &WireMessage) -> Vec<u8> {
+    let plain = encode(msg);
+    let mut out = vec![WIRE_VERSION | COMPRESSED_FLAG];
+    for run in plain.chunk_by(|a, b| a == b) {
+        for part in run.chunks(u8::MAX as usize) {
+            out.push(part.len() as u8);
+            out.push(part[0]);
+        }
+    }
+    out
+}
+
+/// The plain frame inside `bytes`: borrowed as-is unless it leads with
+/// `WIRE_VERSION | COMPRESSED_FLAG`, in which case it is decompressed, failing with `TooLarge` as soon as the output would pass
+/// `limit` bytes. Allocation is bounded by `limit` however small the compressed input.
+pub fn decompress(bytes: &[u8], limit: usize) -> Result<Cow<'_, [u8]>, CodecError> {
+    match bytes.first() {
+        Some(&first) if first == WIRE_VERSION | COMPRESSED_FLAG => {
+            let body = &bytes[1..];
+            if !body.len().is_multiple_of(2) {
+                return Err(CodecError::Truncated);
+            }
+            let mut out = Vec::new();
+            for pair in body.chunks_exact(2) {
+                let (count, byte) = (pair[0] as usize, pair[1]);
+                if count == 0 {
+                    return Err(CodecError::InvalidEncoding);
+                }
+                if out.len() + count > limit {
+                    return Err(CodecError::TooLarge);
+                }
+                out.resize(out.len() + count, byte);
+            }
+            Ok(Cow::Owned(out))
+        }
+        _ => Ok(Cow::Borrowed(bytes)),
+    }
+}
+
+/// Decode a message, ignoring any bytes after it.
+pub fn decode(bytes: &[u8]) -> Result<WireMessage, CodecError> {
+    decode_prefix(bytes).map(|(msg, _)| msg)
+}
+
+/// Like `decode`, but fails unless the message consumes the whole buffer.
+pub fn decode_strict(bytes: &[u8]) -> Result<WireMessage, CodecError> {
+    match decode_prefix(bytes)? {
+        (msg, 0) => Ok(msg),
+        _ => Err(CodecError::TrailingBytes),
+    }
+}
+
+/// sha256(domain || bytes): the content address shared by gossipsub message ids and the
+/// validator's dedupe cache.
+pub fn content_hash(domain: &str, bytes: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(domain.as_bytes());
+    hasher.update(bytes);
+    hasher.finalize().into()
+}
+
+// The message and how many bytes were left after it.
+fn decode_prefix(bytes: &[u8]) -> Result<(WireMessage, usize), CodecError> {
+    let (&version, body) = bytes.split_first().ok_or(CodecError::Truncated)?;
+    if version != WIRE_VERSION {
+        return Err(CodecError::UnknownVersion(version));
+    }
+    if declared_payload_len(body).is_some_and(|len| len > MAX_PAYLOAD_BYTES) {
+        return Err(CodecError::TooLarge);
+    }
+    let mut rest = body;
+    let msg = bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .deserialize_from(&mut rest)?;
+    Ok((msg, rest.len()))
 }
 
-pub fn decode(bytes: &[u8]) -> Result<WireMessage, bincode::Error> {
-    bincode::deserialize(bytes)
+// Payload length a `Good` message declares: bincode's fixint layout is a u32 variant tag,
+// then `seq` as u64, then the payload's u64 length prefix.
+fn declared_payload_len(body: &[u8]) -> Option<u64> {
+    let tag = u32::from_le_bytes(body.get(0..4)?.try_into().ok()?);
+    if tag != 0 {
+        return None;
+    }
+    Some(u64::from_le_bytes(body.get(12..20)?.try_into().ok()?))
 }
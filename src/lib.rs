@@ -1,7 +1,11 @@
+pub mod audit;
 pub mod behaviour;
 pub mod cli;
 pub mod codec;
 pub mod metrics;
 pub mod p2p;
 pub mod sim;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod trace;
 pub mod validator;
@@ -9,5 +9,9 @@ async fn main() -> anyhow::Result<()> {
         .init();
 
     let cli = gossipsub_score_sim::cli::Cli::parse();
-    gossipsub_score_sim::sim::run(cli).await
+    if let Some(path) = &cli.replay_trace {
+        return gossipsub_score_sim::trace::replay_file(&cli, path);
+    }
+    gossipsub_score_sim::sim::run(cli).await?;
+    Ok(())
 }
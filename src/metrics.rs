@@ -1,3 +1,6 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
@@ -5,4 +8,92 @@ pub struct Counters {
     pub accepted: u64,
     pub rejected: u64,
     pub ignored: u64,
+    /// Messages whose validation could not complete and were reported as `Ignore`.
+    pub validation_failures: u64,
+    /// Messages reported as `Ignore` unvalidated because too many validations were in flight.
+    pub shed_validations: u64,
+    /// Messages that arrived without a source (anonymous publishing).
+    pub no_source_messages: u64,
+    /// Decisions broken down by validator reason (e.g. "ok", "oversize").
+    pub by_reason: BTreeMap<String, u64>,
+}
+
+impl Counters {
+    /// Render the counters in the Prometheus text exposition format, labelled with `node`.
+    pub fn to_prometheus_text(&self, node_idx: usize) -> String {
+        let mut out = String::new();
+        for (name, value) in [
+            ("accepted", self.accepted),
+            ("rejected", self.rejected),
+            ("ignored", self.ignored),
+            ("validation_failures", self.validation_failures),
+            ("shed_validations", self.shed_validations),
+            ("no_source", self.no_source_messages),
+        ] {
+            let _ = writeln!(out, "# TYPE gossipsub_messages_{name}_total counter");
+            let _ = writeln!(out, "gossipsub_messages_{name}_total{{node=\"{node_idx}\"}} {value}");
+        }
+        let _ = writeln!(out, "# TYPE gossipsub_decisions_total counter");
+        for (reason, value) in &self.by_reason {
+            let _ = writeln!(
+                out,
+                "gossipsub_decisions_total{{node=\"{node_idx}\",reason=\"{reason}\"}} {value}"
+            );
+        }
+        out
+    }
+}
+
+/// Upper bounds (in microseconds) of the latency histogram buckets; the last bucket is open.
+const LATENCY_BUCKETS_US: [u64; 12] = [1, 2, 5, 10, 20, 50, 100, 200, 500, 1_000, 5_000, 10_000];
+
+/// Fixed-bucket histogram of durations, cheap enough to update on every message.
+#[derive(Debug, Clone, Default)]
+pub struct LatencyHistogram {
+    // one slot per bucket in LATENCY_BUCKETS_US plus the overflow bucket
+    counts: [u64; LATENCY_BUCKETS_US.len() + 1],
+    total: u64,
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, d: std::time::Duration) {
+        let us = d.as_micros();
+        let idx = LATENCY_BUCKETS_US
+            .iter()
+            .position(|&b| us <= b as u128)
+            .unwrap_or(LATENCY_BUCKETS_US.len());
+        self.counts[idx] += 1;
+        self.total += 1;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.total
+    }
+
+    /// Upper bound, in microseconds, of the bucket holding the `q` quantile (0.0..=1.0).
+    /// The overflow bucket reports `u64::MAX`; an empty histogram reports 0.
+    pub fn percentile_us(&self, q: f64) -> u64 {
+        if self.total == 0 {
+            return 0;
+        }
+        let rank = ((q.clamp(0.0, 1.0) * self.total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, c) in self.counts.iter().enumerate() {
+            seen += c;
+            if seen >= rank {
+                return LATENCY_BUCKETS_US.get(i).copied().unwrap_or(u64::MAX);
+            }
+        }
+        u64::MAX
+    }
+
+    /// (bucket upper bound in microseconds, count) pairs; the overflow bucket is `u64::MAX`.
+    pub fn buckets(&self) -> Vec<(u64, u64)> {
+        LATENCY_BUCKETS_US
+            .iter()
+            .copied()
+            .chain(std::iter::once(u64::MAX))
+            .zip(self.counts.iter().copied())
+            .collect()
+    }
 }
@@ -1,36 +1,290 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use anyhow::Context;
 use futures::StreamExt;
+use libp2p::identity::Keypair;
+use libp2p::multiaddr::Protocol;
 use libp2p::swarm::SwarmEvent;
 use libp2p::{gossipsub, Multiaddr, Swarm, SwarmBuilder};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{interval_at, Duration, Instant};
 use tracing::{debug, info, warn};
 
-use crate::behaviour::{Behaviour, Event as BehaviourEvent};
+use crate::audit::{AuditLog, AuditRecord};
+use crate::behaviour::{Authenticity, Behaviour, Event as BehaviourEvent, MeshConfig, ValidationMode, GRAYLIST_THRESHOLD};
+use crate::codec::DEFAULT_MESSAGE_DOMAIN;
 use crate::metrics::Counters;
-use crate::validator::{Validator, ValidatorConfig};
+use crate::validator::{acceptance_str, AcceptanceController, Classification, DecayConfig, Decision, RejectReason, ThrottleStats, Validator, ValidatorConfig, ValidatorSnapshot};
+
+// How often the node forgets peers that have gone quiet, and how quiet counts as idle.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(30);
+const PEER_IDLE_TIMEOUT: Duration = Duration::from_secs(600);
+// How often gossipsub's composite peer scores are compared against our own.
+const SCORE_OBSERVE_INTERVAL: Duration = Duration::from_secs(10);
+// Batched app-score flushes line up with gossipsub's default heartbeat.
+const APP_SCORE_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+// How often `target_acceptance` re-tunes penalties.
+const PENALTY_CONTROL_INTERVAL: Duration = Duration::from_secs(1);
+// Reconnects from a quarantined peer are refused for this long after its first abusive
+// disconnect, doubling with each further one up to the cap.
+const RECONNECT_BACKOFF_BASE: Duration = Duration::from_secs(5);
+const RECONNECT_BACKOFF_MAX: Duration = Duration::from_secs(300);
+/// Default `max_pending_validations`: far above a healthy backlog, low enough to bound memory.
+pub const DEFAULT_MAX_PENDING_VALIDATIONS: usize = 1024;
+
+/// When validator scores are pushed into gossipsub via `set_application_score`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum AppScoreUpdate {
+    /// After every validated message.
+    #[default]
+    PerMessage,
+    /// Once per heartbeat for each peer whose score changed, coalescing its deltas.
+    Batched,
+}
+
+/// Verdict reported to gossipsub in place of the validator's own, see `NodeConfig::report_as`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportAs {
+    Accept,
+    /// Drop without gossipsub's invalid-delivery penalty; our app score still applies.
+    Ignore,
+    Reject,
+}
+
+impl From<ReportAs> for gossipsub::MessageAcceptance {
+    fn from(r: ReportAs) -> Self {
+        match r {
+            ReportAs::Accept => gossipsub::MessageAcceptance::Accept,
+            ReportAs::Ignore => gossipsub::MessageAcceptance::Ignore,
+            ReportAs::Reject => gossipsub::MessageAcceptance::Reject,
+        }
+    }
+}
+
+/// The verdict handed to `report_message_validation_result` for `decision`: its reason's
+/// entry in `report_as` if there is one, else the validator's own.
+pub fn reported_acceptance(decision: &Decision, report_as: &HashMap<RejectReason, ReportAs>) -> gossipsub::MessageAcceptance {
+    match report_as.get(&decision.reason) {
+        Some(r) => (*r).into(),
+        None => clone_acceptance(&decision.acceptance),
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct NodeConfig {
     pub idx: usize,
     pub topic: String,
     pub max_message_bytes: usize,
+    /// Interface and transport to listen on, e.g. `/ip6/::1/tcp/0`. Must include a TCP port.
+    pub listen_addr: Multiaddr,
+    /// TCP port to listen on, replacing the one in `listen_addr`; 0 keeps `listen_addr`'s.
+    pub listen_port: u16,
+    pub authenticity: Authenticity,
+    /// Gossipsub-level signature checks; `None` picks the one matching `authenticity`.
+    pub validation_mode: Option<ValidationMode>,
+    /// Append every validation decision to this audit log.
+    pub audit: Option<AuditLog>,
+    /// Hold back publishes while fewer mesh peers than this still keep us in their mesh;
+    /// 0 never pauses. Peers that graylist us prune us, so this tracks how many accept from us.
+    pub min_publish_peers: usize,
+    /// Run the validator's content-hash dedupe step.
+    pub dedupe_enabled: bool,
+    /// Score charged to forwarders of ignored messages (see `ValidatorConfig::ignore_penalty`).
+    pub ignore_penalty: f64,
+    /// Emit a `NodeEvent::Decision` per validated message. Off by default; events that don't
+    /// fit in the channel are dropped and counted rather than slowing the node down.
+    pub emit_decisions: bool,
+    pub app_score_update: AppScoreUpdate,
+    /// Domain separator for message ids; given to both the behaviour and the validator.
+    pub message_domain: String,
+    /// Periodically decay validator scores and offences; `None` never forgives.
+    pub score_decay: Option<DecayConfig>,
+    /// Honest acceptance rate (0..=1) to steer penalties toward; `None` keeps them fixed.
+    pub target_acceptance: Option<f64>,
+    /// See `ValidatorConfig::app_score_floor`.
+    pub app_score_floor: Option<f64>,
+    /// See `ValidatorConfig::bind_reputation_to_address`; each connection's remote IP is
+    /// passed to the validator.
+    pub bind_reputation_to_address: bool,
+    /// Gossipsub message cache and gossip settings.
+    pub mesh: MeshConfig,
+    /// Validate on a dedicated blocking worker instead of the swarm task. Messages are still
+    /// validated one at a time in arrival order, so per-author sequence checks are unchanged.
+    pub validation_worker: bool,
+    /// Validations allowed in flight at once; further messages are reported as `Ignore`
+    /// without being validated. `None` never sheds.
+    pub max_pending_validations: Option<usize>,
+    /// Verdicts reported to gossipsub by decision reason (e.g. `RejectReason::Oversize`), so
+    /// a reason can skip gossipsub's own invalid-delivery scoring. Counters and events keep ours.
+    pub report_as: HashMap<RejectReason, ReportAs>,
+    /// Policy for a shadow validator fed every message the primary validates, for A/B
+    /// comparison. Its decisions are tallied in `NodeSummary::shadow` and never reach
+    /// gossipsub. `None` runs no shadow.
+    pub shadow: Option<ValidatorConfig>,
+    /// The node's identity; `None` generates a fresh random one.
+    pub keypair: Option<Keypair>,
+}
+
+/// Loopback on an ephemeral port: `/ip4/127.0.0.1/tcp/0`.
+pub fn default_listen_addr() -> Multiaddr {
+    Multiaddr::empty()
+        .with(Protocol::Ip4(std::net::Ipv4Addr::LOCALHOST))
+        .with(Protocol::Tcp(0))
+}
+
+impl Default for NodeConfig {
+    fn default() -> Self {
+        Self {
+            idx: 0,
+            topic: "test-topic".to_string(),
+            max_message_bytes: 16384,
+            listen_addr: default_listen_addr(),
+            listen_port: 0,
+            authenticity: Authenticity::Signed,
+            validation_mode: None,
+            audit: None,
+            min_publish_peers: 0,
+            dedupe_enabled: true,
+            ignore_penalty: 0.0,
+            emit_decisions: false,
+            app_score_update: AppScoreUpdate::PerMessage,
+            message_domain: DEFAULT_MESSAGE_DOMAIN.to_string(),
+            score_decay: None,
+            target_acceptance: None,
+            app_score_floor: None,
+            bind_reputation_to_address: false,
+            mesh: MeshConfig::default(),
+            validation_worker: false,
+            max_pending_validations: Some(DEFAULT_MAX_PENDING_VALIDATIONS),
+            report_as: HashMap::new(),
+            shadow: None,
+            keypair: None,
+        }
+    }
+}
+
+impl NodeConfig {
+    /// The primary validator's configuration for this node, before the swarm's own peer id
+    /// is filled in.
+    pub fn validator_config(&self) -> ValidatorConfig {
+        ValidatorConfig {
+            max_message_bytes: self.max_message_bytes,
+            dedupe_enabled: self.dedupe_enabled,
+            ignore_penalty: self.ignore_penalty,
+            message_domain: self.message_domain.clone(),
+            app_score_floor: self.app_score_floor,
+            bind_reputation_to_address: self.bind_reputation_to_address,
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum NodeCommand {
     Dial { addr: Multiaddr },
     Subscribe,
+    /// Subscribe once the first connection (either direction) is up, or right away if one
+    /// already is; after `fallback` without a connection, subscribe anyway. Avoids joining
+    /// the topic before there is anyone to tell.
+    SubscribeAfterConnect { fallback: Duration },
     Publish { data: Vec<u8> },
     SetBadPeers { bad_peer_ids: Vec<libp2p::PeerId> },
+    /// Close all connections to `peer`; `NodeEvent::PeerDisconnected` follows once they're gone.
+    Disconnect { peer: libp2p::PeerId },
+    /// Snapshot the node's live tallies and peer scores without stopping it.
+    QueryState { reply: oneshot::Sender<NodeState> },
+    /// A reputation report gossiped from `reporter` (see `Validator::apply_reputation_report`).
+    ReputationReport { reporter: libp2p::PeerId, victim: libp2p::PeerId, delta: f64 },
+    /// The validator's full state (`Validator::snapshot`), e.g. for `--dump-node`.
+    DumpState { reply: oneshot::Sender<ValidatorSnapshot> },
+    /// Copy of the node's message counters so far, without stopping it.
+    GetCounters { reply: oneshot::Sender<Counters> },
+    /// Zero the counters, honest tallies and per-reason breakdown to start a new measurement
+    /// window. Peer reputation is kept.
+    ResetMetrics,
+    /// Park the validation worker until `release` fires (or is dropped), so tests can build
+    /// up a backlog. Ignored without `NodeConfig::validation_worker`.
+    #[cfg(feature = "testing")]
+    HoldValidation { release: oneshot::Receiver<()> },
     Shutdown,
 }
 
 #[derive(Debug)]
 pub enum NodeEvent {
     NewListenAddr(Multiaddr),
-    Summary(NodeSummary),
+    /// A peer we were told to disconnect has no connections left.
+    PeerDisconnected(libp2p::PeerId),
+    /// A connection we dialed is up. Dropped when the event channel is full.
+    Connected(libp2p::PeerId),
+    /// A dial failed; `peer` is known when the address named it (`/p2p/...`). Dropped
+    /// when the event channel is full.
+    DialFailed { peer: Option<libp2p::PeerId>, error: String },
+    /// A connected peer doesn't speak gossipsub, so it will never join our mesh.
+    PeerUnsupported(libp2p::PeerId),
+    /// One validation decision, when `NodeConfig::emit_decisions` is set.
+    Decision {
+        propagation_source: libp2p::PeerId,
+        author: Option<libp2p::PeerId>,
+        reason: RejectReason,
+        acceptance: gossipsub::MessageAcceptance,
+    },
+    /// Joining the topic failed; unless it was already joined, the node is out of the mesh.
+    SubscribeFailed(SubscribeError),
+    /// Gossipsub refused a publish (e.g. no peers to send it to). Dropped when the event
+    /// channel is full; `NodeSummary::publish_errors` has the exact count.
+    PublishError(gossipsub::PublishError),
+    Summary(Box<NodeSummary>),
 }
 
-#[derive(Debug, Clone)]
+/// Why `NodeCommand::Subscribe` did not join the topic.
+#[derive(Debug)]
+pub enum SubscribeError {
+    /// Already subscribed; gossipsub treats the repeat as a no-op.
+    AlreadySubscribed,
+    Gossipsub(gossipsub::SubscriptionError),
+}
+
+/// What the shadow validator (`NodeConfig::shadow`) would have decided on the same traffic.
+#[derive(Debug, Clone, Default)]
+pub struct ShadowSummary {
+    pub counters: Counters,
+    pub honest_accepted: u64,
+    pub honest_rejected: u64,
+    pub quarantined_peers: u64,
+}
+
+impl ShadowSummary {
+    pub fn record(&mut self, decision: &Decision, honest: bool) {
+        *self.counters.by_reason.entry(decision.reason.to_string()).or_default() += 1;
+        match decision.acceptance {
+            gossipsub::MessageAcceptance::Accept => {
+                self.counters.accepted += 1;
+                self.honest_accepted += u64::from(honest);
+            }
+            gossipsub::MessageAcceptance::Reject => {
+                self.counters.rejected += 1;
+                self.honest_rejected += u64::from(honest);
+            }
+            gossipsub::MessageAcceptance::Ignore => self.counters.ignored += 1,
+        }
+    }
+
+    /// Fold another node's shadow tallies into this one.
+    pub fn merge(&mut self, other: &ShadowSummary) {
+        self.counters.accepted += other.counters.accepted;
+        self.counters.rejected += other.counters.rejected;
+        self.counters.ignored += other.counters.ignored;
+        for (reason, n) in &other.counters.by_reason {
+            *self.counters.by_reason.entry(reason.clone()).or_default() += n;
+        }
+        self.honest_accepted += other.honest_accepted;
+        self.honest_rejected += other.honest_rejected;
+        self.quarantined_peers += other.quarantined_peers;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct NodeSummary {
     pub accepted: u64,
     pub rejected: u64,
@@ -40,6 +294,69 @@ pub struct NodeSummary {
     pub honest_accepted: u64,
     pub honest_rejected: u64,
     pub honest_published: u64,
+    /// Publishes dropped because too few mesh peers remained (see `min_publish_peers`).
+    pub paused_publishes: u64,
+    /// Publishes gossipsub refused (see `NodeEvent::PublishError`).
+    pub publish_errors: u64,
+    /// `NodeEvent::SubscribeFailed` events seen for this node, filled in by the sim.
+    pub subscribe_failures: u64,
+    /// `NodeEvent::PeerUnsupported` events seen for this node, filled in by the sim.
+    pub unsupported_peers: u64,
+    pub validation_failures: u64,
+    /// Messages shed unvalidated past `max_pending_validations`.
+    pub shed_validations: u64,
+    /// Messages without a `source`, attributed to their propagation source instead.
+    pub no_source_messages: u64,
+    /// Peers in our gossipsub mesh for the topic at shutdown.
+    pub mesh_peer_count: usize,
+    /// Peers we hold a connection to at shutdown, meshed or not.
+    pub connected_peer_count: usize,
+    /// Bucket upper bounds (microseconds) for the median and 99th percentile validate time.
+    pub validate_p50_us: u64,
+    pub validate_p99_us: u64,
+    /// Gossipsub's composite score (mesh time, deliveries, app score...) for each connected peer.
+    pub composite_scores: Vec<(libp2p::PeerId, f64)>,
+    /// Ids of every message this node accepted, so the sim can count distinct content.
+    pub accepted_ids: HashSet<gossipsub::MessageId>,
+    /// Ids of every message this node published, so the sim can follow each one.
+    pub published_ids: HashSet<gossipsub::MessageId>,
+    /// Decision events dropped because the event channel was full.
+    pub dropped_decision_events: u64,
+    /// `set_application_score` calls made into gossipsub.
+    pub app_score_updates: u64,
+    /// Connections from quarantined peers closed because they redialed inside their backoff.
+    pub refused_reconnects: u64,
+    /// Peers our validator holds in quarantine at shutdown.
+    pub quarantined_ids: HashSet<libp2p::PeerId>,
+    /// Validator score of every peer it tracks at shutdown.
+    pub peer_scores: Vec<(libp2p::PeerId, f64)>,
+    /// Behavioural classification of every peer the validator tracks (see `Validator::classify`).
+    pub classifications: Vec<(libp2p::PeerId, Classification)>,
+    /// Rate-limiter outcomes per forwarder (see `Validator::throttle_stats`).
+    pub throttle_stats: Vec<(libp2p::PeerId, ThrottleStats)>,
+    /// First offence to quarantine, per quarantined peer (see `Validator::time_to_quarantine`).
+    pub time_to_quarantine: Vec<(libp2p::PeerId, Duration)>,
+    /// Node start to first accepted message, per peer (see `Validator::time_to_first_accept`).
+    pub time_to_first_accept: Vec<(libp2p::PeerId, Duration)>,
+    /// Shadow validator tallies, when `NodeConfig::shadow` is set.
+    pub shadow: Option<Box<ShadowSummary>>,
+    /// Placeholder for a node that sent no summary before the collection timeout; every
+    /// other field is empty.
+    pub missing: bool,
+}
+
+/// Live view of a running node, returned by `NodeCommand::QueryState`.
+#[derive(Debug, Clone)]
+pub struct NodeState {
+    pub accepted: u64,
+    pub rejected: u64,
+    pub ignored: u64,
+    pub quarantined_peers: u64,
+    pub no_source_messages: u64,
+    pub mesh_peer_count: usize,
+    pub connected_peer_count: usize,
+    /// (peer, score, quarantined) for every peer the validator tracks.
+    pub peer_scores: Vec<(libp2p::PeerId, f64, bool)>,
 }
 
 #[derive(Clone)]
@@ -56,7 +373,10 @@ pub fn spawn_node(
     let (cmd_tx, cmd_rx) = mpsc::channel::<NodeCommand>(128);
     let (evt_tx, evt_rx) = mpsc::channel::<NodeEvent>(512);
 
-    let swarm = build_swarm(&cfg.topic)?;
+    let swarm = build_swarm(&cfg)
+        .with_context(|| {
+            format!("node {} failed to start listening on {} (port {})", cfg.idx, cfg.listen_addr, cfg.listen_port)
+        })?;
     let peer_id = *swarm.local_peer_id();
 
     tokio::spawn(async move {
@@ -74,20 +394,290 @@ pub fn spawn_node(
     ))
 }
 
-fn build_swarm(topic: &str) -> anyhow::Result<Swarm<Behaviour>> {
+/// Run a validation step so that it always yields a decision. gossipsub holds every message
+/// until `report_message_validation_result` is called, so a panicking validator must still
+/// produce a verdict; we fall back to `Ignore`. The flag is `false` when validation failed,
+/// in which case the validator may be half-updated: call `Validator::recover_from_panic`.
+pub fn validate_guarded(f: impl FnOnce() -> Decision) -> (Decision, bool) {
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+        Ok(decision) => (decision, true),
+        Err(_) => (
+            Decision {
+                acceptance: gossipsub::MessageAcceptance::Ignore,
+                reason: RejectReason::ValidationFailed,
+                score_delta: 0.0,
+            },
+            false,
+        ),
+    }
+}
+
+// the IP in a connection's remote address, if it has one (not e.g. a relayed or memory address)
+fn remote_ip(addr: &Multiaddr) -> Option<std::net::IpAddr> {
+    addr.iter().find_map(|p| match p {
+        Protocol::Ip4(ip) => Some(ip.into()),
+        Protocol::Ip6(ip) => Some(ip.into()),
+        _ => None,
+    })
+}
+
+/// A validator shared between a node's swarm task and its validation worker.
+pub type SharedValidator = Arc<Mutex<Validator>>;
+
+// A panic inside `validate` poisons the lock; the validator is still usable (see `validate_guarded`).
+fn lock(validator: &SharedValidator) -> MutexGuard<'_, Validator> {
+    validator.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A received message waiting for its decision.
+struct ValidationJob {
+    propagation_source: libp2p::PeerId,
+    source: Option<libp2p::PeerId>,
+    // `source`, or the forwarder for messages without one
+    author: libp2p::PeerId,
+    message_id: gossipsub::MessageId,
+    data: Vec<u8>,
+    span: tracing::Span,
+}
+
+/// A job with its decision, ready to be reported to gossipsub.
+struct Validated {
+    job: ValidationJob,
+    decision: Decision,
+    completed: bool,
+    // the author's and forwarder's state right after the decision
+    author: PeerView,
+    source: PeerView,
+}
+
+/// One peer's validator state as the swarm task last heard it, so the swarm task can act
+/// on scores and quarantine without taking the validator lock.
+#[derive(Debug, Clone, Copy)]
+struct PeerView {
+    score: f64,
+    quarantined: bool,
+    app_score: Option<f64>,
+}
+
+/// Fresh views of some peers after a validator operation; `None` means the validator forgot
+/// the peer.
+type PeerViews = Vec<(libp2p::PeerId, Option<PeerView>)>;
+
+fn peer_view(validator: &Validator, peer: &libp2p::PeerId) -> PeerView {
+    PeerView {
+        score: validator.get_peer_score(peer),
+        quarantined: validator.is_quarantined(peer),
+        app_score: validator.get_app_score_option(peer),
+    }
+}
+
+fn all_peer_views(validator: &Validator) -> PeerViews {
+    validator
+        .dump_peer_states()
+        .into_iter()
+        .map(|(peer, _, _)| (peer, Some(peer_view(validator, &peer))))
+        .collect()
+}
+
+fn validate_job(validator: &SharedValidator, job: ValidationJob) -> Validated {
+    let (decision, completed) = job.span.in_scope(|| {
+        validate_guarded(|| lock(validator).validate(&job.propagation_source, job.source.as_ref(), &job.data))
+    });
+    if !completed {
+        lock(validator).recover_from_panic(&job.data);
+        validator.clear_poison();
+    }
+    let v = lock(validator);
+    let (author, source) = (peer_view(&v, &job.author), peer_view(&v, &job.propagation_source));
+    Validated { job, decision, completed, author, source }
+}
+
+/// Work for the validator, done strictly in the order it was queued.
+enum ValidatorTask {
+    Validate(Box<ValidationJob>),
+    /// Any other operation; the views it returns go back to the swarm task.
+    Run(Box<dyn FnOnce(&mut Validator) -> PeerViews + Send>),
+}
+
+/// Run validator tasks on a blocking thread, one at a time in the order they were queued,
+/// and hand the results back in that same order.
+fn spawn_validation_worker(
+    validator: SharedValidator,
+    done_tx: mpsc::UnboundedSender<Validated>,
+    views_tx: mpsc::UnboundedSender<PeerViews>,
+) -> (mpsc::UnboundedSender<ValidatorTask>, tokio::task::JoinHandle<()>) {
+    let (task_tx, mut task_rx) = mpsc::unbounded_channel::<ValidatorTask>();
+    let worker = tokio::task::spawn_blocking(move || {
+        while let Some(task) = task_rx.blocking_recv() {
+            let sent = match task {
+                ValidatorTask::Validate(job) => done_tx.send(validate_job(&validator, *job)).is_ok(),
+                ValidatorTask::Run(op) => {
+                    let views = op(&mut lock(&validator));
+                    views.is_empty() || views_tx.send(views).is_ok()
+                }
+            };
+            if !sent {
+                break;
+            }
+        }
+    });
+    (task_tx, worker)
+}
+
+/// The swarm task's way to the validator. With a worker every operation is queued behind
+/// the validations already waiting, so the swarm task never blocks on the validator lock;
+/// without one it runs in place. Results come back on the `done` and `views` channels.
+struct ValidatorQueue {
+    validator: SharedValidator,
+    worker: Option<(mpsc::UnboundedSender<ValidatorTask>, tokio::task::JoinHandle<()>)>,
+    done_tx: mpsc::UnboundedSender<Validated>,
+    views_tx: mpsc::UnboundedSender<PeerViews>,
+}
+
+impl ValidatorQueue {
+    fn validate(&self, job: ValidationJob) {
+        match &self.worker {
+            Some((worker, _)) => {
+                let _ = worker.send(ValidatorTask::Validate(Box::new(job)));
+            }
+            None => {
+                let _ = self.done_tx.send(validate_job(&self.validator, job));
+            }
+        }
+    }
+
+    fn run(&self, op: impl FnOnce(&mut Validator) -> PeerViews + Send + 'static) {
+        match &self.worker {
+            Some((worker, _)) => {
+                let _ = worker.send(ValidatorTask::Run(Box::new(op)));
+            }
+            None => {
+                let views = op(&mut lock(&self.validator));
+                if !views.is_empty() {
+                    let _ = self.views_tx.send(views);
+                }
+            }
+        }
+    }
+
+    /// Close the queue and wait until the worker has done everything already on it.
+    async fn finish(self) {
+        if let Some((worker, handle)) = self.worker {
+            drop(worker);
+            let _ = handle.await;
+        }
+    }
+}
+
+// join `topic`: readiness on success, `NodeEvent::SubscribeFailed` otherwise
+async fn join_topic(
+    swarm: &mut Swarm<Behaviour>,
+    topic: &str,
+    idx: usize,
+    ready_tx: Option<&mpsc::UnboundedSender<usize>>,
+    evt_tx: &mpsc::Sender<NodeEvent>,
+) {
+    let topic_hash = gossipsub::IdentTopic::new(topic);
+    let error = match swarm.behaviour_mut().gossipsub.subscribe(&topic_hash) {
+        Ok(true) => None,
+        Ok(false) => Some(SubscribeError::AlreadySubscribed),
+        Err(e) => Some(SubscribeError::Gossipsub(e)),
+    };
+    match error {
+        // Signal ready after subscription
+        None => {
+            if let Some(tx) = ready_tx {
+                let _ = tx.send(idx);
+            }
+        }
+        Some(error) => {
+            warn!(node = idx, ?error, "subscribe failed");
+            let _ = evt_tx.send(NodeEvent::SubscribeFailed(error)).await;
+        }
+    }
+}
+
+fn mesh_peer_count(swarm: &Swarm<Behaviour>, topic: &str) -> usize {
+    let topic_hash = gossipsub::IdentTopic::new(topic).hash();
+    swarm.behaviour().gossipsub.mesh_peers(&topic_hash).count()
+}
+
+// `MessageAcceptance` isn't `Clone`, but the decision stream needs its own copy.
+fn clone_acceptance(acceptance: &gossipsub::MessageAcceptance) -> gossipsub::MessageAcceptance {
+    match acceptance {
+        gossipsub::MessageAcceptance::Accept => gossipsub::MessageAcceptance::Accept,
+        gossipsub::MessageAcceptance::Reject => gossipsub::MessageAcceptance::Reject,
+        gossipsub::MessageAcceptance::Ignore => gossipsub::MessageAcceptance::Ignore,
+    }
+}
+
+/// Refusal windows for quarantined peers that were disconnected, growing with each disconnect.
+#[derive(Default)]
+struct ReconnectBackoff {
+    // peer -> (end of the current refusal window, disconnects so far)
+    peers: HashMap<libp2p::PeerId, (Instant, u32)>,
+}
+
+impl ReconnectBackoff {
+    /// Start a new, longer window unless one is still running (closing a refused
+    /// connection is not a fresh offence).
+    fn note_disconnect(&mut self, peer: libp2p::PeerId, now: Instant) {
+        let (until, strikes) = self.peers.entry(peer).or_insert((now, 0));
+        if now >= *until {
+            let window = RECONNECT_BACKOFF_BASE.saturating_mul(1 << (*strikes).min(16));
+            *until = now + window.min(RECONNECT_BACKOFF_MAX);
+            *strikes += 1;
+        }
+    }
+
+    fn refuses(&self, peer: &libp2p::PeerId, now: Instant) -> bool {
+        self.peers.get(peer).is_some_and(|(until, _)| now < *until)
+    }
+
+    /// Forget peers whose last window ended long enough ago that they start over.
+    fn prune(&mut self, now: Instant) {
+        self.peers.retain(|_, (until, _)| now < *until + RECONNECT_BACKOFF_MAX);
+    }
+}
+
+/// Gossipsub's own score for every connected peer, where scoring has one.
+fn composite_scores(swarm: &Swarm<Behaviour>) -> Vec<(libp2p::PeerId, f64)> {
+    let gossipsub = &swarm.behaviour().gossipsub;
+    swarm
+        .connected_peers()
+        .filter_map(|peer| gossipsub.peer_score(peer).map(|score| (*peer, score)))
+        .collect()
+}
+
+fn build_swarm(cfg: &NodeConfig) -> anyhow::Result<Swarm<Behaviour>> {
     // SwarmBuilder + TCP + Noise + Yamux (common baseline).
-    let mut swarm = SwarmBuilder::with_new_identity()
+    let key = cfg.keypair.clone().unwrap_or_else(Keypair::generate_ed25519);
+    let mut swarm = SwarmBuilder::with_existing_identity(key)
         .with_tokio()
         .with_tcp(
             libp2p::tcp::Config::new(),
             libp2p::noise::Config::new,
             libp2p::yamux::Config::default,
         )?
-        .with_behaviour(|key| Behaviour::new(key.clone(), topic))?
+        .with_behaviour(|key| {
+            let mode = cfg.validation_mode.unwrap_or_else(|| cfg.authenticity.default_validation_mode());
+            Behaviour::new(key.clone(), &cfg.topic, cfg.authenticity, mode, &cfg.message_domain, &cfg.mesh)
+                .map_err(Into::into)
+        })?
         .build();
 
-    // Listen on an ephemeral localhost TCP port so we receive NewListenAddr events.
-    let listen_addr: Multiaddr = "/ip4/127.0.0.1/tcp/0".parse()?;
+    // Listen so we receive NewListenAddr events. Port 0 means ephemeral.
+    if !cfg.listen_addr.iter().any(|p| matches!(p, Protocol::Tcp(_))) {
+        anyhow::bail!("listen address {} has no TCP port", cfg.listen_addr);
+    }
+    let listen_addr: Multiaddr = cfg
+        .listen_addr
+        .iter()
+        .map(|p| match p {
+            Protocol::Tcp(_) if cfg.listen_port != 0 => Protocol::Tcp(cfg.listen_port),
+            p => p,
+        })
+        .collect();
     swarm.listen_on(listen_addr)?;
 
     Ok(swarm)
@@ -98,131 +688,474 @@ async fn run_node(
     mut swarm: Swarm<Behaviour>,
     mut cmd_rx: mpsc::Receiver<NodeCommand>,
     evt_tx: mpsc::Sender<NodeEvent>,
-    mut bad_peer_ids: Vec<libp2p::PeerId>,
+    bad_peer_ids: Vec<libp2p::PeerId>,
     ready_tx: Option<mpsc::UnboundedSender<usize>>,
 ) -> anyhow::Result<()> {
     let topic = cfg.topic.clone();
-    let mut validator = Validator::new(ValidatorConfig {
-        max_message_bytes: cfg.max_message_bytes,
+    // checked on every message, so keep membership O(1) however many bad peers there are
+    let mut bad_peer_ids: HashSet<libp2p::PeerId> = bad_peer_ids.into_iter().collect();
+    let validator: SharedValidator = Arc::new(Mutex::new(Validator::new(ValidatorConfig {
+        local_peer_id: Some(*swarm.local_peer_id()),
+        ..cfg.validator_config()
+    })));
+    // decisions and peer views come back here, from the worker or straight from the swarm task
+    let (done_tx, mut done_rx) = mpsc::unbounded_channel::<Validated>();
+    let (views_tx, mut views_rx) = mpsc::unbounded_channel::<PeerViews>();
+    let queue = ValidatorQueue {
+        worker: cfg
+            .validation_worker
+            .then(|| spawn_validation_worker(validator.clone(), done_tx.clone(), views_tx.clone())),
+        validator: validator.clone(),
+        done_tx,
+        views_tx,
+    };
+    // jobs handed out but not yet back on done_rx
+    let mut in_flight = 0usize;
+    // what the validator last told us about each peer
+    let mut peer_views: HashMap<libp2p::PeerId, PeerView> = HashMap::new();
+    // set by Shutdown: take no new work, report what is queued, then send the summary
+    let mut shutting_down = false;
+    let mut shadow = cfg.shadow.clone().map(|shadow| {
+        let v = Validator::new(ValidatorConfig {
+            message_domain: cfg.message_domain.clone(),
+            local_peer_id: Some(*swarm.local_peer_id()),
+            ..shadow
+        });
+        (v, ShadowSummary::default())
     });
     let mut counters = Counters::default();
     let mut honest_accepted = 0u64;
     let mut honest_rejected = 0u64;
     let mut honest_published = 0u64;
+    let mut paused_publishes = 0u64;
+    let mut publish_errors = 0u64;
+    let mut publishing_paused = false;
+    let mut warned_no_source = false;
+    let mut pending_disconnects: HashSet<libp2p::PeerId> = HashSet::new();
+    let mut accepted_ids: HashSet<gossipsub::MessageId> = HashSet::new();
+    let mut published_ids: HashSet<gossipsub::MessageId> = HashSet::new();
+    let mut dropped_decision_events = 0u64;
+    let mut app_score_updates = 0u64;
+    let mut pending_app_scores: HashMap<libp2p::PeerId, f64> = HashMap::new();
+    let mut controller = cfg
+        .target_acceptance
+        .map(|target| AcceptanceController::new(target, lock(&validator).config().penalties.clone()));
+    let mut control_tick = interval_at(Instant::now() + PENALTY_CONTROL_INTERVAL, PENALTY_CONTROL_INTERVAL);
+    // honest tallies at the last control step
+    let mut controlled_at = (0u64, 0u64);
+    let mut reconnect_backoff = ReconnectBackoff::default();
+    let mut refused_reconnects = 0u64;
 
     info!(node = cfg.idx, peer=%swarm.local_peer_id(), "node started");
 
+    let mut prune_tick = interval_at(Instant::now() + PRUNE_INTERVAL, PRUNE_INTERVAL);
+    let mut score_tick = interval_at(Instant::now() + SCORE_OBSERVE_INTERVAL, SCORE_OBSERVE_INTERVAL);
+    let mut flush_tick = interval_at(Instant::now() + APP_SCORE_FLUSH_INTERVAL, APP_SCORE_FLUSH_INTERVAL);
+    let decay_every = cfg.score_decay.as_ref().map_or(PRUNE_INTERVAL, |d| d.interval.max(Duration::from_millis(10)));
+    let mut decay_tick = interval_at(Instant::now() + decay_every, decay_every);
+    // set while a `SubscribeAfterConnect` waits for its first connection
+    let mut subscribe_deadline: Option<Instant> = None;
+
     loop {
+        if shutting_down && in_flight == 0 {
+            break;
+        }
         tokio::select! {
-            cmd = cmd_rx.recv() => {
+            // finished validations go first, so none is left unreported when Shutdown arrives
+            biased;
+            Some(Validated { job, decision, completed, author: author_view, source: source_view }) = done_rx.recv() => {
+                in_flight = in_flight.saturating_sub(1);
+                peer_views.insert(job.author, author_view);
+                peer_views.insert(job.propagation_source, source_view);
+                let ValidationJob { propagation_source, source, author, message_id, data, span } = job;
+                span.record("reason", decision.reason.as_str());
+                span.record("acceptance", acceptance_str(&decision.acceptance));
+                if !completed {
+                    counters.validation_failures += 1;
+                    warn!(node = cfg.idx, peer = %propagation_source, %message_id, "validation did not complete; reporting Ignore");
+                }
+
+                // Classify honesty by *author* (not by forwarder)
+                let is_honest_peer = !bad_peer_ids.contains(&author);
+                if let Some((shadow, tally)) = shadow.as_mut() {
+                    tally.record(&shadow.validate(&propagation_source, source.as_ref(), &data), is_honest_peer);
+                }
+
+                *counters.by_reason.entry(decision.reason.to_string()).or_default() += 1;
+                match decision.acceptance {
+                    gossipsub::MessageAcceptance::Accept => {
+                        counters.accepted += 1;
+                        accepted_ids.insert(message_id.clone());
+                        if is_honest_peer {
+                            honest_accepted += 1;
+                        }
+                        debug!(node = cfg.idx, peer = %propagation_source, reason = %decision.reason, "message accepted");
+                    },
+                    gossipsub::MessageAcceptance::Reject => {
+                        counters.rejected += 1;
+                        if is_honest_peer {
+                            honest_rejected += 1;
+                        }
+                        debug!(node = cfg.idx, peer = %propagation_source, reason = %decision.reason, "message rejected");
+                    },
+                    gossipsub::MessageAcceptance::Ignore => {
+                        counters.ignored += 1;
+                        debug!(node = cfg.idx, peer = %propagation_source, reason = %decision.reason, "message ignored");
+                    },
+                }
+
+                if let Some(audit) = &cfg.audit {
+                    audit.record(AuditRecord::new(
+                        cfg.idx,
+                        &propagation_source,
+                        source.as_ref(),
+                        &decision,
+                        author_view.score,
+                        source_view.score,
+                    )).await;
+                }
+
+                if cfg.emit_decisions {
+                    let event = NodeEvent::Decision {
+                        propagation_source,
+                        author: source,
+                        reason: decision.reason,
+                        acceptance: clone_acceptance(&decision.acceptance),
+                    };
+                    if evt_tx.try_send(event).is_err() {
+                        dropped_decision_events += 1;
+                    }
+                }
+
+                // report to gossipsub (important)
+                swarm.behaviour_mut().gossipsub.report_message_validation_result(
+                    &message_id,
+                    &propagation_source,
+                    reported_acceptance(&decision, &cfg.report_as),
+                );
+
+                // update libp2p app score from validator (if validator exposes get_app_score)
+                if let Some(new_score) = author_view.app_score {
+                    match cfg.app_score_update {
+                        AppScoreUpdate::PerMessage => {
+                            swarm.behaviour_mut().gossipsub.set_application_score(&author, new_score);
+                            app_score_updates += 1;
+                        },
+                        AppScoreUpdate::Batched => {
+                            pending_app_scores.insert(author, new_score);
+                        },
+                    }
+                }
+            },
+            Some(views) = views_rx.recv() => {
+                for (peer, view) in views {
+                    let Some(view) = view else {
+                        peer_views.remove(&peer);
+                        pending_app_scores.remove(&peer);
+                        continue;
+                    };
+                    peer_views.insert(peer, view);
+                    if let Some(new_score) = view.app_score {
+                        match cfg.app_score_update {
+                            AppScoreUpdate::PerMessage => {
+                                swarm.behaviour_mut().gossipsub.set_application_score(&peer, new_score);
+                                app_score_updates += 1;
+                            },
+                            AppScoreUpdate::Batched => {
+                                pending_app_scores.insert(peer, new_score);
+                            },
+                        }
+                    }
+                }
+            },
+            _ = prune_tick.tick(), if !shutting_down => {
+                let idx = cfg.idx;
+                queue.run(move |validator| {
+                    let before: Vec<libp2p::PeerId> = validator.dump_peer_states().into_iter().map(|(p, _, _)| p).collect();
+                    if validator.prune_idle(PEER_IDLE_TIMEOUT) == 0 {
+                        return Vec::new();
+                    }
+                    let kept: HashSet<libp2p::PeerId> = validator.dump_peer_states().into_iter().map(|(p, _, _)| p).collect();
+                    let forgotten: PeerViews = before.into_iter().filter(|p| !kept.contains(p)).map(|p| (p, None)).collect();
+                    debug!(node = idx, pruned = forgotten.len(), "pruned idle peers");
+                    forgotten
+                });
+                if let Some((shadow, _)) = shadow.as_mut() {
+                    shadow.prune_idle(PEER_IDLE_TIMEOUT);
+                }
+                reconnect_backoff.prune(Instant::now());
+            },
+            _ = flush_tick.tick(), if !pending_app_scores.is_empty() && !shutting_down => {
+                for (peer, score) in pending_app_scores.drain() {
+                    swarm.behaviour_mut().gossipsub.set_application_score(&peer, score);
+                    app_score_updates += 1;
+                }
+            },
+            _ = tokio::time::sleep_until(subscribe_deadline.unwrap_or_else(Instant::now)), if subscribe_deadline.is_some() && !shutting_down => {
+                subscribe_deadline = None;
+                warn!(node = cfg.idx, "no connection before the subscribe fallback; subscribing anyway");
+                join_topic(&mut swarm, &topic, cfg.idx, ready_tx.as_ref(), &evt_tx).await;
+            },
+            _ = decay_tick.tick(), if cfg.score_decay.is_some() && !shutting_down => {
+                if let Some(decay) = &cfg.score_decay {
+                    let factor = decay.score_factor;
+                    // the fresh app scores come back through `views_rx`, like any other update
+                    queue.run(move |validator| {
+                        validator.decay_scores(factor);
+                        validator.decay_offences();
+                        all_peer_views(validator)
+                    });
+                    if let Some((shadow, _)) = shadow.as_mut() {
+                        shadow.decay_scores(factor);
+                        shadow.decay_offences();
+                    }
+                }
+            },
+            _ = control_tick.tick(), if controller.is_some() && !shutting_down => {
+                let Some(controller) = controller.as_mut() else { continue };
+                let (accepted, rejected) = (honest_accepted - controlled_at.0, honest_rejected - controlled_at.1);
+                controlled_at = (honest_accepted, honest_rejected);
+                if let Some(penalties) = controller.step(accepted, rejected) {
+                    debug!(node = cfg.idx, scale = controller.scale(), accepted, rejected, "re-tuned penalties");
+                    queue.run(move |validator| {
+                        let retuned = ValidatorConfig { penalties, ..validator.config().clone() };
+                        validator.update_config(retuned);
+                        all_peer_views(validator)
+                    });
+                }
+            },
+            _ = score_tick.tick(), if !shutting_down => {
+                for (peer, composite) in composite_scores(&swarm) {
+                    let view = peer_views.get(&peer);
+                    let app = view.map_or(0.0, |v| v.score);
+                    let quarantined = view.is_some_and(|v| v.quarantined);
+                    debug!(node = cfg.idx, %peer, composite, app, quarantined, "peer scores");
+                    if composite < GRAYLIST_THRESHOLD && !quarantined {
+                        warn!(node = cfg.idx, %peer, composite, app, "gossipsub graylists a peer our validator considers healthy");
+                    } else if quarantined && composite >= 0.0 {
+                        warn!(node = cfg.idx, %peer, composite, app, "gossipsub trusts a peer our validator quarantined");
+                    }
+                }
+            },
+            cmd = cmd_rx.recv(), if !shutting_down => {
                 match cmd {
                     Some(NodeCommand::Dial { addr }) => {
                         swarm.dial(addr)?;
                     },
                     Some(NodeCommand::Subscribe) => {
-                        let topic_hash = gossipsub::IdentTopic::new(&topic);
-                        let _ = swarm.behaviour_mut().gossipsub.subscribe(&topic_hash)?;
-                        
-                        // Signal ready after subscription
-                        if let Some(tx) = &ready_tx {
-                            let _ = tx.send(cfg.idx);
+                        join_topic(&mut swarm, &topic, cfg.idx, ready_tx.as_ref(), &evt_tx).await;
+                    },
+                    Some(NodeCommand::SubscribeAfterConnect { fallback }) => {
+                        if swarm.connected_peers().next().is_some() {
+                            join_topic(&mut swarm, &topic, cfg.idx, ready_tx.as_ref(), &evt_tx).await;
+                        } else {
+                            subscribe_deadline = Some(Instant::now() + fallback);
                         }
                     },
                     Some(NodeCommand::Publish { data }) => {
                         let topic_hash = gossipsub::IdentTopic::new(&topic);
-                        
-                        // Count honest published messages
-                        let local_peer = *swarm.local_peer_id();
-                        if !bad_peer_ids.contains(&local_peer) {
-                            honest_published += 1;
+
+                        // Back off while the network no longer takes our messages
+                        let mesh_peers = mesh_peer_count(&swarm, &topic);
+                        let should_pause = mesh_peers < cfg.min_publish_peers;
+                        if should_pause != publishing_paused {
+                            publishing_paused = should_pause;
+                            if should_pause {
+                                warn!(node = cfg.idx, mesh_peers, "pausing publishing, too few mesh peers accept us");
+                            } else {
+                                info!(node = cfg.idx, mesh_peers, "resuming publishing");
+                            }
+                        }
+                        if publishing_paused {
+                            paused_publishes += 1;
+                        } else {
+                            // Count honest published messages
+                            let local_peer = *swarm.local_peer_id();
+                            if !bad_peer_ids.contains(&local_peer) {
+                                honest_published += 1;
+                            }
+                            match swarm.behaviour_mut().gossipsub.publish(topic_hash, data) {
+                                Ok(id) => {
+                                    published_ids.insert(id);
+                                },
+                                Err(error) => {
+                                    publish_errors += 1;
+                                    debug!(node = cfg.idx, ?error, "publish failed");
+                                    let _ = evt_tx.try_send(NodeEvent::PublishError(error));
+                                },
+                            }
                         }
-                        let _ = swarm.behaviour_mut().gossipsub.publish(topic_hash, data);
                     },
                     Some(NodeCommand::SetBadPeers { bad_peer_ids: new_bad_peers }) => {
-                        bad_peer_ids = new_bad_peers;
+                        bad_peer_ids = new_bad_peers.into_iter().collect();
                         info!(node = cfg.idx, ?bad_peer_ids, "updated bad peer list");
                     },
-                    Some(NodeCommand::Shutdown) | None => {
-                        for (peer, score, quarantined) in validator.dump_peer_states() {
-                            tracing::info!(node = cfg.idx, peer = %peer, score = score, quarantined = quarantined, "peer-state");
-                        }
-
-                        let quarantined = validator.get_quarantined_count() as u64;
-                        let avg_score = if counters.accepted + counters.rejected > 0 {
-                            (counters.accepted as f64 * 0.1 - counters.rejected as f64 * 3.0) /
-                            (counters.accepted + counters.rejected) as f64
+                    Some(NodeCommand::Disconnect { peer }) => {
+                        if swarm.disconnect_peer_id(peer).is_ok() {
+                            pending_disconnects.insert(peer);
                         } else {
-                            0.0
-                        };
-
-                        let summary = NodeSummary {
+                            warn!(node = cfg.idx, %peer, "disconnect requested for a peer we are not connected to");
+                        }
+                    },
+                    Some(NodeCommand::GetCounters { reply }) => {
+                        let _ = reply.send(counters.clone());
+                    },
+                    Some(NodeCommand::ResetMetrics) => {
+                        counters = Counters::default();
+                        honest_accepted = 0;
+                        honest_rejected = 0;
+                        honest_published = 0;
+                        controlled_at = (0, 0);
+                        queue.run(|validator| {
+                            validator.reset_decision_counts();
+                            Vec::new()
+                        });
+                        if let Some((shadow, tally)) = shadow.as_mut() {
+                            shadow.reset_decision_counts();
+                            *tally = ShadowSummary::default();
+                        }
+                        info!(node = cfg.idx, "metrics reset");
+                    },
+                    Some(NodeCommand::ReputationReport { reporter, victim, delta }) => {
+                        queue.run(move |validator| {
+                            validator.apply_reputation_report(&reporter, &victim, delta);
+                            vec![(victim, Some(peer_view(validator, &victim)))]
+                        });
+                    },
+                    Some(NodeCommand::DumpState { reply }) => {
+                        queue.run(move |validator| {
+                            let _ = reply.send(validator.snapshot());
+                            Vec::new()
+                        });
+                    },
+                    Some(NodeCommand::QueryState { reply }) => {
+                        let mut state = NodeState {
                             accepted: counters.accepted,
                             rejected: counters.rejected,
                             ignored: counters.ignored,
-                            quarantined_peers: quarantined,
-                            avg_peer_score: avg_score,
-                            honest_accepted,
-                            honest_rejected,
-                            honest_published,
+                            quarantined_peers: 0,
+                            no_source_messages: counters.no_source_messages,
+                            mesh_peer_count: mesh_peer_count(&swarm, &topic),
+                            connected_peer_count: swarm.connected_peers().count(),
+                            peer_scores: Vec::new(),
                         };
-
-                        let _ = evt_tx.send(NodeEvent::Summary(summary)).await;
-                        break;
+                        queue.run(move |validator| {
+                            state.quarantined_peers = validator.get_quarantined_count() as u64;
+                            state.peer_scores = validator.dump_peer_states();
+                            let _ = reply.send(state);
+                            Vec::new()
+                        });
+                    },
+                    #[cfg(feature = "testing")]
+                    Some(NodeCommand::HoldValidation { release }) => {
+                        if cfg.validation_worker {
+                            queue.run(move |_| {
+                                let _ = release.blocking_recv();
+                                Vec::new()
+                            });
+                        }
+                    },
+                    Some(NodeCommand::Shutdown) | None => {
+                        shutting_down = true;
                     },
                 }
             },
-            event = swarm.select_next_some() => {
+            event = swarm.select_next_some(), if !shutting_down => {
                 match event {
                     SwarmEvent::NewListenAddr { address, .. } => {
                         let _ = evt_tx.send(NodeEvent::NewListenAddr(address)).await;
                     }
+                    SwarmEvent::ConnectionEstablished { peer_id, .. }
+                        if peer_views.get(&peer_id).is_some_and(|v| v.quarantined)
+                            && reconnect_backoff.refuses(&peer_id, Instant::now()) =>
+                    {
+                        info!(node = cfg.idx, peer = %peer_id, "refusing reconnect from quarantined peer during backoff");
+                        refused_reconnects += 1;
+                        let _ = swarm.disconnect_peer_id(peer_id);
+                    }
+                    SwarmEvent::ConnectionEstablished { peer_id, endpoint, .. } => {
+                        if let Some(ip) = remote_ip(endpoint.get_remote_address()) {
+                            queue.run(move |validator| {
+                                validator.note_peer_address(&peer_id, ip);
+                                vec![(peer_id, Some(peer_view(validator, &peer_id)))]
+                            });
+                        }
+                        if subscribe_deadline.take().is_some() {
+                            debug!(node = cfg.idx, peer = %peer_id, "first connection up, subscribing");
+                            join_topic(&mut swarm, &topic, cfg.idx, ready_tx.as_ref(), &evt_tx).await;
+                        }
+                        if endpoint.is_dialer() {
+                            let _ = evt_tx.try_send(NodeEvent::Connected(peer_id));
+                        }
+                    }
+                    SwarmEvent::OutgoingConnectionError { peer_id, error, .. } => {
+                        debug!(node = cfg.idx, peer = ?peer_id, %error, "dial failed");
+                        let _ = evt_tx.try_send(NodeEvent::DialFailed { peer: peer_id, error: error.to_string() });
+                    }
+                    SwarmEvent::ConnectionClosed { peer_id, num_established: 0, .. } => {
+                        if peer_views.get(&peer_id).is_some_and(|v| v.quarantined) {
+                            reconnect_backoff.note_disconnect(peer_id, Instant::now());
+                        }
+                        if pending_disconnects.remove(&peer_id) {
+                            info!(node = cfg.idx, peer = %peer_id, "peer disconnected on request");
+                            let _ = evt_tx.send(NodeEvent::PeerDisconnected(peer_id)).await;
+                        }
+                    }
+                    SwarmEvent::Behaviour(BehaviourEvent::Gossipsub(gossipsub::Event::GossipsubNotSupported { peer_id })) => {
+                        warn!(node = cfg.idx, peer = %peer_id, "peer does not support gossipsub");
+                        let _ = evt_tx.send(NodeEvent::PeerUnsupported(peer_id)).await;
+                    }
                     SwarmEvent::Behaviour(BehaviourEvent::Gossipsub(gossipsub::Event::Message {
                         propagation_source,
                         message_id,
                         message,
                     })) => {
-                        let author_opt: Option<&libp2p::PeerId> = message.source.as_ref();
-                        let decision = validator.validate(&propagation_source, author_opt, &message.data);
-                        
-                        // Determine message author (publisher). If absent, fall back to propagation source.
-                        let author = message.source.clone().unwrap_or_else(|| propagation_source.clone());
-                        // Classify honesty by *author* (not by forwarder)
-                        let is_honest_peer = !bad_peer_ids.contains(&author);
-
-                        match decision.acceptance {
-                            gossipsub::MessageAcceptance::Accept => {
-                                counters.accepted += 1;
-                                if is_honest_peer {
-                                    honest_accepted += 1;
-                                }
-                                debug!(node = cfg.idx, peer = %propagation_source, reason = decision.reason, "message accepted");
-                            },
-                            gossipsub::MessageAcceptance::Reject => {
-                                counters.rejected += 1;
-                                if is_honest_peer {
-                                    honest_rejected += 1;
+                        // Unsigned messages carry no author; everything downstream (scoring and
+                        // honesty) then attributes them to the forwarder instead.
+                        let author = match message.source {
+                            Some(source) => source,
+                            None => {
+                                counters.no_source_messages += 1;
+                                if !warned_no_source {
+                                    warned_no_source = true;
+                                    warn!(node = cfg.idx, peer = %propagation_source, "message without source; attributing to propagation source");
                                 }
-                                debug!(node = cfg.idx, peer = %propagation_source, reason = decision.reason, "message rejected");
-                            },
-                            gossipsub::MessageAcceptance::Ignore => {
-                                counters.ignored += 1;
-                                debug!(node = cfg.idx, peer = %propagation_source, reason = decision.reason, "message ignored");
-                            },
+                                propagation_source
+                            }
+                        };
+                        if cfg.max_pending_validations.is_some_and(|cap| in_flight >= cap) {
+                            counters.ignored += 1;
+                            counters.shed_validations += 1;
+                            debug!(node = cfg.idx, peer = %propagation_source, %message_id, in_flight, "validation backlog full; shedding message");
+                            swarm.behaviour_mut().gossipsub.report_message_validation_result(
+                                &message_id,
+                                &propagation_source,
+                                gossipsub::MessageAcceptance::Ignore,
+                            );
+                            continue;
                         }
-
-                        // report to gossipsub (important)
-                        swarm.behaviour_mut().gossipsub.report_message_validation_result(
-                            &message_id,
-                            &propagation_source,
-                            decision.acceptance,
+                        #[cfg(feature = "spans")]
+                        let span = tracing::info_span!(
+                            "handle_message",
+                            node = cfg.idx,
+                            peer = %propagation_source,
+                            author = %author,
+                            %message_id,
+                            reason = tracing::field::Empty,
+                            acceptance = tracing::field::Empty,
                         );
-
-                        // update libp2p app score from validator (if validator exposes get_app_score)
-                        if let Some(new_score) = validator.get_app_score_option(author_opt.unwrap_or(&propagation_source)) {
-                            // set_application_score expects owned PeerId
-                            let target = author_opt.cloned().unwrap_or_else(|| propagation_source.clone());
-                            swarm.behaviour_mut().gossipsub.set_application_score(&target, new_score);
-                        }
+                        #[cfg(not(feature = "spans"))]
+                        let span = tracing::Span::none();
+                        let job = ValidationJob {
+                            propagation_source,
+                            source: message.source,
+                            author,
+                            message_id,
+                            data: message.data,
+                            span,
+                        };
+                        in_flight += 1;
+                        queue.validate(job);
                     }
 
                     _ => { /* ignore other events */ }
@@ -231,5 +1164,81 @@ async fn run_node(
         }
     }
 
+    // every validation is reported; let the worker finish whatever else is queued, then
+    // pick up the app scores it produced before taking the lock for the summary
+    queue.finish().await;
+    while let Ok(views) = views_rx.try_recv() {
+        for (peer, view) in views {
+            if let Some(score) = view.and_then(|v| v.app_score) {
+                pending_app_scores.insert(peer, score);
+            }
+        }
+    }
+    let summary = {
+        let validator = lock(&validator);
+        for (peer, score) in pending_app_scores.drain() {
+            swarm.behaviour_mut().gossipsub.set_application_score(&peer, score);
+            app_score_updates += 1;
+        }
+        let composite = composite_scores(&swarm);
+        for (peer, score, quarantined) in validator.dump_peer_states() {
+            let composite = composite.iter().find(|(p, _)| *p == peer).map(|(_, c)| *c);
+            tracing::info!(node = cfg.idx, peer = %peer, score = score, ?composite, quarantined = quarantined, "peer-state");
+        }
+
+        let quarantined = validator.get_quarantined_count() as u64;
+        let avg_score = if counters.accepted + counters.rejected > 0 {
+            (counters.accepted as f64 * 0.1 - counters.rejected as f64 * 3.0) /
+            (counters.accepted + counters.rejected) as f64
+        } else {
+            0.0
+        };
+
+        NodeSummary {
+            accepted: counters.accepted,
+            rejected: counters.rejected,
+            ignored: counters.ignored,
+            quarantined_peers: quarantined,
+            avg_peer_score: avg_score,
+            honest_accepted,
+            honest_rejected,
+            honest_published,
+            paused_publishes,
+            publish_errors,
+            subscribe_failures: 0,
+            unsupported_peers: 0,
+            validation_failures: counters.validation_failures,
+            shed_validations: counters.shed_validations,
+            no_source_messages: counters.no_source_messages,
+            mesh_peer_count: mesh_peer_count(&swarm, &topic),
+            connected_peer_count: swarm.connected_peers().count(),
+            validate_p50_us: validator.latency_histogram().percentile_us(0.5),
+            validate_p99_us: validator.latency_histogram().percentile_us(0.99),
+            composite_scores: composite,
+            accepted_ids: std::mem::take(&mut accepted_ids),
+            published_ids: std::mem::take(&mut published_ids),
+            dropped_decision_events,
+            app_score_updates,
+            refused_reconnects,
+            quarantined_ids: validator
+                .dump_peer_states()
+                .into_iter()
+                .filter(|(_, _, quarantined)| *quarantined)
+                .map(|(peer, _, _)| peer)
+                .collect(),
+            peer_scores: validator.dump_peer_states().into_iter().map(|(peer, score, _)| (peer, score)).collect(),
+            classifications: validator.classify_all(),
+            throttle_stats: validator.throttle_stats_all(),
+            time_to_quarantine: validator.time_to_quarantine_all(),
+            time_to_first_accept: validator.time_to_first_accept_all(),
+            shadow: shadow.take().map(|(shadow, mut tally)| {
+                tally.quarantined_peers = shadow.get_quarantined_count() as u64;
+                Box::new(tally)
+            }),
+            missing: false,
+        }
+    };
+    let _ = evt_tx.send(NodeEvent::Summary(Box::new(summary))).await;
+
     Ok(())
 }
@@ -1,52 +1,761 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use anyhow::Context;
+use libp2p::identity::Keypair;
 use rand::rngs::StdRng;
+use rand::seq::index::sample;
 use rand::{Rng, SeedableRng};
-use tokio::time::{interval, Duration};
-use tokio::sync::mpsc;
-use tracing::info;
+use sha2::{Digest, Sha256};
+use tokio::time::{interval, interval_at, timeout, timeout_at, Duration, Instant};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{info, warn};
 
+use crate::audit::AuditLog;
 use crate::cli::Cli;
+use crate::behaviour::{message_id, MeshConfig};
 use crate::codec::{encode, WireMessage};
-use crate::p2p::{spawn_node, NodeCommand, NodeConfig, NodeEvent, NodeHandle};
+use crate::p2p::{spawn_node, NodeCommand, NodeConfig, NodeEvent, NodeHandle, NodeSummary, ShadowSummary};
+use crate::validator::{Classification, DecayConfig, PeerStatus, RejectReason, ThrottleStats, Validator, ValidatorConfig, ValidatorSnapshot};
+
+// How often the recovery scenario samples honest nodes' view of the attackers.
+const RECOVERY_SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+// How often honest nodes' views of the bad peers are compared, and reputation gossiped.
+const REPUTATION_ROUND_INTERVAL: Duration = Duration::from_secs(1);
+// how long each node gets to send its summary after Shutdown
+const SUMMARY_TIMEOUT: Duration = Duration::from_secs(5);
+// how long one batch of `--dial-concurrency` dials may take to connect
+const DIAL_TIMEOUT: Duration = Duration::from_secs(10);
+// a stealthy attacker's publish rate as a fraction of the default rate limit, and its size
+const STEALTH_RATE_FRACTION: f64 = 0.8;
+const STEALTH_PAYLOAD_BYTES: usize = 32;
+// mixed into each node's seed for its `--drop-rate` draws
+const DROP_RNG_SALT: u64 = 0xd509_1055;
+
+/// How the simulation picks which nodes are malicious.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum BadSelection {
+    /// The first `--bad-peers` nodes (including the bootstrap).
+    #[default]
+    Prefix,
+    /// A seeded random sample across all nodes.
+    Random,
+}
+
+/// What the bad peers do over the run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Scenario {
+    /// Spam for the whole run.
+    #[default]
+    Steady,
+    /// Spam for the first half, then go silent; the report shows how scores recover.
+    Recovery,
+    /// Spam until quarantine is likely (`--adapt-after-secs`), then switch to stealthy
+    /// well-formed traffic just under the rate limit (see `AttackerState`).
+    Adaptive,
+}
+
+/// Phase of an adaptive attacker (`Scenario::Adaptive`) in its publisher task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttackerState {
+    /// The full mix of invalid messages at `--spam-per-sec`.
+    Spamming,
+    /// Reckons it has been quarantined: small well-formed messages a little under the
+    /// default sustained rate, which pass every content check.
+    Stealth,
+}
+
+impl AttackerState {
+    /// State after attacking for `elapsed`; the switch to stealth happens at `adapt_after`.
+    pub fn step(self, elapsed: Duration, adapt_after: Duration) -> Self {
+        match self {
+            AttackerState::Spamming if elapsed >= adapt_after => AttackerState::Stealth,
+            state => state,
+        }
+    }
+
+    /// Gap between publishes in this state.
+    pub fn interval(self, spam_per_sec: u32) -> Duration {
+        match self {
+            AttackerState::Spamming => Duration::from_secs_f64(1.0 / spam_per_sec.max(1) as f64),
+            AttackerState::Stealth => {
+                Duration::from_secs_f64(1.0 / (ValidatorConfig::default().sustained_rate * STEALTH_RATE_FRACTION))
+            }
+        }
+    }
+
+    pub fn message(self, rng: &mut StdRng, node: usize, seq: u64, max_bytes: usize) -> Vec<u8> {
+        match self {
+            AttackerState::Spamming => traffic_message(rng, node, seq, true, max_bytes),
+            AttackerState::Stealth => self.attack(rng, seq, max_bytes).1,
+        }
+    }
+
+    /// Like `message`, tagged with the kind of attack it is.
+    pub fn attack(self, rng: &mut StdRng, seq: u64, max_bytes: usize) -> (AttackType, Vec<u8>) {
+        match self {
+            AttackerState::Spamming => attack_message(rng, max_bytes),
+            AttackerState::Stealth => {
+                let mut payload = vec![0u8; STEALTH_PAYLOAD_BYTES];
+                rng.fill(&mut payload[..]);
+                (AttackType::Stealth, encode(&WireMessage::Good { seq, payload }))
+            }
+        }
+    }
+}
+
+/// Kind of message a bad peer publishes, so the report can show which check caught each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AttackType {
+    /// Random bytes that don't decode.
+    Junk,
+    /// A well-formed message over `--max-message-bytes`.
+    Oversize,
+    /// A well-formed message with no payload.
+    EmptyPayload,
+    /// The malicious marker.
+    Malicious,
+    /// `AttackerState::Stealth` traffic, which passes every content check.
+    Stealth,
+}
+
+impl AttackType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AttackType::Junk => "junk",
+            AttackType::Oversize => "oversize",
+            AttackType::EmptyPayload => "empty_payload",
+            AttackType::Malicious => "malicious",
+            AttackType::Stealth => "stealth",
+        }
+    }
+
+    /// Reasons a fresh validator can give for this attack, before rate limiting, dedupe or
+    /// quarantine get in first. Junk fails whichever codec check its random bytes trip, and
+    /// junk that happens to lead with the compressed version byte can expand past the limit.
+    pub fn expected_reasons(self) -> &'static [RejectReason] {
+        match self {
+            AttackType::Junk => &[
+                RejectReason::DecodeError,
+                RejectReason::Truncated,
+                RejectReason::TooLarge,
+                RejectReason::Oversize,
+            ],
+            AttackType::Oversize => &[RejectReason::Oversize],
+            AttackType::EmptyPayload => &[RejectReason::EmptyPayload],
+            AttackType::Malicious => &[RejectReason::MaliciousPayload],
+            AttackType::Stealth => &[RejectReason::Ok],
+        }
+    }
+}
+
+/// How honest nodes' view of the attackers evolved in the recovery scenario. Scores are the
+/// mean validator score of bad peers across honest nodes.
+#[derive(Debug, Clone, Default)]
+pub struct RecoveryReport {
+    /// When the bad peers stopped, since the start of publishing.
+    pub switchover: Duration,
+    /// Lowest mean score observed, and when.
+    pub peak_score: f64,
+    pub peak_at: Duration,
+    /// Mean score at the last sample before shutdown.
+    pub final_score: f64,
+    /// Time after the switchover until no honest node quarantined any bad peer, if that
+    /// happened before the run ended.
+    pub recovered_after: Option<Duration>,
+}
+
+/// How closely honest nodes agree on the bad peers' scores over the run. Diverging views
+/// mean detection reached only part of the network, e.g. the attackers' direct neighbours.
+#[derive(Debug, Clone, Default)]
+pub struct ScoreAgreement {
+    /// (time since publishing started, variance of a bad peer's score across honest nodes,
+    /// averaged over bad peers). A node that never saw a bad peer counts as scoring it 0.
+    pub samples: Vec<(Duration, f64)>,
+}
+
+impl ScoreAgreement {
+    pub fn mean_variance(&self) -> Option<f64> {
+        (!self.samples.is_empty()).then(|| self.samples.iter().map(|(_, v)| v).sum::<f64>() / self.samples.len() as f64)
+    }
+
+    pub fn final_variance(&self) -> Option<f64> {
+        self.samples.last().map(|(_, v)| *v)
+    }
+}
+
+/// Live tally across all nodes, printed every `--summary-interval` seconds.
+#[derive(Debug, Clone)]
+pub struct ProgressTally {
+    pub elapsed: Duration,
+    pub nodes_responding: usize,
+    pub accepted: u64,
+    pub rejected: u64,
+    pub ignored: u64,
+    pub quarantined_peers: u64,
+}
+
+/// Aggregated outcome of a simulation run; this is what the final report prints.
+#[derive(Debug, Clone, Default)]
+pub struct SimReport {
+    pub total_peers: usize,
+    pub bad_peers: usize,
+    /// Seed behind bad-peer selection, bootstrap dials, publisher traffic and node identities.
+    pub seed: u64,
+    pub summaries: Vec<(usize, NodeSummary)>,
+    pub progress: Vec<ProgressTally>,
+    pub total_accepted: u64,
+    pub total_rejected: u64,
+    pub total_ignored: u64,
+    pub total_quarantined: u64,
+    pub honest_accepted: u64,
+    pub honest_rejected: u64,
+    pub honest_published: u64,
+    pub validation_failures: u64,
+    pub shed_validations: u64,
+    pub no_source_messages: u64,
+    pub paused_publishes: u64,
+    pub publish_errors: u64,
+    pub subscribe_failures: u64,
+    /// Connected peers that turned out not to speak gossipsub.
+    pub unsupported_peers: u64,
+    /// Dials that didn't connect under `--dial-concurrency` (not tracked without it).
+    pub failed_dials: usize,
+    /// Publishes lost to `--drop-rate` before reaching their node; not in `honest_published`.
+    pub dropped_publishes: u64,
+    /// Distinct message ids accepted anywhere; `total_accepted` counts each delivery.
+    pub unique_messages: usize,
+    /// Nodes that sent no summary (placeholders in `summaries`).
+    pub missing_summaries: usize,
+    /// Shadow validator tallies summed over nodes, when `--shadow-penalty-scale` is set.
+    pub shadow: Option<ShadowSummary>,
+    pub quarantine_consensus: QuarantineConsensus,
+    pub unique_delivery: UniqueDelivery,
+    pub detection_latency: DetectionLatency,
+    pub mesh_warmup: MeshWarmup,
+    pub score_separation: ScoreSeparation,
+    pub blind_detection: BlindDetection,
+    pub throttling: ThrottlingSummary,
+    /// Only set for `Scenario::Recovery`.
+    pub recovery: Option<RecoveryReport>,
+    /// Only sampled in networked runs with bad peers.
+    pub score_agreement: ScoreAgreement,
+    /// Deliveries of each bad-node attack type by the reason the receiving validator gave.
+    /// Only tracked with `--no-network`, where messages can be tagged at the source.
+    pub attack_reasons: BTreeMap<(AttackType, &'static str), u64>,
+    /// Each `--sla-*` criterion checked against this run.
+    pub sla_checks: Vec<SlaCheck>,
+}
+
+/// How far honest nodes agree on who the attackers are.
+#[derive(Debug, Clone, Default)]
+pub struct QuarantineConsensus {
+    /// Each bad peer with the number of honest nodes that quarantined it.
+    pub votes: Vec<(libp2p::PeerId, usize)>,
+    /// Honest nodes whose summaries were counted.
+    pub honest_nodes: usize,
+    /// (honest node, honest peer) pairs where the node quarantined the peer.
+    pub honest_quarantines: usize,
+}
+
+impl QuarantineConsensus {
+    /// For each bad node, count the honest nodes (summaries from indices not in `bad_nodes`)
+    /// that ended the run with it quarantined.
+    pub fn compute(summaries: &[(usize, NodeSummary)], bad_nodes: &[(usize, libp2p::PeerId)]) -> Self {
+        let honest: Vec<&NodeSummary> = summaries
+            .iter()
+            .filter(|(idx, s)| !s.missing && !bad_nodes.iter().any(|(bad, _)| bad == idx))
+            .map(|(_, s)| s)
+            .collect();
+        let votes = bad_nodes
+            .iter()
+            .map(|(_, peer)| (*peer, honest.iter().filter(|s| s.quarantined_ids.contains(peer)).count()))
+            .collect();
+        let honest_quarantines = honest
+            .iter()
+            .flat_map(|s| &s.quarantined_ids)
+            .filter(|peer| !bad_nodes.iter().any(|(_, bad)| bad == *peer))
+            .count();
+        Self { votes, honest_nodes: honest.len(), honest_quarantines }
+    }
+
+    /// Share of (honest node, bad peer) pairs where the node quarantined the peer, in
+    /// percent; `None` without bad peers or honest nodes.
+    pub fn percent(&self) -> Option<f64> {
+        let pairs = self.votes.len() * self.honest_nodes;
+        if pairs == 0 {
+            return None;
+        }
+        let quarantined: usize = self.votes.iter().map(|(_, n)| n).sum();
+        Some(100.0 * quarantined as f64 / pairs as f64)
+    }
+}
+
+/// Honest delivery counted per distinct message rather than per node: how many of the
+/// messages honest nodes published were accepted by at least `quorum` honest nodes.
+#[derive(Debug, Clone, Default)]
+pub struct UniqueDelivery {
+    pub published: usize,
+    pub delivered: usize,
+    pub quorum: usize,
+}
+
+impl UniqueDelivery {
+    /// Follow every id in honest nodes' `published_ids` through the other honest nodes'
+    /// `accepted_ids`; nodes in `bad_nodes` neither publish nor vote.
+    pub fn compute(summaries: &[(usize, NodeSummary)], bad_nodes: &[(usize, libp2p::PeerId)], quorum: usize) -> Self {
+        let honest: Vec<&NodeSummary> = summaries
+            .iter()
+            .filter(|(idx, s)| !s.missing && !bad_nodes.iter().any(|(bad, _)| bad == idx))
+            .map(|(_, s)| s)
+            .collect();
+        let published: HashSet<&libp2p::gossipsub::MessageId> = honest.iter().flat_map(|s| &s.published_ids).collect();
+        let delivered = published
+            .iter()
+            .filter(|id| honest.iter().filter(|s| s.accepted_ids.contains(**id)).count() >= quorum)
+            .count();
+        Self { published: published.len(), delivered, quorum }
+    }
+
+    /// Delivered share of distinct honest messages in percent; `None` if none were published.
+    pub fn percent(&self) -> Option<f64> {
+        (self.published > 0).then(|| 100.0 * self.delivered as f64 / self.published as f64)
+    }
+}
+
+/// How quickly honest nodes quarantined each bad peer after its first offence against them.
+#[derive(Debug, Clone, Default)]
+pub struct DetectionLatency {
+    /// Each bad peer quarantined somewhere, with its mean time to quarantine and the number
+    /// of honest nodes that quarantined it.
+    pub per_peer: Vec<(libp2p::PeerId, Duration, usize)>,
+}
+
+impl DetectionLatency {
+    pub fn compute(summaries: &[(usize, NodeSummary)], bad_nodes: &[(usize, libp2p::PeerId)]) -> Self {
+        let honest: Vec<&NodeSummary> = summaries
+            .iter()
+            .filter(|(idx, s)| !s.missing && !bad_nodes.iter().any(|(bad, _)| bad == idx))
+            .map(|(_, s)| s)
+            .collect();
+        let per_peer = bad_nodes
+            .iter()
+            .filter_map(|(_, peer)| {
+                let times: Vec<Duration> = honest
+                    .iter()
+                    .filter_map(|s| s.time_to_quarantine.iter().find(|(p, _)| p == peer).map(|(_, t)| *t))
+                    .collect();
+                let n = times.len();
+                (n > 0).then(|| (*peer, times.iter().sum::<Duration>() / n as u32, n))
+            })
+            .collect();
+        Self { per_peer }
+    }
+}
+
+/// How well honest nodes' final validator scores separate honest peers from bad ones. One
+/// sample per (honest node, tracked peer) pair, labelled by whether the peer is bad.
+#[derive(Debug, Clone, Default)]
+pub struct ScoreSeparation {
+    pub honest: Vec<f64>,
+    pub bad: Vec<f64>,
+}
+
+impl ScoreSeparation {
+    pub fn compute(summaries: &[(usize, NodeSummary)], bad_nodes: &[(usize, libp2p::PeerId)]) -> Self {
+        let mut sep = Self::default();
+        for (idx, s) in summaries {
+            if s.missing || bad_nodes.iter().any(|(bad, _)| bad == idx) {
+                continue;
+            }
+            for (peer, score) in &s.peer_scores {
+                if bad_nodes.iter().any(|(_, bad)| bad == peer) {
+                    sep.bad.push(*score);
+                } else {
+                    sep.honest.push(*score);
+                }
+            }
+        }
+        sep
+    }
+
+    pub fn honest_mean(&self) -> Option<f64> {
+        mean(&self.honest)
+    }
+
+    pub fn bad_mean(&self) -> Option<f64> {
+        mean(&self.bad)
+    }
+
+    /// Honest mean minus bad mean; large and positive when scoring discriminates well.
+    pub fn gap(&self) -> Option<f64> {
+        Some(self.honest_mean()? - self.bad_mean()?)
+    }
+
+    /// Chance that a random honest sample outscores a random bad one (ties count half):
+    /// 1.0 separates perfectly, 0.5 is no better than a coin. `None` without both labels.
+    pub fn auc(&self) -> Option<f64> {
+        if self.honest.is_empty() || self.bad.is_empty() {
+            return None;
+        }
+        let wins: f64 = self
+            .honest
+            .iter()
+            .flat_map(|h| self.bad.iter().map(move |b| match h.total_cmp(b) {
+                std::cmp::Ordering::Greater => 1.0,
+                std::cmp::Ordering::Equal => 0.5,
+                std::cmp::Ordering::Less => 0.0,
+            }))
+            .sum();
+        Some(wins / (self.honest.len() * self.bad.len()) as f64)
+    }
+}
+
+fn mean(xs: &[f64]) -> Option<f64> {
+    (!xs.is_empty()).then(|| xs.iter().sum::<f64>() / xs.len() as f64)
+}
+
+/// How long honest authors took to get their first message accepted by each honest node,
+/// i.e. how quickly the mesh warmed up. One sample per (honest node, honest author) pair.
+#[derive(Debug, Clone, Default)]
+pub struct MeshWarmup {
+    /// Sorted ascending.
+    pub samples: Vec<Duration>,
+}
+
+impl MeshWarmup {
+    pub fn compute(summaries: &[(usize, NodeSummary)], bad_nodes: &[(usize, libp2p::PeerId)]) -> Self {
+        let mut samples: Vec<Duration> = summaries
+            .iter()
+            .filter(|(idx, s)| !s.missing && !bad_nodes.iter().any(|(bad, _)| bad == idx))
+            .flat_map(|(_, s)| &s.time_to_first_accept)
+            .filter(|(peer, _)| !bad_nodes.iter().any(|(_, bad)| bad == peer))
+            .map(|(_, t)| *t)
+            .collect();
+        samples.sort();
+        Self { samples }
+    }
+
+    /// The `q`th quantile (0-1), nearest rank.
+    pub fn percentile(&self, q: f64) -> Option<Duration> {
+        let last = self.samples.len().checked_sub(1)?;
+        Some(self.samples[((last as f64 * q.clamp(0.0, 1.0)).round() as usize).min(last)])
+    }
+}
+
+/// How well honest nodes' behavioural classifications (`Validator::classify`, no access to
+/// the bad set) match the known attackers. One sample per (honest node, tracked peer) pair;
+/// only `Malicious` counts as flagged.
+#[derive(Debug, Clone, Default)]
+pub struct BlindDetection {
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub true_negatives: usize,
+    pub false_negatives: usize,
+}
 
-pub async fn run(cli: Cli) -> anyhow::Result<()> {
+impl BlindDetection {
+    pub fn compute(summaries: &[(usize, NodeSummary)], bad_nodes: &[(usize, libp2p::PeerId)]) -> Self {
+        let mut d = Self::default();
+        for (idx, s) in summaries {
+            if bad_nodes.iter().any(|(bad, _)| bad == idx) {
+                continue;
+            }
+            for (peer, class) in &s.classifications {
+                let is_bad = bad_nodes.iter().any(|(_, p)| p == peer);
+                match (is_bad, *class == Classification::Malicious) {
+                    (true, true) => d.true_positives += 1,
+                    (false, true) => d.false_positives += 1,
+                    (false, false) => d.true_negatives += 1,
+                    (true, false) => d.false_negatives += 1,
+                }
+            }
+        }
+        d
+    }
+
+    /// Share of samples classified correctly, in percent; `None` without samples.
+    pub fn accuracy(&self) -> Option<f64> {
+        let total = self.true_positives + self.false_positives + self.true_negatives + self.false_negatives;
+        if total == 0 {
+            return None;
+        }
+        Some(100.0 * (self.true_positives + self.true_negatives) as f64 / total as f64)
+    }
+
+    /// Share of honest samples flagged malicious, in percent; `None` without honest samples.
+    pub fn false_positive_rate(&self) -> Option<f64> {
+        let honest = self.false_positives + self.true_negatives;
+        if honest == 0 {
+            return None;
+        }
+        Some(100.0 * self.false_positives as f64 / honest as f64)
+    }
+}
+
+/// Pass/fail criteria for a run, from the `--sla-*` flags.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sla {
+    /// Percent of honest messages accepted.
+    pub min_honest_success: f64,
+    /// Percent of all messages rejected.
+    pub min_rejection_rate: f64,
+    /// (honest node, honest peer) quarantine pairs.
+    pub max_honest_quarantines: usize,
+    /// Percent of honest peers blind detection flags as malicious.
+    pub max_false_positive_rate: f64,
+}
+
+/// Default `Sla` limits, shared with the `--sla-*` flags.
+pub const DEFAULT_SLA_MIN_HONEST_SUCCESS: f64 = 90.0;
+pub const DEFAULT_SLA_MIN_REJECTION_RATE: f64 = 70.0;
+pub const DEFAULT_SLA_MAX_HONEST_QUARANTINES: usize = 0;
+pub const DEFAULT_SLA_MAX_FALSE_POSITIVE_RATE: f64 = 5.0;
+
+impl Default for Sla {
+    fn default() -> Self {
+        Self {
+            min_honest_success: DEFAULT_SLA_MIN_HONEST_SUCCESS,
+            min_rejection_rate: DEFAULT_SLA_MIN_REJECTION_RATE,
+            max_honest_quarantines: DEFAULT_SLA_MAX_HONEST_QUARANTINES,
+            max_false_positive_rate: DEFAULT_SLA_MAX_FALSE_POSITIVE_RATE,
+        }
+    }
+}
+
+/// One SLA criterion measured against a run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlaCheck {
+    pub name: &'static str,
+    /// `None` when the criterion doesn't apply to this run; it then passes.
+    pub actual: Option<f64>,
+    pub limit: f64,
+    /// Whether `actual` is a lower bound (`>= limit`) rather than an upper one.
+    pub at_least: bool,
+    pub passed: bool,
+}
+
+impl Sla {
+    pub fn from_cli(cli: &Cli) -> Self {
+        Self {
+            min_honest_success: cli.sla_min_honest_success,
+            min_rejection_rate: cli.sla_min_rejection_rate,
+            max_honest_quarantines: cli.sla_max_honest_quarantines,
+            max_false_positive_rate: cli.sla_max_false_positive_rate,
+        }
+    }
+
+    /// Check every criterion against `report`. A run without blind-detection samples has a
+    /// false-positive rate of 0; a run without bad peers has nothing that ought to be
+    /// rejected, so its rejection rate is not applicable.
+    pub fn check(&self, report: &SimReport) -> Vec<SlaCheck> {
+        let at_least = |name, actual: Option<f64>, limit: f64| SlaCheck {
+            name,
+            actual,
+            limit,
+            at_least: true,
+            passed: actual.is_none_or(|a| a >= limit),
+        };
+        let at_most = |name, actual: Option<f64>, limit: f64| SlaCheck {
+            name,
+            actual,
+            limit,
+            at_least: false,
+            passed: actual.is_none_or(|a| a <= limit),
+        };
+        vec![
+            at_least("honest_success", Some(report.honest_success_rate()), self.min_honest_success),
+            at_least(
+                "rejection_rate",
+                (report.bad_peers > 0).then(|| report.rejection_rate()),
+                self.min_rejection_rate,
+            ),
+            at_most(
+                "honest_quarantines",
+                Some(report.quarantine_consensus.honest_quarantines as f64),
+                self.max_honest_quarantines as f64,
+            ),
+            at_most(
+                "false_positive_rate",
+                Some(report.blind_detection.false_positive_rate().unwrap_or(0.0)),
+                self.max_false_positive_rate,
+            ),
+        ]
+    }
+}
+
+/// How much traffic the rate limiter shed, to tell its share of the defence apart from
+/// content-based rejection.
+#[derive(Debug, Clone, Default)]
+pub struct ThrottlingSummary {
+    /// Messages throttled by any node, from any forwarder.
+    pub total_throttled: u64,
+    /// Each bad peer with its rate-limiter outcomes summed over the honest nodes.
+    pub bad_peers: Vec<(libp2p::PeerId, ThrottleStats)>,
+}
+
+impl ThrottlingSummary {
+    pub fn compute(summaries: &[(usize, NodeSummary)], bad_nodes: &[(usize, libp2p::PeerId)]) -> Self {
+        let total_throttled = summaries.iter().flat_map(|(_, s)| &s.throttle_stats).map(|(_, t)| t.throttled).sum();
+        let bad_peers = bad_nodes
+            .iter()
+            .map(|(_, peer)| {
+                let mut sum = ThrottleStats::default();
+                let honest = summaries.iter().filter(|(idx, _)| !bad_nodes.iter().any(|(bad, _)| bad == idx));
+                for (_, t) in honest.flat_map(|(_, s)| &s.throttle_stats).filter(|(p, _)| p == peer) {
+                    sum.attempts += t.attempts;
+                    sum.throttled += t.throttled;
+                    sum.passed += t.passed;
+                }
+                (*peer, sum)
+            })
+            .collect();
+        Self { total_throttled, bad_peers }
+    }
+
+    /// Shares of the bad peers' attempts, in percent, that were throttled and that reached
+    /// content validation; `None` if no bad peer was seen.
+    pub fn bad_fractions(&self) -> Option<(f64, f64)> {
+        let attempts: u64 = self.bad_peers.iter().map(|(_, t)| t.attempts).sum();
+        if attempts == 0 {
+            return None;
+        }
+        let throttled: u64 = self.bad_peers.iter().map(|(_, t)| t.throttled).sum();
+        let passed: u64 = self.bad_peers.iter().map(|(_, t)| t.passed).sum();
+        Some((100.0 * throttled as f64 / attempts as f64, 100.0 * passed as f64 / attempts as f64))
+    }
+}
+
+impl SimReport {
+    pub fn total_messages(&self) -> u64 {
+        self.total_accepted + self.total_rejected + self.total_ignored
+    }
+
+    /// Honest messages accepted out of those accepted or rejected, in percent (0 without any).
+    pub fn honest_success_rate(&self) -> f64 {
+        let honest = self.honest_accepted + self.honest_rejected;
+        if honest > 0 {
+            100.0 * self.honest_accepted as f64 / honest as f64
+        } else {
+            0.0
+        }
+    }
+
+    /// Messages rejected out of all messages, in percent (0 without any).
+    pub fn rejection_rate(&self) -> f64 {
+        let total = self.total_messages();
+        if total > 0 {
+            100.0 * self.total_rejected as f64 / total as f64
+        } else {
+            0.0
+        }
+    }
+
+    /// Whether every SLA criterion passed (vacuously true before they are checked).
+    pub fn sla_passed(&self) -> bool {
+        self.sla_checks.iter().all(|c| c.passed)
+    }
+}
+
+pub async fn run(mut cli: Cli) -> anyhow::Result<SimReport> {
+    if cli.random_seed {
+        cli.seed = rand::random();
+    }
+    info!(seed = cli.seed, "simulation seed; pass --seed {} to reproduce this run", cli.seed);
     let peers = cli.peers.max(1);
     let bad_peers = cli.bad_peers.min(peers);
     let duration = Duration::from_secs(cli.duration_secs);
+    if let Some(idx) = cli.dump_node {
+        anyhow::ensure!(idx < peers, "--dump-node {idx} is out of range for {peers} peers");
+    }
+
+    if cli.no_network {
+        let mut report = run_offline(&cli, peers, bad_peers)?;
+        report.sla_checks = Sla::from_cli(&cli).check(&report);
+        print_simulation_report(&report);
+        return Ok(report);
+    }
 
-    let mut nodes: Vec<NodeHandle> = Vec::with_capacity(peers);
     let mut event_rxs = Vec::with_capacity(peers);
 
     // Create ready barrier
     let (ready_tx, mut ready_rx) = mpsc::unbounded_channel::<usize>();
 
+    let (audit, audit_writer) = match &cli.audit_log {
+        Some(path) => {
+            let (log, writer) = AuditLog::open(path).await?;
+            (Some(log), Some(writer))
+        }
+        None => (None, None),
+    };
+
     // First pass: spawn all nodes to get their peer IDs
     let mut temp_handles = Vec::with_capacity(peers);
     for i in 0..peers {
-        let cfg = NodeConfig {
+        let listen_port = match cli.base_port {
+            Some(base) => u16::try_from(i)
+                .ok()
+                .and_then(|i| base.checked_add(i))
+                .ok_or_else(|| anyhow::anyhow!("base port {base} + node {i} exceeds the port range"))?,
+            None => 0,
+        };
+        let mut cfg = NodeConfig {
             idx: i,
             topic: "test-topic".to_string(),
             max_message_bytes: cli.max_message_bytes,
+            listen_addr: cli.listen_addr.clone(),
+            listen_port,
+            authenticity: cli.authenticity,
+            validation_mode: cli.validation_mode,
+            audit: audit.clone(),
+            min_publish_peers: cli.min_publish_peers,
+            dedupe_enabled: !cli.no_dedupe,
+            ignore_penalty: 0.0,
+            emit_decisions: false,
+            app_score_update: cli.app_score_update,
+            message_domain: cli.message_domain.clone(),
+            score_decay: cli.score_decay.map(|score_factor| DecayConfig { score_factor, ..Default::default() }),
+            target_acceptance: cli.target_acceptance,
+            app_score_floor: cli.app_score_floor,
+            // every simulated node shares 127.0.0.1
+            bind_reputation_to_address: false,
+            mesh: MeshConfig {
+                history_length: cli.history_length,
+                history_gossip: cli.history_gossip,
+                gossip_lazy: cli.gossip_lazy,
+            },
+            validation_worker: cli.validation_worker,
+            max_pending_validations: (cli.max_pending_validations > 0).then_some(cli.max_pending_validations),
+            report_as: cli.report_as.iter().cloned().collect(),
+            shadow: None,
+            keypair: Some(node_keypair(cli.seed, i)),
         };
+        cfg.shadow = cli.shadow_penalty_scale.map(|factor| shadow_config(&cfg.validator_config(), factor));
         let (handle, rx) = spawn_node(cfg, vec![], Some(ready_tx.clone()))?;
         temp_handles.push(handle);
         event_rxs.push(rx);
     }
 
-    // Collect bad peer IDs (first bad_peers nodes are malicious)
-    let bad_peer_ids: Vec<libp2p::PeerId> = temp_handles
+    // Collect bad peer IDs
+    let exclude = if cli.bad_excludes_bootstraps { cli.bootstrap_count } else { 0 };
+    let bad_indices: HashSet<usize> =
+        select_bad_peers(peers, bad_peers, cli.bad_selection, exclude, cli.seed).into_iter().collect();
+    let bad_nodes: Vec<(usize, libp2p::PeerId)> = temp_handles
         .iter()
-        .take(bad_peers)
-        .map(|h| h.peer_id)
+        .enumerate()
+        .filter(|(i, _)| bad_indices.contains(i))
+        .map(|(i, h)| (i, h.peer_id))
         .collect();
+    let bad_peer_ids: Vec<libp2p::PeerId> = bad_nodes.iter().map(|(_, p)| *p).collect();
 
     info!(?bad_peer_ids, "identified bad peers");
 
-    nodes = temp_handles;
+    let nodes: Vec<NodeHandle> = temp_handles;
 
     // Wait for listen addresses.
     let mut listen_addrs = Vec::with_capacity(peers);
-    for i in 0..peers {
-        let rx = &mut event_rxs[i];
+    for (i, rx) in event_rxs.iter_mut().enumerate() {
         let addr = loop {
             match rx.recv().await {
                 Some(NodeEvent::NewListenAddr(a)) => break a,
@@ -57,54 +766,61 @@ pub async fn run(cli: Cli) -> anyhow::Result<()> {
         listen_addrs.push(addr);
     }
 
-    // Dial everyone into node 0 as a bootstrap, then create more connections for better mesh
-    let bootstrap = listen_addrs[0].clone();
-    for i in 1..peers {
-        let _ = nodes[i]
-            .cmd
-            .send(NodeCommand::Dial {
-                addr: bootstrap.clone(),
-            })
-            .await;
-    }
-
+    // Dial everyone into a few bootstrap nodes, then create more connections for better mesh
+    let mut dials: Vec<(usize, usize)> = bootstrap_dials(peers, cli.bootstrap_count, cli.seed)
+        .into_iter()
+        .enumerate()
+        .flat_map(|(i, targets)| targets.into_iter().map(move |j| (i, j)))
+        .collect();
     // Create additional connections for better mesh formation
     // Each node dials 2 random other nodes
     for i in 0..peers {
         for j in 0..peers {
             if i != j && (i + j) % 3 == 0 {
-                let _ = nodes[i]
-                    .cmd
-                    .send(NodeCommand::Dial {
-                        addr: listen_addrs[j].clone(),
-                    })
-                    .await;
+                dials.push((i, j));
             }
         }
     }
+    let failed_dials = match cli.dial_concurrency {
+        Some(limit) => dial_bounded(&nodes, &mut event_rxs, &listen_addrs, &dials, limit).await,
+        None => {
+            for &(i, j) in &dials {
+                let _ = nodes[i].cmd.send(NodeCommand::Dial { addr: listen_addrs[j].clone() }).await;
+            }
+            0
+        }
+    };
 
-    // Subscribe everyone.
+    // Subscribe everyone, each node once it has a connection so it joins a mesh with someone in it.
+    let fallback = Duration::from_millis(cli.subscribe_fallback_ms);
     for n in &nodes {
-        let _ = n.cmd.send(NodeCommand::Subscribe).await;
+        let _ = n.cmd.send(NodeCommand::SubscribeAfterConnect { fallback }).await;
     }
 
-    // Give time for gossipsub mesh to form
-    tokio::time::sleep(Duration::from_secs(3)).await;
-
-    // Wait until all nodes report ready (with timeout)
+    // Wait until all nodes report ready (with timeout); each is ready once it subscribed,
+    // which is no later than `fallback`
     let mut ready_count = 0usize;
     let expected = peers;
-    let timeout = tokio::time::sleep(Duration::from_secs(5));
-    tokio::pin!(timeout);
+    let ready_deadline = Instant::now() + Duration::from_secs(cli.ready_timeout_secs);
 
     while ready_count < expected {
-        tokio::select! {
-            Some(_idx) = ready_rx.recv() => { ready_count += 1; }
-            () = &mut timeout => {
-                eprintln!("WARN: ready barrier timeout: got {}/{} ready", ready_count, expected);
-                break;
+        let ready = if Instant::now() < ready_deadline {
+            timeout_at(ready_deadline, ready_rx.recv()).await.ok().flatten()
+        } else {
+            None
+        };
+        if ready.is_some() {
+            ready_count += 1;
+            continue;
+        }
+        eprintln!("WARN: ready barrier timeout: got {}/{} ready", ready_count, expected);
+        if cli.strict_ready {
+            for n in &nodes {
+                let _ = n.cmd.send(NodeCommand::Shutdown).await;
             }
+            anyhow::bail!("ready barrier timed out with {ready_count}/{expected} nodes ready");
         }
+        break;
     }
 
     info!(ready_count, expected, "nodes ready, sending bad peer list");
@@ -118,12 +834,21 @@ pub async fn run(cli: Cli) -> anyhow::Result<()> {
     }
 
     // Spawn publisher tasks per node
+    let switchover = Instant::now() + duration / 2;
+    let attack_ends = (cli.scenario == Scenario::Recovery).then_some(switchover);
+    let adapt_after = adapt_after(&cli, duration);
+    let attack_started = Instant::now();
+    let dropped = Arc::new(AtomicU64::new(0));
     let mut pub_tasks = Vec::new();
     for (i, n) in nodes.iter().enumerate() {
         let cmd = n.cmd.clone();
-        let is_bad = i < bad_peers;
+        let is_bad = bad_indices.contains(&i);
+        let adaptive = is_bad && cli.scenario == Scenario::Adaptive;
         let node_seed = cli.seed.wrapping_add(i as u64);
         let mut rng = StdRng::seed_from_u64(node_seed);
+        let mut drop_rng = drop_rng(cli.seed, i);
+        let drop_rate = cli.drop_rate;
+        let dropped = dropped.clone();
         let rate = if is_bad {
             cli.spam_per_sec
         } else {
@@ -134,55 +859,34 @@ pub async fn run(cli: Cli) -> anyhow::Result<()> {
         pub_tasks.push(tokio::spawn(async move {
             let mut tick = interval(Duration::from_secs_f64(1.0 / (rate.max(1) as f64)));
             let mut seq: u64 = 0;
+            let mut attacker = AttackerState::Spamming;
 
             loop {
                 tick.tick().await;
+                if is_bad && attack_ends.is_some_and(|at| Instant::now() >= at) {
+                    info!(node = i, "attack over, going silent");
+                    break;
+                }
+                if adaptive {
+                    let next = attacker.step(attack_started.elapsed(), adapt_after);
+                    if next != attacker {
+                        info!(node = i, ?next, "attacker likely quarantined, adapting");
+                        attacker = next;
+                        tick = interval(attacker.interval(rate));
+                        tick.tick().await;
+                    }
+                }
                 seq += 1;
 
-                let bytes = if is_bad {
-                    // Generate various types of bad messages - each unique to avoid gossipsub dedupe
-                    let nonce: u64 = rng.gen();
-                    match rng.gen_range(0..5) {
-                        0 => {
-                            // Pure junk (decode_error)
-                            let mut junk = vec![0u8; rng.gen_range(100..500)];
-                            rng.fill(&mut junk[..]);
-                            junk
-                        }
-                        1 => {
-                            // Oversize payload
-                            let payload_len = max_bytes + rng.gen_range(100..1000);
-                            let mut payload = vec![0u8; payload_len];
-                            rng.fill(&mut payload[..]);
-                            encode(&WireMessage::Good { seq: nonce, payload })
-                        }
-                        2 => {
-                            // Empty payload
-                            encode(&WireMessage::Good { seq: nonce, payload: vec![] })
-                        }
-                        3 => {
-                            // Malicious marker
-                            encode(&WireMessage::Bad)
-                        }
-                        _ => {
-                            // Random junk
-                            let mut junk = vec![0u8; rng.gen_range(50..200)];
-                            rng.fill(&mut junk[..]);
-                            junk
-                        }
-                    }
+                let bytes = if adaptive {
+                    attacker.message(&mut rng, i, seq, max_bytes)
                 } else {
-                    // Honest nodes: use node index and seq to create unique payloads
-                    let mut payload = vec![0u8; 100];
-                    // Fill with node-specific pattern
-                    for (j, byte) in payload.iter_mut().enumerate() {
-                        *byte = ((i + j + seq as usize) % 256) as u8;
-                    }
-                    encode(&WireMessage::Good {
-                        seq,
-                        payload,
-                    })
+                    traffic_message(&mut rng, i, seq, is_bad, max_bytes)
                 };
+                if lost(&mut drop_rng, drop_rate) {
+                    dropped.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                }
 
                 let _ = cmd.send(NodeCommand::Publish { data: bytes }).await;
             }
@@ -190,89 +894,749 @@ pub async fn run(cli: Cli) -> anyhow::Result<()> {
     }
 
     info!(?duration, peers, bad_peers, "simulation running");
-    tokio::time::sleep(duration).await;
-
-    // Shutdown.
-    for n in &nodes {
-        let _ = n.cmd.send(NodeCommand::Shutdown).await;
+    let started = Instant::now();
+    let end = tokio::time::sleep(duration);
+    tokio::pin!(end);
+    let mut progress_tick = cli
+        .summary_interval
+        .map(|secs| Duration::from_secs(secs.max(1)))
+        .map(|every| interval_at(started + every, every));
+    let mut progress = Vec::new();
+    let honest_nodes: Vec<&NodeHandle> =
+        nodes.iter().enumerate().filter(|(i, _)| !bad_indices.contains(i)).map(|(_, n)| n).collect();
+    let mut recovery_tick = attack_ends.map(|_| interval(RECOVERY_SAMPLE_INTERVAL));
+    let mut recovery: Option<RecoveryReport> = None;
+    let honest_handles: Vec<(libp2p::PeerId, &NodeHandle)> = honest_nodes.iter().map(|n| (n.peer_id, *n)).collect();
+    let mut reputation_tick = (!bad_peer_ids.is_empty()).then(|| interval_at(started + REPUTATION_ROUND_INTERVAL, REPUTATION_ROUND_INTERVAL));
+    let mut reported: Option<HashMap<(libp2p::PeerId, libp2p::PeerId), f64>> = cli.reputation_gossip.then(HashMap::new);
+    let mut score_agreement = ScoreAgreement::default();
+    loop {
+        tokio::select! {
+            () = &mut end => break,
+            _ = async { recovery_tick.as_mut().unwrap().tick().await }, if recovery_tick.is_some() => {
+                let Some((score, quarantined)) = sample_bad_peers(&honest_nodes, &bad_peer_ids).await else {
+                    continue;
+                };
+                let elapsed = started.elapsed();
+                let r = recovery.get_or_insert_with(|| RecoveryReport {
+                    switchover: switchover.saturating_duration_since(started),
+                    peak_score: score,
+                    peak_at: elapsed,
+                    ..Default::default()
+                });
+                if score < r.peak_score {
+                    r.peak_score = score;
+                    r.peak_at = elapsed;
+                }
+                r.final_score = score;
+                if Instant::now() >= switchover && quarantined == 0 && r.recovered_after.is_none() {
+                    r.recovered_after = Some(elapsed.saturating_sub(r.switchover));
+                }
+            }
+            _ = async { reputation_tick.as_mut().unwrap().tick().await }, if reputation_tick.is_some() => {
+                if let Some(variance) = reputation_round(&honest_handles, &bad_peer_ids, reported.as_mut()).await {
+                    score_agreement.samples.push((started.elapsed(), variance));
+                }
+            }
+            _ = async { progress_tick.as_mut().unwrap().tick().await }, if progress_tick.is_some() => {
+                let tally = poll_progress(&nodes, started.elapsed()).await;
+                println!(
+                    "[progress {:>5.1}s] accepted={} rejected={} ignored={} quarantined={} ({}/{} nodes)",
+                    tally.elapsed.as_secs_f64(),
+                    tally.accepted,
+                    tally.rejected,
+                    tally.ignored,
+                    tally.quarantined_peers,
+                    tally.nodes_responding,
+                    nodes.len()
+                );
+                progress.push(tally);
+            }
+        }
     }
+
+    // Shutdown: stop publishing first so no publish races a node's shutdown, then nodes in
+    // index order.
     for t in pub_tasks {
         t.abort();
     }
+    if let Some(idx) = cli.dump_node {
+        let (reply, rx) = oneshot::channel();
+        let _ = nodes[idx].cmd.send(NodeCommand::DumpState { reply }).await;
+        match timeout(Duration::from_secs(5), rx).await {
+            Ok(Ok(snapshot)) => NodeDump::new(idx, &snapshot).write(cli.dump_node_out.as_deref())?,
+            _ => warn!(node = idx, "no state from node; nothing to dump"),
+        }
+    }
+    for n in &nodes {
+        let _ = n.cmd.send(NodeCommand::Shutdown).await;
+    }
 
     // Collect and analyze summaries
-    let mut summaries = Vec::new();
-    for (i, mut rx) in event_rxs.into_iter().enumerate() {
-        while let Some(ev) = rx.recv().await {
-            if let NodeEvent::Summary(s) = ev {
-                info!(node = i, ?s, "node summary");
-                summaries.push((i, s));
-                break;
+    let summaries = collect_summaries(event_rxs, SUMMARY_TIMEOUT).await;
+
+    // Nodes have dropped their audit handles on shutdown; drop ours and let the writer flush.
+    drop(audit);
+    if let Some(writer) = audit_writer {
+        writer.await??;
+    }
+
+    // Generate final report
+    let consensus = QuarantineConsensus::compute(&summaries, &bad_nodes);
+    let unique_delivery = UniqueDelivery::compute(&summaries, &bad_nodes, cli.delivery_quorum);
+    let detection_latency = DetectionLatency::compute(&summaries, &bad_nodes);
+    let mesh_warmup = MeshWarmup::compute(&summaries, &bad_nodes);
+    let score_separation = ScoreSeparation::compute(&summaries, &bad_nodes);
+    let blind_detection = BlindDetection::compute(&summaries, &bad_nodes);
+    let throttling = ThrottlingSummary::compute(&summaries, &bad_nodes);
+    let mut report = build_report(summaries, peers, bad_peers);
+    report.seed = cli.seed;
+    report.failed_dials = failed_dials;
+    report.dropped_publishes = dropped.load(Ordering::Relaxed);
+    report.progress = progress;
+    report.quarantine_consensus = consensus;
+    report.unique_delivery = unique_delivery;
+    report.detection_latency = detection_latency;
+    report.mesh_warmup = mesh_warmup;
+    report.score_separation = score_separation;
+    report.blind_detection = blind_detection;
+    report.throttling = throttling;
+    report.recovery = recovery;
+    report.score_agreement = score_agreement;
+    report.sla_checks = Sla::from_cli(&cli).check(&report);
+    print_simulation_report(&report);
+
+    Ok(report)
+}
+
+/// Run `dials` (dialer, target) at most `limit` at a time: each batch waits until every
+/// dial in it connected or failed (or `DIAL_TIMEOUT` passed) before the next is sent, so
+/// a large run doesn't swamp its bootstraps. Returns the dials that didn't connect.
+async fn dial_bounded(
+    nodes: &[NodeHandle],
+    event_rxs: &mut [mpsc::Receiver<NodeEvent>],
+    listen_addrs: &[libp2p::Multiaddr],
+    dials: &[(usize, usize)],
+    limit: usize,
+) -> usize {
+    let mut failed = 0;
+    for batch in dials.chunks(limit.max(1)) {
+        let mut pending: Vec<(usize, libp2p::PeerId)> = Vec::with_capacity(batch.len());
+        for &(i, j) in batch {
+            let peer = nodes[j].peer_id;
+            let addr = listen_addrs[j].clone().with(libp2p::multiaddr::Protocol::P2p(peer));
+            if nodes[i].cmd.send(NodeCommand::Dial { addr }).await.is_ok() {
+                pending.push((i, peer));
+            } else {
+                failed += 1;
+            }
+        }
+        let deadline = Instant::now() + DIAL_TIMEOUT;
+        while let Some(&(i, _)) = pending.first() {
+            let outcome = match timeout_at(deadline, event_rxs[i].recv()).await {
+                Ok(Some(NodeEvent::Connected(peer))) => Some((peer, true)),
+                Ok(Some(NodeEvent::DialFailed { peer: Some(peer), error })) => {
+                    warn!(node = i, %peer, %error, "dial failed");
+                    Some((peer, false))
+                }
+                Ok(Some(_)) => None,
+                Ok(None) | Err(_) => {
+                    let stuck = pending.iter().filter(|(n, _)| *n == i).count();
+                    warn!(node = i, stuck, "dials got no answer in time");
+                    failed += stuck;
+                    pending.retain(|(n, _)| *n != i);
+                    continue;
+                }
+            };
+            if let Some((peer, connected)) = outcome {
+                if let Some(k) = pending.iter().position(|p| *p == (i, peer)) {
+                    pending.remove(k);
+                    failed += usize::from(!connected);
+                }
             }
         }
     }
+    failed
+}
 
-    // Generate final report
-    print_simulation_report(&summaries, peers, bad_peers);
+/// Indices of the `bad_peers` malicious nodes, sorted. Random selection skips the first
+/// `exclude` nodes (the bootstraps) when enough others remain, and is reproducible per seed.
+pub fn select_bad_peers(peers: usize, bad_peers: usize, selection: BadSelection, exclude: usize, seed: u64) -> Vec<usize> {
+    let bad_peers = bad_peers.min(peers);
+    match selection {
+        BadSelection::Prefix => (0..bad_peers).collect(),
+        BadSelection::Random => {
+            let skip = if peers.saturating_sub(exclude) >= bad_peers { exclude } else { 0 };
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut bad: Vec<usize> = sample(&mut rng, peers - skip, bad_peers)
+                .into_iter()
+                .map(|i| i + skip)
+                .collect();
+            bad.sort_unstable();
+            bad
+        }
+    }
+}
 
-    Ok(())
+/// Bootstrap indices each node dials at startup. The first `bootstrap_count` nodes (at least
+/// one) are bootstraps and dial the bootstraps before them; every other node dials a seeded
+/// random half (rounded up) of the bootstraps, so no single bootstrap carries the network.
+pub fn bootstrap_dials(peers: usize, bootstrap_count: usize, seed: u64) -> Vec<Vec<usize>> {
+    let bootstraps = bootstrap_count.clamp(1, peers.max(1));
+    let per_node = bootstraps.div_ceil(2);
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..peers)
+        .map(|i| {
+            if i < bootstraps {
+                (0..i).collect()
+            } else {
+                let mut targets = sample(&mut rng, bootstraps, per_node).into_vec();
+                targets.sort_unstable();
+                targets
+            }
+        })
+        .collect()
 }
 
-fn print_simulation_report(
-    summaries: &[(usize, crate::p2p::NodeSummary)],
-    total_peers: usize,
-    bad_peers: usize,
-) {
-    let honest_peers = total_peers - bad_peers;
+/// One honest node's validator scores, keyed by peer.
+pub type ScoreView = (libp2p::PeerId, HashMap<libp2p::PeerId, f64>);
+
+/// One round of score comparison: fetch every honest node's peer scores (all at once) and
+/// return `score_variance` over them. With `reported` (`--reputation-gossip`), also send the
+/// reports `plan_reputation_reports` picks.
+async fn reputation_round(
+    honest: &[(libp2p::PeerId, &NodeHandle)],
+    bad: &[libp2p::PeerId],
+    reported: Option<&mut HashMap<(libp2p::PeerId, libp2p::PeerId), f64>>,
+) -> Option<f64> {
+    let queries = honest.iter().map(|(id, n)| async move {
+        let (reply, rx) = oneshot::channel();
+        n.cmd.send(NodeCommand::QueryState { reply }).await.ok()?;
+        let state = timeout(Duration::from_secs(1), rx).await.ok()?.ok()?;
+        Some((*id, state.peer_scores.into_iter().map(|(p, score, _)| (p, score)).collect()))
+    });
+    let views: Vec<ScoreView> = futures::future::join_all(queries).await.into_iter().flatten().collect();
 
-    let mut total_accepted = 0;
-    let mut total_rejected = 0;
-    let mut total_ignored = 0;
-    let mut total_quarantined = 0;
+    if let Some(reported) = reported {
+        for (to, reporter, victim, delta) in plan_reputation_reports(&views, reported) {
+            if let Some((_, n)) = honest.iter().find(|(id, _)| *id == to) {
+                let _ = n.cmd.send(NodeCommand::ReputationReport { reporter, victim, delta }).await;
+            }
+        }
+    }
+    score_variance(&views, bad)
+}
+
+/// Reputation reports for one round, as `(recipient, reporter, victim, delta)`: each node's
+/// score changes since its last report (tracked in `reported`), sent to the other nodes
+/// whose view of the victim is higher, so witnesses who already agree don't push each other
+/// further down.
+pub fn plan_reputation_reports(
+    views: &[ScoreView],
+    reported: &mut HashMap<(libp2p::PeerId, libp2p::PeerId), f64>,
+) -> Vec<(libp2p::PeerId, libp2p::PeerId, libp2p::PeerId, f64)> {
+    let mut reports = Vec::new();
+    for (reporter, scores) in views {
+        for (victim, score) in scores {
+            let last = reported.insert((*reporter, *victim), *score).unwrap_or(0.0);
+            let delta = score - last;
+            if delta == 0.0 {
+                continue;
+            }
+            for (to, theirs) in views {
+                if to != reporter && to != victim && theirs.get(victim).copied().unwrap_or(0.0) > *score {
+                    reports.push((*to, *reporter, *victim, delta));
+                }
+            }
+        }
+    }
+    reports
+}
 
-    let mut honest_accepted = 0;
-    let mut honest_rejected = 0;
-    let mut honest_published = 0;
+/// Variance of each `bad` peer's score across `views`, averaged over `bad`; a view without
+/// the peer counts as scoring it 0. `None` without views or bad peers.
+pub fn score_variance(views: &[ScoreView], bad: &[libp2p::PeerId]) -> Option<f64> {
+    if views.is_empty() || bad.is_empty() {
+        return None;
+    }
+    let n = views.len() as f64;
+    let variance = bad
+        .iter()
+        .map(|peer| {
+            let scores: Vec<f64> = views.iter().map(|(_, s)| s.get(peer).copied().unwrap_or(0.0)).collect();
+            let mean = scores.iter().sum::<f64>() / n;
+            scores.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / n
+        })
+        .sum::<f64>()
+        / bad.len() as f64;
+    Some(variance)
+}
 
-    for (_idx, summary) in summaries {
-        total_accepted += summary.accepted;
-        total_rejected += summary.rejected;
-        total_ignored += summary.ignored;
-        total_quarantined += summary.quarantined_peers;
+/// Mean validator score of `bad` across `honest` nodes' views, and how many (node, bad peer)
+/// pairs are quarantined. `None` if no node reported on any bad peer.
+async fn sample_bad_peers(honest: &[&NodeHandle], bad: &[libp2p::PeerId]) -> Option<(f64, usize)> {
+    let (mut sum, mut seen, mut quarantined) = (0.0, 0usize, 0usize);
+    for n in honest {
+        let (reply, rx) = oneshot::channel();
+        if n.cmd.send(NodeCommand::QueryState { reply }).await.is_err() {
+            continue;
+        }
+        let Ok(Ok(state)) = timeout(Duration::from_secs(1), rx).await else { continue };
+        for (peer, score, q) in state.peer_scores {
+            if bad.contains(&peer) {
+                sum += score;
+                seen += 1;
+                quarantined += q as usize;
+            }
+        }
+    }
+    (seen > 0).then(|| (sum / seen as f64, quarantined))
+}
+
+/// Wire bytes for the `seq`th message published by node `node`. Bad nodes pick one of several
+/// attacks at random, each unique to avoid gossipsub dedupe; honest nodes send a node- and
+/// seq-specific payload.
+fn traffic_message(rng: &mut StdRng, node: usize, seq: u64, is_bad: bool, max_bytes: usize) -> Vec<u8> {
+    if is_bad {
+        attack_message(rng, max_bytes).1
+    } else {
+        // Fill with node-specific pattern
+        let payload = (0..100).map(|j| ((node + j + seq as usize) % 256) as u8).collect();
+        encode(&WireMessage::Good { seq, payload })
+    }
+}
+
+/// Node `idx`'s identity under `seed`: the Ed25519 key whose secret is sha256(seed || idx),
+/// so a run's peer ids, and everything ordered or reported by them, repeat with its seed.
+pub fn node_keypair(seed: u64, idx: usize) -> Keypair {
+    let mut hasher = Sha256::new();
+    hasher.update(seed.to_le_bytes());
+    hasher.update((idx as u64).to_le_bytes());
+    let mut secret: [u8; 32] = hasher.finalize().into();
+    Keypair::ed25519_from_bytes(&mut secret).expect("any 32 bytes are a valid ed25519 secret")
+}
+
+/// RNG for node `node`'s `--drop-rate` draws, separate from its traffic RNG so that enabling
+/// loss doesn't change which messages get generated.
+fn drop_rng(seed: u64, node: usize) -> StdRng {
+    StdRng::seed_from_u64(seed.wrapping_add(node as u64) ^ DROP_RNG_SALT)
+}
+
+/// Whether a publish is lost under `--drop-rate` (clamped to 0-1).
+fn lost(rng: &mut StdRng, drop_rate: f64) -> bool {
+    drop_rate > 0.0 && rng.gen_bool(drop_rate.min(1.0))
+}
+
+/// One bad-node message, picked at random from the attack mix, with its type.
+pub fn attack_message(rng: &mut StdRng, max_bytes: usize) -> (AttackType, Vec<u8>) {
+    let nonce: u64 = rng.gen();
+    match rng.gen_range(0..5) {
+        0 => {
+            // Pure junk (decode_error)
+            let mut junk = vec![0u8; rng.gen_range(100..500)];
+            rng.fill(&mut junk[..]);
+            (AttackType::Junk, junk)
+        }
+        1 => {
+            // Oversize payload
+            let payload_len = max_bytes + rng.gen_range(100..1000);
+            let mut payload = vec![0u8; payload_len];
+            rng.fill(&mut payload[..]);
+            (AttackType::Oversize, encode(&WireMessage::Good { seq: nonce, payload }))
+        }
+        2 => {
+            // Empty payload
+            (AttackType::EmptyPayload, encode(&WireMessage::Good { seq: nonce, payload: vec![] }))
+        }
+        3 => {
+            // Malicious marker
+            (AttackType::Malicious, encode(&WireMessage::Malicious))
+        }
+        _ => {
+            // Random junk
+            let mut junk = vec![0u8; rng.gen_range(50..200)];
+            rng.fill(&mut junk[..]);
+            (AttackType::Junk, junk)
+        }
+    }
+}
+
+/// `--no-network`: the same traffic fed straight into one validator per node on a simulated
+/// clock. Every message reaches every other node directly from its publisher, so there is
+/// no mesh, no gossipsub dedupe and no waiting; the run takes as long as validation does.
+fn run_offline(cli: &Cli, peers: usize, bad_peers: usize) -> anyhow::Result<SimReport> {
+    let duration = Duration::from_secs(cli.duration_secs);
+    let exclude = if cli.bad_excludes_bootstraps { cli.bootstrap_count } else { 0 };
+    let bad_indices: HashSet<usize> =
+        select_bad_peers(peers, bad_peers, cli.bad_selection, exclude, cli.seed).into_iter().collect();
+    let peer_ids: Vec<libp2p::PeerId> = (0..peers).map(|i| node_keypair(cli.seed, i).public().to_peer_id()).collect();
+    let primary = ValidatorConfig {
+        max_message_bytes: cli.max_message_bytes,
+        dedupe_enabled: !cli.no_dedupe,
+        message_domain: cli.message_domain.clone(),
+        ..Default::default()
+    };
+    let shadow = cli.shadow_penalty_scale.map(|factor| shadow_config(&primary, factor));
+    let mut validators: Vec<Validator> = peer_ids
+        .iter()
+        .map(|me| {
+            let mut v = Validator::new(ValidatorConfig { local_peer_id: Some(*me), ..primary.clone() });
+            v.freeze_clock();
+            v
+        })
+        .collect();
+    let mut shadows: Vec<Option<Validator>> = peer_ids
+        .iter()
+        .map(|me| {
+            let mut v = Validator::new(ValidatorConfig { local_peer_id: Some(*me), ..shadow.clone()? });
+            v.freeze_clock();
+            Some(v)
+        })
+        .collect();
+    let mut summaries: Vec<NodeSummary> = vec![NodeSummary::default(); peers];
+    if cli.shadow_penalty_scale.is_some() {
+        for s in &mut summaries {
+            s.shadow = Some(Box::default());
+        }
+    }
+
+    // every publish, in time order: (offset, node, seq)
+    let attack_ends = (cli.scenario == Scenario::Recovery).then_some(duration / 2);
+    let adapt_after = adapt_after(cli, duration);
+    let adaptive = |i: usize| cli.scenario == Scenario::Adaptive && bad_indices.contains(&i);
+    let mut schedule = Vec::new();
+    for i in 0..peers {
+        let is_bad = bad_indices.contains(&i);
+        let rate = if is_bad { cli.spam_per_sec } else { cli.publish_per_sec }.max(1);
+        let every = Duration::from_secs_f64(1.0 / rate as f64);
+        let stop = if is_bad { attack_ends.unwrap_or(duration) } else { duration };
+        let mut at = Duration::ZERO;
+        let mut seq = 0u64;
+        while at < stop {
+            seq += 1;
+            schedule.push((at, i, seq));
+            at += if adaptive(i) { AttackerState::Spamming.step(at, adapt_after).interval(rate) } else { every };
+        }
+    }
+    schedule.sort();
+
+    let mut rngs: Vec<StdRng> = (0..peers).map(|i| StdRng::seed_from_u64(cli.seed.wrapping_add(i as u64))).collect();
+    let mut drop_rngs: Vec<StdRng> = (0..peers).map(|i| drop_rng(cli.seed, i)).collect();
+    let mut dropped = 0;
+    let mut clock = Duration::ZERO;
+    let mut next_decay = Duration::from_secs(1);
+    let mut attack_reasons = BTreeMap::new();
+    for (at, i, seq) in schedule {
+        if let Some(factor) = cli.score_decay {
+            while next_decay <= at {
+                for v in validators.iter_mut().chain(shadows.iter_mut().flatten()) {
+                    v.decay_scores(factor);
+                    v.decay_offences();
+                }
+                next_decay += Duration::from_secs(1);
+            }
+        }
+        for v in validators.iter_mut().chain(shadows.iter_mut().flatten()) {
+            v.advance_clock(at - clock);
+        }
+        clock = at;
+
+        let is_bad = bad_indices.contains(&i);
+        let (attack, bytes) = if adaptive(i) {
+            let (attack, bytes) = AttackerState::Spamming.step(at, adapt_after).attack(&mut rngs[i], seq, cli.max_message_bytes);
+            (Some(attack), bytes)
+        } else if is_bad {
+            let (attack, bytes) = attack_message(&mut rngs[i], cli.max_message_bytes);
+            (Some(attack), bytes)
+        } else {
+            (None, traffic_message(&mut rngs[i], i, seq, false, cli.max_message_bytes))
+        };
+        if lost(&mut drop_rngs[i], cli.drop_rate) {
+            dropped += 1;
+            continue;
+        }
+        if !is_bad {
+            summaries[i].honest_published += 1;
+            summaries[i].published_ids.insert(message_id(&cli.message_domain, &bytes));
+        }
+        let author = peer_ids[i];
+        for (j, v) in validators.iter_mut().enumerate() {
+            if j == i {
+                continue;
+            }
+            let decision = v.validate(&author, Some(&author), &bytes);
+            if let Some(attack) = attack {
+                *attack_reasons.entry((attack, decision.reason.as_str())).or_insert(0) += 1;
+            }
+            let s = &mut summaries[j];
+            if let (Some(shadow), Some(tally)) = (shadows[j].as_mut(), s.shadow.as_mut()) {
+                tally.record(&shadow.validate(&author, Some(&author), &bytes), !is_bad);
+            }
+            match decision.acceptance {
+                libp2p::gossipsub::MessageAcceptance::Accept => {
+                    s.accepted += 1;
+                    s.honest_accepted += u64::from(!is_bad);
+                    s.accepted_ids.insert(message_id(&cli.message_domain, &bytes));
+                }
+                libp2p::gossipsub::MessageAcceptance::Reject => {
+                    s.rejected += 1;
+                    s.honest_rejected += u64::from(!is_bad);
+                }
+                libp2p::gossipsub::MessageAcceptance::Ignore => s.ignored += 1,
+            }
+        }
+    }
+
+    if let Some(idx) = cli.dump_node {
+        NodeDump::new(idx, &validators[idx].snapshot()).write(cli.dump_node_out.as_deref())?;
+    }
+    for ((s, v), shadow) in summaries.iter_mut().zip(&validators).zip(&shadows) {
+        if let (Some(shadow), Some(tally)) = (shadow, s.shadow.as_mut()) {
+            tally.quarantined_peers = shadow.get_quarantined_count() as u64;
+        }
+        s.quarantined_peers = v.get_quarantined_count() as u64;
+        s.quarantined_ids = v.peers_where(|_, _, status| status == PeerStatus::Quarantined).into_iter().collect();
+        s.peer_scores = v.dump_peer_states().into_iter().map(|(peer, score, _)| (peer, score)).collect();
+        s.classifications = v.classify_all();
+        s.throttle_stats = v.throttle_stats_all();
+        s.time_to_quarantine = v.time_to_quarantine_all();
+        s.time_to_first_accept = v.time_to_first_accept_all();
+        s.validate_p50_us = v.latency_histogram().percentile_us(0.5);
+        s.validate_p99_us = v.latency_histogram().percentile_us(0.99);
+    }
+    let summaries: Vec<(usize, NodeSummary)> = summaries.into_iter().enumerate().collect();
+    let bad_nodes: Vec<(usize, libp2p::PeerId)> =
+        (0..peers).filter(|i| bad_indices.contains(i)).map(|i| (i, peer_ids[i])).collect();
+    let consensus = QuarantineConsensus::compute(&summaries, &bad_nodes);
+    let unique_delivery = UniqueDelivery::compute(&summaries, &bad_nodes, cli.delivery_quorum);
+    let detection_latency = DetectionLatency::compute(&summaries, &bad_nodes);
+    let mesh_warmup = MeshWarmup::compute(&summaries, &bad_nodes);
+    let score_separation = ScoreSeparation::compute(&summaries, &bad_nodes);
+    let blind_detection = BlindDetection::compute(&summaries, &bad_nodes);
+    let throttling = ThrottlingSummary::compute(&summaries, &bad_nodes);
+    let mut report = build_report(summaries, peers, bad_peers);
+    report.seed = cli.seed;
+    report.quarantine_consensus = consensus;
+    report.unique_delivery = unique_delivery;
+    report.detection_latency = detection_latency;
+    report.mesh_warmup = mesh_warmup;
+    report.score_separation = score_separation;
+    report.blind_detection = blind_detection;
+    report.throttling = throttling;
+    report.attack_reasons = attack_reasons;
+    report.dropped_publishes = dropped;
+    Ok(report)
+}
+
+/// One node's validator state for `--dump-node`: its `ValidatorSnapshot` with peer ids
+/// spelled out, peers sorted by id.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct NodeDump {
+    pub node: usize,
+    pub taken_at_ms: u64,
+    pub peers: Vec<PeerDump>,
+    /// Authors with their last accepted sequence number, oldest first.
+    pub authors: Vec<AuthorDump>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PeerDump {
+    pub peer: String,
+    pub score: f64,
+    pub quarantined: bool,
+    pub offences: u32,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AuthorDump {
+    pub author: String,
+    pub last_seq: u64,
+}
+
+fn peer_string(bytes: &[u8]) -> String {
+    libp2p::PeerId::from_bytes(bytes).map_or_else(|_| hex::encode(bytes), |p| p.to_string())
+}
+
+impl NodeDump {
+    pub fn new(node: usize, snapshot: &ValidatorSnapshot) -> Self {
+        let mut peers: Vec<PeerDump> = snapshot
+            .peers
+            .iter()
+            .map(|p| PeerDump { peer: peer_string(&p.peer), score: p.score, quarantined: p.quarantined, offences: p.offences })
+            .collect();
+        peers.sort_by(|a, b| a.peer.cmp(&b.peer));
+        let authors = snapshot
+            .authors
+            .iter()
+            .map(|(author, seq)| AuthorDump { author: peer_string(author), last_seq: *seq })
+            .collect();
+        Self { node, taken_at_ms: snapshot.taken_at_ms, peers, authors }
+    }
+
+    /// Write as pretty-printed JSON to `out`, or to stdout.
+    pub fn write(&self, out: Option<&std::path::Path>) -> anyhow::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        match out {
+            Some(path) => std::fs::write(path, json + "\n").with_context(|| format!("writing {}", path.display()))?,
+            None => println!("{json}"),
+        }
+        Ok(())
+    }
+}
+
+/// Ask every node for its live state and sum the tallies. Nodes that don't answer
+/// promptly are skipped so a stuck node can't stall the run.
+async fn poll_progress(nodes: &[NodeHandle], elapsed: Duration) -> ProgressTally {
+    let mut tally = ProgressTally {
+        elapsed,
+        nodes_responding: 0,
+        accepted: 0,
+        rejected: 0,
+        ignored: 0,
+        quarantined_peers: 0,
+    };
+    for (i, n) in nodes.iter().enumerate() {
+        let (reply, rx) = oneshot::channel();
+        if n.cmd.send(NodeCommand::QueryState { reply }).await.is_err() {
+            continue;
+        }
+        match timeout(Duration::from_secs(1), rx).await {
+            Ok(Ok(state)) => {
+                tally.nodes_responding += 1;
+                tally.accepted += state.accepted;
+                tally.rejected += state.rejected;
+                tally.ignored += state.ignored;
+                tally.quarantined_peers += state.quarantined_peers;
+            }
+            _ => warn!(node = i, "no state reply for progress tally"),
+        }
+    }
+    tally
+}
+
+/// Read one summary per node, in index order. A node that exits without one or doesn't send
+/// it within `per_node` gets a `missing` placeholder, so one dead or stuck node can't stall
+/// the run.
+pub async fn collect_summaries(event_rxs: Vec<mpsc::Receiver<NodeEvent>>, per_node: Duration) -> Vec<(usize, NodeSummary)> {
+    let mut summaries = Vec::with_capacity(event_rxs.len());
+    for (i, mut rx) in event_rxs.into_iter().enumerate() {
+        let mut subscribe_failures = 0;
+        let mut unsupported_peers = 0;
+        let summary = timeout(per_node, async {
+            while let Some(ev) = rx.recv().await {
+                match ev {
+                    NodeEvent::Summary(s) => return Some(*s),
+                    NodeEvent::SubscribeFailed(error) => {
+                        warn!(node = i, ?error, "node failed to subscribe; it may be out of the mesh");
+                        subscribe_failures += 1;
+                    }
+                    NodeEvent::PeerUnsupported(peer) => {
+                        warn!(node = i, %peer, "peer does not speak gossipsub; it won't join the mesh");
+                        unsupported_peers += 1;
+                    }
+                    _ => {}
+                }
+            }
+            None
+        })
+        .await;
+        match summary {
+            Ok(Some(s)) => {
+                info!(node = i, ?s, "node summary");
+                summaries.push((i, NodeSummary { subscribe_failures, unsupported_peers, ..s }));
+            }
+            Ok(None) | Err(_) => {
+                warn!(node = i, "no summary from node; recording a placeholder");
+                summaries.push((i, NodeSummary { missing: true, ..Default::default() }));
+            }
+        }
+    }
+    summaries
+}
+
+/// When adaptive attackers switch to stealth: `--adapt-after-secs`, else halfway through.
+fn adapt_after(cli: &Cli, duration: Duration) -> Duration {
+    cli.adapt_after_secs.map_or(duration / 2, Duration::from_secs)
+}
+
+/// The shadow validator's policy: the primary's with every penalty scaled.
+pub fn shadow_config(primary: &ValidatorConfig, factor: f64) -> ValidatorConfig {
+    ValidatorConfig { penalties: primary.penalties.scaled(factor), ..primary.clone() }
+}
+
+/// Fold per-node summaries into network-wide totals.
+pub fn build_report(summaries: Vec<(usize, NodeSummary)>, total_peers: usize, bad_peers: usize) -> SimReport {
+    let mut report = SimReport {
+        total_peers,
+        bad_peers,
+        ..Default::default()
+    };
+
+    for (_idx, summary) in &summaries {
+        report.total_accepted += summary.accepted;
+        report.total_rejected += summary.rejected;
+        report.total_ignored += summary.ignored;
+        report.total_quarantined += summary.quarantined_peers;
 
         // Use the honest counters collected per-node (these are tracked by author).
-        honest_accepted += summary.honest_accepted;
-        honest_rejected += summary.honest_rejected;
-        honest_published += summary.honest_published;
+        report.honest_accepted += summary.honest_accepted;
+        report.honest_rejected += summary.honest_rejected;
+        report.honest_published += summary.honest_published;
+        report.validation_failures += summary.validation_failures;
+        report.shed_validations += summary.shed_validations;
+        report.no_source_messages += summary.no_source_messages;
+        report.paused_publishes += summary.paused_publishes;
+        report.publish_errors += summary.publish_errors;
+        report.subscribe_failures += summary.subscribe_failures;
+        report.unsupported_peers += summary.unsupported_peers;
+        report.missing_summaries += usize::from(summary.missing);
+        if let Some(shadow) = &summary.shadow {
+            report.shadow.get_or_insert_with(ShadowSummary::default).merge(shadow);
+        }
     }
+    report.unique_messages = summaries
+        .iter()
+        .flat_map(|(_, s)| &s.accepted_ids)
+        .collect::<HashSet<_>>()
+        .len();
+    report.summaries = summaries;
+    report
+}
 
-    let total_messages = total_accepted + total_rejected + total_ignored;
+fn print_simulation_report(report: &SimReport) {
+    let total_peers = report.total_peers;
+    let bad_peers = report.bad_peers;
+    let honest_peers = total_peers - bad_peers;
+
+    let total_accepted = report.total_accepted;
+    let total_rejected = report.total_rejected;
+    let total_ignored = report.total_ignored;
+    let total_quarantined = report.total_quarantined;
+
+    let honest_accepted = report.honest_accepted;
+    let honest_rejected = report.honest_rejected;
+
+    let total_messages = report.total_messages();
     let acceptance_rate = if total_messages > 0 {
         (total_accepted as f64 / total_messages as f64) * 100.0
     } else {
         0.0
     };
 
-    let rejection_rate = if total_messages > 0 {
-        (total_rejected as f64 / total_messages as f64) * 100.0
-    } else {
-        0.0
-    };
+    let rejection_rate = report.rejection_rate();
 
     // Calculate honest success rate: honest messages accepted vs total honest messages processed
     let total_honest_messages = honest_accepted + honest_rejected;
-    let honest_success_rate = if total_honest_messages > 0 {
-        100.0 * (honest_accepted as f64) / (total_honest_messages as f64)
-    } else {
-        0.0
-    };
+    let honest_success_rate = report.honest_success_rate();
 
     println!("\n=== SIMULATION SUMMARY ===");
     println!(
         "Total Peers: {} (Honest: {}, Bad: {})",
         total_peers, honest_peers, bad_peers
     );
+    println!("Seed: {}", report.seed);
     println!("Total Messages: {}", total_messages);
+    println!("Unique Messages Accepted: {}", report.unique_messages);
     println!("  - Accepted: {} ({:.1}%)", total_accepted, acceptance_rate);
     println!("  - Rejected: {} ({:.1}%)", total_rejected, rejection_rate);
     println!(
@@ -284,15 +1648,162 @@ fn print_simulation_report(
         "Honest Message Success Rate: {:.1}% ({}/{} honest messages accepted/processed)",
         honest_success_rate, honest_accepted, total_honest_messages
     );
+    if let Some(delivered) = report.unique_delivery.percent() {
+        let u = &report.unique_delivery;
+        println!(
+            "Unique Honest Delivery: {:.1}% ({}/{} distinct honest messages accepted by >= {} honest nodes)",
+            delivered, u.delivered, u.published, u.quorum
+        );
+    }
     println!("Quarantined Peers: {}", total_quarantined);
+    if let Some(consensus) = report.quarantine_consensus.percent() {
+        println!(
+            "Quarantine Consensus: {:.1}% (bad peers quarantined across {} honest nodes)",
+            consensus, report.quarantine_consensus.honest_nodes
+        );
+    }
+    for (peer, mean, nodes) in &report.detection_latency.per_peer {
+        println!("Time to Quarantine: {peer} after {:.2}s on average ({nodes} honest nodes)", mean.as_secs_f64());
+    }
+    if let (Some(p50), Some(p90), Some(max)) =
+        (report.mesh_warmup.percentile(0.5), report.mesh_warmup.percentile(0.9), report.mesh_warmup.percentile(1.0))
+    {
+        println!(
+            "Time to First Accept: p50 {:.2}s, p90 {:.2}s, max {:.2}s ({} honest node/author pairs)",
+            p50.as_secs_f64(),
+            p90.as_secs_f64(),
+            max.as_secs_f64(),
+            report.mesh_warmup.samples.len()
+        );
+    }
+    let sep = &report.score_separation;
+    if let (Some(honest), Some(bad), Some(gap), Some(auc)) = (sep.honest_mean(), sep.bad_mean(), sep.gap(), sep.auc()) {
+        println!(
+            "Score Separation: honest peers {honest:.1} vs bad peers {bad:.1} on average (gap {gap:.1}, AUC {auc:.2}; {} / {} samples)",
+            sep.honest.len(),
+            sep.bad.len()
+        );
+    }
+    println!("Rate Limiter: {} messages throttled", report.throttling.total_throttled);
+    if let Some((throttled, passed)) = report.throttling.bad_fractions() {
+        println!(
+            "  - Bad peers: {:.1}% of attempts throttled, {:.1}% reached content validation",
+            throttled, passed
+        );
+    }
+    if !report.attack_reasons.is_empty() {
+        println!("Attack Types Caught:");
+        let mut attacks: Vec<AttackType> = report.attack_reasons.keys().map(|(attack, _)| *attack).collect();
+        attacks.dedup();
+        for attack in attacks {
+            let reasons: Vec<String> = report
+                .attack_reasons
+                .iter()
+                .filter(|((a, _), _)| *a == attack)
+                .map(|((_, reason), n)| format!("{reason}={n}"))
+                .collect();
+            println!("  - {}: {}", attack.as_str(), reasons.join(", "));
+        }
+    }
+    if let Some(accuracy) = report.blind_detection.accuracy() {
+        let d = &report.blind_detection;
+        println!(
+            "Blind Detection: {:.1}% accurate ({} bad / {} honest flagged malicious, {} bad missed)",
+            accuracy, d.true_positives, d.false_positives, d.false_negatives
+        );
+    }
+    if report.missing_summaries > 0 {
+        println!("WARN: {} node(s) sent no summary; their counts are missing", report.missing_summaries);
+    }
+    if report.summaries.len() > report.missing_summaries {
+        let n = (report.summaries.len() - report.missing_summaries) as f64;
+        let mesh: usize = report.summaries.iter().map(|(_, s)| s.mesh_peer_count).sum();
+        let connected: usize = report.summaries.iter().map(|(_, s)| s.connected_peer_count).sum();
+        // nothing to say about the mesh without a network (`--no-network`)
+        if connected > 0 {
+            println!(
+                "Mesh Health: {:.1} mesh peers / {:.1} connected per node",
+                mesh as f64 / n,
+                connected as f64 / n
+            );
+        }
+        let p50 = report.summaries.iter().map(|(_, s)| s.validate_p50_us).max().unwrap_or(0);
+        let p99 = report.summaries.iter().map(|(_, s)| s.validate_p99_us).max().unwrap_or(0);
+        println!("Validate Latency (worst node): p50 <= {}us, p99 <= {}us", p50, p99);
+    }
+    if report.no_source_messages > 0 {
+        println!("Messages Without Source (attributed to forwarder): {}", report.no_source_messages);
+    }
+    if report.paused_publishes > 0 {
+        println!("Publishes Paused (too few mesh peers): {}", report.paused_publishes);
+    }
+    if report.failed_dials > 0 {
+        println!("Failed Dials: {}", report.failed_dials);
+    }
+    if report.dropped_publishes > 0 {
+        println!("Dropped Publishes (--drop-rate): {}", report.dropped_publishes);
+    }
+    if report.subscribe_failures > 0 {
+        println!("Subscribe Failures: {} (affected nodes may have been out of the mesh)", report.subscribe_failures);
+    }
+    if report.unsupported_peers > 0 {
+        println!("Unsupported Peers: {} (connected peers without gossipsub)", report.unsupported_peers);
+    }
+    if report.publish_errors > 0 {
+        println!("Publish Errors: {}", report.publish_errors);
+    }
+    if report.validation_failures > 0 {
+        println!("Validation Failures (reported as Ignore): {}", report.validation_failures);
+    }
+    if report.shed_validations > 0 {
+        println!("Validations Shed (backlog full, reported as Ignore): {}", report.shed_validations);
+    }
+    if let Some(shadow) = &report.shadow {
+        let honest = shadow.honest_accepted + shadow.honest_rejected;
+        let c = &shadow.counters;
+        println!(
+            "Shadow Policy: accepted {} / rejected {} / ignored {} (primary {} / {} / {}), honest success {:.1}% (primary {:.1}%), quarantined {} (primary {})",
+            c.accepted, c.rejected, c.ignored,
+            total_accepted, total_rejected, total_ignored,
+            if honest > 0 { 100.0 * shadow.honest_accepted as f64 / honest as f64 } else { 0.0 },
+            honest_success_rate,
+            shadow.quarantined_peers, total_quarantined,
+        );
+    }
+    if let (Some(mean), Some(last)) = (report.score_agreement.mean_variance(), report.score_agreement.final_variance()) {
+        println!("Score Agreement: bad peer score variance across honest nodes {mean:.1} on average, {last:.1} at the end");
+    }
+    if let Some(r) = &report.recovery {
+        println!(
+            "Recovery: attack stopped at {:.1}s; bad peer score peaked at {:.1} ({:.1}s), ended at {:.1}",
+            r.switchover.as_secs_f64(),
+            r.peak_score,
+            r.peak_at.as_secs_f64(),
+            r.final_score
+        );
+        match r.recovered_after {
+            Some(after) => println!("  - Quarantines lifted {:.1}s after the attack stopped", after.as_secs_f64()),
+            None => println!("  - Still quarantined at the end of the run"),
+        }
+    }
 
-    let _outcome = if honest_success_rate >= 90.0 && rejection_rate >= 70.0 {
-        "SUCCESS: Honest messages delivered, spam mostly rejected"
-    } else if honest_success_rate >= 80.0 {
-        "PARTIAL: Good honest delivery but spam not well filtered"
-    } else {
-        "FAILURE: Poor message filtering performance"
-    };
+    if !report.sla_checks.is_empty() {
+        println!("SLA: {}", if report.sla_passed() { "PASS" } else { "FAIL" });
+        for c in &report.sla_checks {
+            let Some(actual) = c.actual else {
+                println!("  - {}: N/A", c.name);
+                continue;
+            };
+            println!(
+                "  - {}: {:.1} {} {:.1} {}",
+                c.name,
+                actual,
+                if c.at_least { ">=" } else { "<=" },
+                c.limit,
+                if c.passed { "PASS" } else { "FAIL" }
+            );
+        }
+    }
 
     println!("========================\n");
 }
@@ -0,0 +1,14 @@
+//! Helpers for tests and fixtures (enabled by the `testing` feature).
+
+use libp2p::identity::Keypair;
+use libp2p::PeerId;
+use sha2::{Digest, Sha256};
+
+/// A stable peer id derived from `seed`: the id of the Ed25519 identity whose secret key is
+/// the SHA-256 of the seed. The same seed always gives the same peer, so failing cases can
+/// be reproduced and tests can rely on peer ordering.
+pub fn deterministic_peer_id(seed: u64) -> PeerId {
+    let mut secret: [u8; 32] = Sha256::digest(seed.to_le_bytes()).into();
+    let key = Keypair::ed25519_from_bytes(&mut secret).expect("any 32 bytes are a valid ed25519 secret");
+    key.public().to_peer_id()
+}
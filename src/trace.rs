@@ -0,0 +1,133 @@
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Context;
+use libp2p::PeerId;
+
+use crate::audit::AuditRecord;
+use crate::cli::Cli;
+use crate::validator::{Validator, ValidatorConfig};
+
+/// First bytes of every trace file.
+pub const TRACE_MAGIC: &[u8; 8] = b"GSTRACE1";
+
+/// One captured message as a node received it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceRecord {
+    pub propagation_source: PeerId,
+    pub author: Option<PeerId>,
+    /// Arrival time in milliseconds; any epoch works, only the gaps matter on replay.
+    pub timestamp_ms: u64,
+    pub bytes: Vec<u8>,
+}
+
+/// Write `records` as a trace: `TRACE_MAGIC`, then each record as a little-endian `u32`
+/// length followed by that many bytes of `[u8 len][source peer id][u8 len][author peer id,
+/// empty if none][u64 LE timestamp_ms][frame bytes to the end]`.
+pub fn write_trace(mut w: impl Write, records: &[TraceRecord]) -> io::Result<()> {
+    w.write_all(TRACE_MAGIC)?;
+    for r in records {
+        let source = r.propagation_source.to_bytes();
+        let author = r.author.map(|a| a.to_bytes()).unwrap_or_default();
+        let mut body = Vec::with_capacity(2 + source.len() + author.len() + 8 + r.bytes.len());
+        for id in [&source, &author] {
+            let len = u8::try_from(id.len()).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "peer id too long"))?;
+            body.push(len);
+            body.extend_from_slice(id);
+        }
+        body.extend_from_slice(&r.timestamp_ms.to_le_bytes());
+        body.extend_from_slice(&r.bytes);
+        let len = u32::try_from(body.len()).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "record too long"))?;
+        w.write_all(&len.to_le_bytes())?;
+        w.write_all(&body)?;
+    }
+    Ok(())
+}
+
+/// Read a whole trace written by `write_trace`, in file order.
+pub fn read_trace(mut r: impl Read) -> io::Result<Vec<TraceRecord>> {
+    let mut data = Vec::new();
+    r.read_to_end(&mut data)?;
+    let mut rest = data.strip_prefix(TRACE_MAGIC).ok_or_else(|| invalid("not a trace file"))?;
+    let mut records = Vec::new();
+    while !rest.is_empty() {
+        let len = u32::from_le_bytes(take(&mut rest, 4)?.try_into().unwrap()) as usize;
+        let mut body = take(&mut rest, len)?;
+        let source = take_peer(&mut body)?.ok_or_else(|| invalid("record without a propagation source"))?;
+        let author = take_peer(&mut body)?;
+        let timestamp_ms = u64::from_le_bytes(take(&mut body, 8)?.try_into().unwrap());
+        records.push(TraceRecord { propagation_source: source, author, timestamp_ms, bytes: body.to_vec() });
+    }
+    Ok(records)
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+fn take<'a>(buf: &mut &'a [u8], n: usize) -> io::Result<&'a [u8]> {
+    if buf.len() < n {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "trace ends mid-record"));
+    }
+    let (head, tail) = buf.split_at(n);
+    *buf = tail;
+    Ok(head)
+}
+
+// a `[u8 len][peer id]` field; length 0 means none
+fn take_peer(buf: &mut &[u8]) -> io::Result<Option<PeerId>> {
+    let len = take(buf, 1)?[0] as usize;
+    if len == 0 {
+        return Ok(None);
+    }
+    PeerId::from_bytes(take(buf, len)?).map(Some).map_err(|_| invalid("invalid peer id"))
+}
+
+/// Feed `records` through `validator` in timestamp order (ties keep file order), on a frozen
+/// clock advanced by the gaps between them. Returns one audit record per decision, stamped
+/// with the trace timestamp.
+pub fn replay(validator: &mut Validator, mut records: Vec<TraceRecord>) -> Vec<AuditRecord> {
+    records.sort_by_key(|r| r.timestamp_ms);
+    validator.freeze_clock();
+    let mut clock = records.first().map_or(0, |r| r.timestamp_ms);
+    records
+        .iter()
+        .map(|r| {
+            validator.advance_clock(Duration::from_millis(r.timestamp_ms - clock));
+            clock = r.timestamp_ms;
+            let decision = validator.validate(&r.propagation_source, r.author.as_ref(), &r.bytes);
+            let blamed = r.author.unwrap_or(r.propagation_source);
+            AuditRecord {
+                timestamp_ms: u128::from(r.timestamp_ms),
+                ..AuditRecord::new(
+                    0,
+                    &r.propagation_source,
+                    r.author.as_ref(),
+                    &decision,
+                    validator.get_peer_score(&blamed),
+                    validator.get_peer_score(&r.propagation_source),
+                )
+            }
+        })
+        .collect()
+}
+
+/// `--replay-trace`: replay the trace at `path` through a validator configured from `cli`
+/// and print the decision log to stdout as JSON lines.
+pub fn replay_file(cli: &Cli, path: &Path) -> anyhow::Result<()> {
+    let file = std::fs::File::open(path).with_context(|| format!("opening trace {}", path.display()))?;
+    let records = read_trace(io::BufReader::new(file)).with_context(|| format!("reading trace {}", path.display()))?;
+    let mut validator = Validator::new(ValidatorConfig {
+        max_message_bytes: cli.max_message_bytes,
+        dedupe_enabled: !cli.no_dedupe,
+        message_domain: cli.message_domain.clone(),
+        ..Default::default()
+    });
+    let mut out = io::stdout().lock();
+    for record in replay(&mut validator, records) {
+        serde_json::to_writer(&mut out, &record)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
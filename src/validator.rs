@@ -5,43 +5,575 @@ const TOKEN_BUCKET_CAPACITY: u32 = 100;
 const TOKEN_REFILL_RATE: f64 = 50.0; // tokens per second
 // Lower quarantine threshold so attackers are removed faster
 const QUARANTINE_THRESHOLD: f64 = -25.0;
+// Offences beyond this force quarantine regardless of score
+const MAX_OFFENCES: u32 = 4;
+// Penalties remembered per peer for `explain`
+const RECENT_PENALTIES: usize = 8;
+// Upper bounds of the peer score histogram buckets in `to_prometheus_text`
+const SCORE_BUCKETS: [f64; 8] = [-100.0, -50.0, -25.0, -10.0, -1.0, 0.0, 10.0, 50.0];
 
-use std::collections::{HashMap, VecDeque, HashSet};
-use std::time::Instant;
+use std::collections::{BTreeMap, HashMap, VecDeque, HashSet};
+use std::fmt::Write;
+use std::net::IpAddr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use libp2p::gossipsub::MessageAcceptance;
 use libp2p::PeerId;
-use sha2::{Digest, Sha256};
+use serde::{Deserialize, Serialize, Serializer};
 
-use crate::codec::{decode, WireMessage};
+use crate::codec::{content_hash, decode, decode_strict, decompress, encoded_len, CodecError, WireMessage, DEFAULT_MESSAGE_DOMAIN};
+use crate::metrics::LatencyHistogram;
 
 const MAX_PEERS: usize = 1000;
+const MAX_TRACKED_AUTHORS: usize = 10_000;
+
+// Rough per-entry memory (key, value and table overhead) used to split `memory_budget_bytes`.
+const PEER_ENTRY_BYTES: usize = 512;
+const DEDUPE_ENTRY_BYTES: usize = 64;
+const AUTHOR_ENTRY_BYTES: usize = 96;
 
 #[derive(Debug, Clone)]
 pub struct ValidatorConfig {
     pub max_message_bytes: usize,
+    /// Peers scoring at or below this are quarantined.
+    pub quarantine_threshold: f64,
+    /// A quarantined peer is only released once its score climbs above this; `None` (or
+    /// anything below `quarantine_threshold`) releases it as soon as it's back over the
+    /// entry threshold. A band between the two keeps peers near the edge from flapping.
+    pub quarantine_exit_threshold: Option<f64>,
+    /// Capacity of the content-hash dedupe cache (FIFO).
+    pub max_dedupe_entries: usize,
+    /// Drop repeats of already-seen content as `duplicate`. When off every message is
+    /// treated as novel (replay protection still applies).
+    pub dedupe_enabled: bool,
+    /// Messages a forwarder may send back-to-back before throttling kicks in (token bucket
+    /// capacity). Independent of `sustained_rate`.
+    pub burst_capacity: u32,
+    /// Long-run messages per second a forwarder may sustain (token refill rate).
+    pub sustained_rate: f64,
+    /// A second token bucket per author, so a prolific author can't escape throttling by
+    /// spreading its messages over many relays. A message must pass both buckets. `None`
+    /// only limits forwarders.
+    pub author_rate_limit: Option<AuthorRateLimit>,
+    pub penalties: PenaltyConfig,
+    pub reputation: ReputationConfig,
+    /// Score subtracted from the forwarder on every `Ignore` decision (duplicates, replays),
+    /// unless it is already quarantined. Zero keeps ignores penalty-free.
+    pub ignore_penalty: f64,
+    /// Capacity of the per-author sequence table used for replay protection; when full the
+    /// least recently active author is forgotten. Independent of the forwarder scoring map so
+    /// forwarder churn cannot evict replay state.
+    pub max_tracked_authors: usize,
+    /// Capacity of the forwarder scoring map; when full the most idle peer is evicted.
+    pub max_peers: usize,
+    /// Size the peer map, dedupe cache and author table from this one budget at construction,
+    /// overriding `max_peers`, `max_dedupe_entries` and `max_tracked_authors` while keeping
+    /// their default proportions. Based on rough per-entry estimates, so only approximate.
+    pub memory_budget_bytes: Option<usize>,
+    /// Duplicates forwarded by peers already scoring below this are rejected (charging
+    /// `duplicate_penalty`) rather than ignored. `None` ignores every duplicate.
+    pub reject_duplicates_below: Option<f64>,
+    /// Score subtracted for a rejected duplicate.
+    pub duplicate_penalty: f64,
+    /// Reject messages with bytes left over after a valid `WireMessage` (`trailing_bytes`,
+    /// decode-error penalty) instead of ignoring the excess.
+    pub strict_decode: bool,
+    /// Charge a heavier penalty to forwarders that keep hitting the rate limit. `None` leaves
+    /// every throttled message a separate small offence.
+    pub rate_limit_escalation: Option<RateLimitEscalation>,
+    /// Nudge down authors whose publish intervals are too fast and too regular to be human.
+    /// `None` disables the heuristic.
+    pub interval_anomaly: Option<IntervalAnomalyConfig>,
+    /// Nudge down authors that flood small, well-formed messages which pass every content
+    /// check. `None` accepts them without cost.
+    pub well_formed_flood: Option<WellFormedFloodConfig>,
+    /// Reject (rather than ignore) replays from authors that keep sending old sequence
+    /// numbers. `None` ignores every replay penalty-free.
+    pub replay_policy: Option<ReplayPolicy>,
+    /// Validate messages relayed by a quarantined forwarder on their content when the author
+    /// is someone else in good standing, so a bad relay can't censor honest authors. Off
+    /// ignores everything a quarantined forwarder sends.
+    pub accept_via_quarantined_forwarder: bool,
+    /// Include up to this many of the newest dedupe keys in `snapshot`; 0 leaves them out.
+    pub max_persisted_dedupe: usize,
+    /// Dedupe keys at least this old are dropped by `restore`.
+    pub dedupe_ttl: Duration,
+    /// Peers first seen less than this long ago are penalized as usual but not quarantined;
+    /// the flip happens on their first message after the grace period if still deserved.
+    pub min_age_before_quarantine: Duration,
+    /// Soften decode errors from newly seen peers, so a rolling upgrade's wire-format skew
+    /// doesn't wreck honest reputations. `None` charges them in full.
+    pub decode_error_grace: Option<DecodeErrorGrace>,
+    /// Domain separator hashed in front of message bytes for dedupe keys. Must match the
+    /// behaviour's message id domain (see `codec::content_hash`).
+    pub message_domain: String,
+    /// This node's own peer id. Messages it forwarded or authored (gossipsub echoing our
+    /// publishes back) skip rate limiting and never cost it score.
+    pub local_peer_id: Option<PeerId>,
+    /// Fraction of an offence forgiven for each accepted message a peer authors, so trust is
+    /// earned back by useful traffic rather than time (compare `decay_offences`). 0 disables.
+    pub offences_forgiven_per_accept: f64,
+    /// Lowest score handed to gossipsub as the application score. Gossipsub multiplies it by
+    /// `app_specific_weight`, so an unbounded score would override its own delivery-based
+    /// scoring. `None` passes the score through unclamped.
+    pub app_score_floor: Option<f64>,
+    /// Put peers at or below a score on probation, a step short of quarantine: their token
+    /// bucket shrinks and their app score drops until the score recovers.
+    pub probation: Option<ProbationConfig>,
+    /// Lines used by `Validator::classify`.
+    pub classification: ClassificationThresholds,
+    /// Shadow mode for trialling a scoring setup: decisions keep their reasons and intended
+    /// `score_delta`s, but no score or offence is applied, nobody is quarantined and rejected
+    /// messages are accepted. Can be flipped at runtime via `update_config`.
+    pub observe_only: bool,
+    /// UNSAFE, for testing only: non-accept decisions whose reason is listed here are
+    /// returned as `Accept` anyway (keeping their reason and penalty), e.g. `DecodeError` to
+    /// see how gossipsub forwards junk. Every override is logged at warn level. Empty by
+    /// default; never set this on a real node.
+    pub accept_despite: HashSet<RejectReason>,
+    /// Tie reputation to the remote IP address reported through `note_peer_address`, so a
+    /// peer that reconnects under a new key from the same address inherits the lower of
+    /// its own score and the address's last known score. Off by default: peers behind one
+    /// NAT (or every node of a localhost simulation) would share a score.
+    pub bind_reputation_to_address: bool,
+}
+
+impl Default for ValidatorConfig {
+    fn default() -> Self {
+        Self {
+            max_message_bytes: 16384,
+            quarantine_threshold: QUARANTINE_THRESHOLD,
+            quarantine_exit_threshold: None,
+            max_dedupe_entries: MAX_DEDUPE_SIZE,
+            dedupe_enabled: true,
+            burst_capacity: TOKEN_BUCKET_CAPACITY,
+            sustained_rate: TOKEN_REFILL_RATE,
+            author_rate_limit: None,
+            penalties: PenaltyConfig::default(),
+            reputation: ReputationConfig::default(),
+            ignore_penalty: 0.0,
+            max_tracked_authors: MAX_TRACKED_AUTHORS,
+            max_peers: MAX_PEERS,
+            memory_budget_bytes: None,
+            reject_duplicates_below: None,
+            duplicate_penalty: 2.0,
+            strict_decode: false,
+            rate_limit_escalation: None,
+            interval_anomaly: None,
+            well_formed_flood: None,
+            replay_policy: None,
+            accept_via_quarantined_forwarder: false,
+            max_persisted_dedupe: 0,
+            dedupe_ttl: Duration::from_secs(120),
+            min_age_before_quarantine: Duration::ZERO,
+            decode_error_grace: None,
+            message_domain: DEFAULT_MESSAGE_DOMAIN.to_string(),
+            local_peer_id: None,
+            offences_forgiven_per_accept: 0.0,
+            app_score_floor: None,
+            probation: None,
+            classification: ClassificationThresholds::default(),
+            observe_only: false,
+            accept_despite: HashSet::new(),
+            bind_reputation_to_address: false,
+        }
+    }
+}
+
+impl ValidatorConfig {
+    /// Whether a peer at `score` is (still) below the quarantine line: the entry threshold
+    /// for free peers, the exit threshold for ones already quarantined.
+    fn below_quarantine_line(&self, was_quarantined: bool, score: f64) -> bool {
+        let line = match self.quarantine_exit_threshold {
+            Some(exit) if was_quarantined => exit.max(self.quarantine_threshold),
+            _ => self.quarantine_threshold,
+        };
+        score <= line
+    }
+
+    /// Resolve `memory_budget_bytes`, if set, into the individual capacities.
+    fn apply_memory_budget(&mut self) {
+        let Some(budget) = self.memory_budget_bytes else { return };
+        let default_bytes =
+            MAX_PEERS * PEER_ENTRY_BYTES + MAX_DEDUPE_SIZE * DEDUPE_ENTRY_BYTES + MAX_TRACKED_AUTHORS * AUTHOR_ENTRY_BYTES;
+        let scale = budget as f64 / default_bytes as f64;
+        let sized = |default: usize| ((default as f64 * scale) as usize).max(1);
+        self.max_peers = sized(MAX_PEERS);
+        self.max_dedupe_entries = sized(MAX_DEDUPE_SIZE);
+        self.max_tracked_authors = sized(MAX_TRACKED_AUTHORS);
+    }
+}
+
+/// Base score deltas for each kind of violation (negative; scaled by repeat offences).
+#[derive(Debug, Clone)]
+pub struct PenaltyConfig {
+    pub oversize: f64,
+    pub rate_limited: f64,
+    pub decode_error: f64,
+    /// Input that ends mid-message: as likely a transport hiccup as an attack.
+    pub truncated: f64,
+    /// A payload length prefix beyond `codec::MAX_PAYLOAD_BYTES`: an allocation probe.
+    pub too_large: f64,
+    pub empty_payload: f64,
+    pub malicious_payload: f64,
+}
+
+impl Default for PenaltyConfig {
+    fn default() -> Self {
+        Self {
+            oversize: -60.0,
+            // gentle penalty for short bursts; don't kill honest forwarders
+            rate_limited: -5.0,
+            decode_error: -30.0,
+            truncated: -10.0,
+            too_large: -60.0,
+            empty_payload: -30.0,
+            malicious_payload: -80.0,
+        }
+    }
+}
+
+impl PenaltyConfig {
+    /// Every penalty multiplied by `factor`.
+    pub fn scaled(&self, factor: f64) -> Self {
+        Self {
+            oversize: self.oversize * factor,
+            rate_limited: self.rate_limited * factor,
+            decode_error: self.decode_error * factor,
+            truncated: self.truncated * factor,
+            too_large: self.too_large * factor,
+            empty_payload: self.empty_payload * factor,
+            malicious_payload: self.malicious_payload * factor,
+        }
+    }
+}
+
+/// Closed-loop penalty tuning toward a target honest acceptance rate. Each `step` compares
+/// the rate observed since the previous step with `target` and scales all penalties by
+/// `1 - gain` when honest messages are rejected too often, or `1 + gain` when there is
+/// headroom, staying within `min_scale..=max_scale` of the base penalties.
+#[derive(Debug, Clone)]
+pub struct AcceptanceController {
+    pub target: f64,
+    pub gain: f64,
+    pub min_scale: f64,
+    pub max_scale: f64,
+    base: PenaltyConfig,
+    scale: f64,
+}
+
+impl AcceptanceController {
+    pub fn new(target: f64, base: PenaltyConfig) -> Self {
+        Self {
+            target: target.clamp(0.0, 1.0),
+            gain: 0.1,
+            min_scale: 0.25,
+            max_scale: 4.0,
+            base,
+            scale: 1.0,
+        }
+    }
+
+    /// Current multiplier on the base penalties.
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Feed the honest decisions made since the last step; returns the penalties to use
+    /// from now on, or `None` if there was no honest traffic to judge by.
+    pub fn step(&mut self, honest_accepted: u64, honest_rejected: u64) -> Option<PenaltyConfig> {
+        let total = honest_accepted + honest_rejected;
+        if total == 0 {
+            return None;
+        }
+        let rate = honest_accepted as f64 / total as f64;
+        if rate < self.target {
+            self.scale *= 1.0 - self.gain;
+        } else if rate > self.target {
+            self.scale *= 1.0 + self.gain;
+        }
+        self.scale = self.scale.clamp(self.min_scale, self.max_scale);
+        Some(self.base.scaled(self.scale))
+    }
+}
+
+/// Where `Validator::classify` draws its lines. A peer crossing any malicious line is
+/// `Malicious`; otherwise crossing any suspicious line makes it `Suspicious`. The acceptance
+/// ratio is only judged once `min_judged` of its messages were accepted or rejected.
+#[derive(Debug, Clone)]
+pub struct ClassificationThresholds {
+    pub suspicious_score: f64,
+    pub malicious_score: f64,
+    pub suspicious_offences: u32,
+    pub malicious_offences: u32,
+    pub suspicious_acceptance: f64,
+    pub malicious_acceptance: f64,
+    pub min_judged: u64,
+}
+
+impl Default for ClassificationThresholds {
+    fn default() -> Self {
+        Self {
+            suspicious_score: -5.0,
+            malicious_score: QUARANTINE_THRESHOLD,
+            suspicious_offences: 1,
+            malicious_offences: 3,
+            suspicious_acceptance: 0.9,
+            malicious_acceptance: 0.5,
+            min_judged: 5,
+        }
+    }
+}
+
+/// A peer's standing judged from its behaviour alone (see `Validator::classify`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Classification {
+    Honest,
+    Suspicious,
+    Malicious,
+}
+
+/// Token bucket applied per author (see `ValidatorConfig::author_rate_limit`). A drained
+/// bucket rejects the message as `rate_limited` and charges the author, not the relay.
+#[derive(Debug, Clone)]
+pub struct AuthorRateLimit {
+    pub burst_capacity: u32,
+    pub sustained_rate: f64,
+}
+
+impl Default for AuthorRateLimit {
+    fn default() -> Self {
+        Self {
+            burst_capacity: TOKEN_BUCKET_CAPACITY,
+            sustained_rate: TOKEN_REFILL_RATE,
+        }
+    }
+}
+
+/// Fast path to quarantine for sustained floods: every `hits` consecutive rate-limited
+/// messages within `window` charge `penalty` (negative, offence-scaled) instead of the usual
+/// rate-limit penalty. Any accepted message from the forwarder resets the streak.
+#[derive(Debug, Clone)]
+pub struct RateLimitEscalation {
+    pub hits: u32,
+    pub window: Duration,
+    pub penalty: f64,
+}
+
+impl Default for RateLimitEscalation {
+    fn default() -> Self {
+        Self {
+            hits: 20,
+            window: Duration::from_secs(1),
+            penalty: -40.0,
+        }
+    }
+}
+
+/// Up to `max_replays` replays per author within `window` are ignored as honest
+/// retransmits; beyond that each replay is rejected with `penalty` (negative, offence-scaled).
+#[derive(Debug, Clone)]
+pub struct ReplayPolicy {
+    pub max_replays: u32,
+    pub window: Duration,
+    pub penalty: f64,
+}
+
+impl Default for ReplayPolicy {
+    fn default() -> Self {
+        Self {
+            max_replays: 10,
+            window: Duration::from_secs(10),
+            penalty: -10.0,
+        }
+    }
+}
+
+/// Periodic forgiveness, applied by the node every `interval`: scores are multiplied by
+/// `score_factor` (drifting back toward zero) and each offence count drops by one.
+#[derive(Debug, Clone)]
+pub struct DecayConfig {
+    pub interval: Duration,
+    pub score_factor: f64,
+}
+
+impl Default for DecayConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(1),
+            score_factor: 0.9,
+        }
+    }
+}
+
+/// Bot heuristic on an author's accepted messages. Intervals are tracked as an EWMA of mean and
+/// variance; once `min_samples` intervals are in, an author whose mean interval is below
+/// `max_mean_interval` *and* whose coefficient of variation (stddev / mean) is below `max_cv`
+/// gets `penalty` (negative, not counted as an offence) on every further message.
+#[derive(Debug, Clone)]
+pub struct IntervalAnomalyConfig {
+    pub min_samples: u32,
+    pub max_mean_interval: Duration,
+    pub max_cv: f64,
+    pub penalty: f64,
+}
+
+impl Default for IntervalAnomalyConfig {
+    fn default() -> Self {
+        Self {
+            min_samples: 20,
+            max_mean_interval: Duration::from_millis(10),
+            max_cv: 0.05,
+            penalty: -1.0,
+        }
+    }
+}
+
+/// The first `max_errors` codec errors (anything but `too_large`) from a peer first seen less
+/// than `window` ago are still rejected but charged `penalty_factor` times the usual penalty,
+/// without counting as offences. 0 makes them free.
+#[derive(Debug, Clone)]
+pub struct DecodeErrorGrace {
+    pub window: Duration,
+    pub max_errors: u32,
+    pub penalty_factor: f64,
+}
+
+impl Default for DecodeErrorGrace {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(30),
+            max_errors: 3,
+            penalty_factor: 0.25,
+        }
+    }
+}
+
+/// Aggregate check on an author's accepted low-value messages (payload at most
+/// `max_low_value_bytes`). Past `max_per_window` of them within `window`, every further one
+/// is still accepted but costs `penalty` (negative, not counted as an offence).
+#[derive(Debug, Clone)]
+pub struct WellFormedFloodConfig {
+    pub window: Duration,
+    pub max_per_window: u32,
+    pub max_low_value_bytes: usize,
+    pub penalty: f64,
+}
+
+impl Default for WellFormedFloodConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(1),
+            max_per_window: 20,
+            max_low_value_bytes: 64,
+            penalty: -0.5,
+        }
+    }
+}
+
+/// A peer not quarantined whose score is at or below `threshold` is on probation: its
+/// token bucket holds `bucket_fraction` of `burst_capacity` (at least one token) and
+/// `app_score_penalty` (negative) is added to its app score, pushing it toward gossipsub's
+/// publish threshold. Both lift once the score climbs back above `threshold`.
+#[derive(Debug, Clone)]
+pub struct ProbationConfig {
+    pub threshold: f64,
+    pub bucket_fraction: f64,
+    pub app_score_penalty: f64,
+}
+
+impl Default for ProbationConfig {
+    fn default() -> Self {
+        Self {
+            threshold: -10.0,
+            bucket_fraction: 0.25,
+            app_score_penalty: -10.0,
+        }
+    }
+}
+
+// weight of the newest sample in the interval EWMA
+const INTERVAL_EWMA_ALPHA: f64 = 0.2;
+
+/// Running statistics of the gaps between one author's messages, in seconds.
+#[derive(Debug, Clone, Default)]
+struct IntervalStats {
+    last: Option<Instant>,
+    mean: f64,
+    variance: f64,
+    samples: u32,
+}
+
+impl IntervalStats {
+    fn observe(&mut self, now: Instant) {
+        if let Some(last) = self.last {
+            let x = now.saturating_duration_since(last).as_secs_f64();
+            if self.samples == 0 {
+                self.mean = x;
+            } else {
+                let diff = x - self.mean;
+                self.mean += INTERVAL_EWMA_ALPHA * diff;
+                self.variance = (1.0 - INTERVAL_EWMA_ALPHA) * (self.variance + INTERVAL_EWMA_ALPHA * diff * diff);
+            }
+            self.samples = self.samples.saturating_add(1);
+        }
+        self.last = Some(now);
+    }
+
+    fn is_anomalous(&self, cfg: &IntervalAnomalyConfig) -> bool {
+        self.samples >= cfg.min_samples
+            && self.mean < cfg.max_mean_interval.as_secs_f64()
+            && (self.mean <= 0.0 || self.variance.sqrt() / self.mean < cfg.max_cv)
+    }
+}
+
+/// How much we trust reputation reports gossiped to us by other peers.
+#[derive(Debug, Clone)]
+pub struct ReputationConfig {
+    /// Reports from reporters whose local score is below this are dropped.
+    pub min_reporter_score: f64,
+    /// Accepted messages a reporter must have delivered to us before its reports count in
+    /// full; the weight ramps up from zero, so a peer we have never heard from is ignored.
+    pub min_reporter_accepts: u64,
+    /// Cap on how far external reports may lower a single victim's score.
+    pub max_external_influence: f64,
+    /// Cap on how far external reports may raise a single victim's score, kept low so a
+    /// colluding group can't vouch an attacker out of quarantine.
+    pub max_external_boost: f64,
+}
+
+impl Default for ReputationConfig {
+    fn default() -> Self {
+        Self {
+            min_reporter_score: -10.0,
+            min_reporter_accepts: 5,
+            max_external_influence: 20.0,
+            max_external_boost: 0.0,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 struct TokenBucket {
     capacity: u32,
+    refill_rate: f64,
     tokens: f64,
     last: Instant,
 }
 
 impl TokenBucket {
-    fn new() -> Self {
+    fn new(now: Instant, capacity: u32, refill_rate: f64) -> Self {
         Self {
-            capacity: TOKEN_BUCKET_CAPACITY,
-            tokens: TOKEN_BUCKET_CAPACITY as f64,
-            last: Instant::now(),
+            capacity,
+            refill_rate,
+            tokens: capacity as f64,
+            last: now,
         }
     }
 
-    fn try_consume(&mut self, amount: u32) -> bool {
-        let now = Instant::now();
+    fn try_consume(&mut self, now: Instant, amount: u32) -> bool {
         let elapsed = now.duration_since(self.last).as_secs_f64();
         self.last = now;
-        self.tokens += elapsed * TOKEN_REFILL_RATE;
+        self.tokens += elapsed * self.refill_rate;
         if self.tokens > self.capacity as f64 {
             self.tokens = self.capacity as f64;
         }
@@ -54,185 +586,930 @@ impl TokenBucket {
     }
 }
 
+/// Coarse standing of a tracked peer, for filtering with `Validator::peers_where`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeerStatus {
+    /// Score at or above zero.
+    Healthy,
+    /// Penalized below zero but not (yet) quarantined.
+    Degraded,
+    /// At or below `ProbationConfig::threshold`: throttled harder, not yet quarantined.
+    Probation,
+    /// Its messages are ignored until the score recovers.
+    Quarantined,
+}
+
+impl PeerStatus {
+    fn of(state: &PeerState) -> Self {
+        if state.quarantined {
+            PeerStatus::Quarantined
+        } else if state.probation {
+            PeerStatus::Probation
+        } else if state.score < 0.0 {
+            PeerStatus::Degraded
+        } else {
+            PeerStatus::Healthy
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct PeerState {
     score: f64,
     bucket: TokenBucket,
-    last_seq: Option<u64>,
     quarantined: bool,
+    // under `probation`, with a shrunken bucket
+    probation: bool,
+    // net score change applied on behalf of other peers' reputation reports
+    external_delta: f64,
+    // last time this peer forwarded or authored anything we validated
+    last_activity: Instant,
+    // consecutive rate-limited messages, and when the current streak began
+    rate_limit_streak: u32,
+    streak_started: Instant,
+    // gaps between messages this peer authored
+    intervals: IntervalStats,
+    // newest last, at most RECENT_PENALTIES
+    recent_penalties: VecDeque<(RejectReason, f64)>,
+    // replays authored by this peer in the current window, and when the window began
+    replays: u32,
+    replay_window_start: Instant,
+    first_seen: Instant,
+    // accepted messages' progress toward forgiving the next offence
+    forgiveness: f64,
+    // messages blamed on this peer (author, else forwarder) that were accepted / rejected
+    accepted: u64,
+    rejected: u64,
+    // low-value messages authored in the current flood window, and when the window began
+    low_value: u32,
+    low_value_window_start: Instant,
+    throttle: ThrottleStats,
+    // bucket for messages this peer authored, when `author_rate_limit` is set
+    author_bucket: Option<TokenBucket>,
+    // codec errors forgiven under `decode_error_grace`
+    graced_errors: u32,
+    // when this peer first lost score (offence or not), and when it was first quarantined
+    first_offence: Option<Instant>,
+    quarantined_at: Option<Instant>,
+    // when a message blamed on this peer was first accepted
+    first_accept: Option<Instant>,
+    // remote address, when `bind_reputation_to_address` is set
+    address: Option<IpAddr>,
 }
 
-impl Default for PeerState {
-    fn default() -> Self {
+impl PeerState {
+    fn new(now: Instant, cfg: &ValidatorConfig) -> Self {
         Self {
             score: 0.0,
-            bucket: TokenBucket::new(),
-            last_seq: None,
+            bucket: TokenBucket::new(now, cfg.burst_capacity, cfg.sustained_rate),
             quarantined: false,
+            probation: false,
+            external_delta: 0.0,
+            last_activity: now,
+            rate_limit_streak: 0,
+            streak_started: now,
+            intervals: IntervalStats::default(),
+            recent_penalties: VecDeque::new(),
+            replays: 0,
+            replay_window_start: now,
+            first_seen: now,
+            forgiveness: 0.0,
+            accepted: 0,
+            rejected: 0,
+            low_value: 0,
+            low_value_window_start: now,
+            throttle: ThrottleStats::default(),
+            author_bucket: None,
+            graced_errors: 0,
+            first_offence: None,
+            quarantined_at: None,
+            first_accept: None,
+            address: None,
+        }
+    }
+
+    fn set_quarantined(&mut self, quarantined: bool, now: Instant) {
+        if quarantined && self.quarantined_at.is_none() {
+            self.quarantined_at = Some(now);
+        }
+        self.quarantined = quarantined;
+    }
+
+    // enter or leave probation to match the current score, resizing the bucket to suit
+    fn refresh_probation(&mut self, peer: &PeerId, cfg: &ValidatorConfig) {
+        let probation = cfg.probation.as_ref().filter(|p| !self.quarantined && self.score <= p.threshold);
+        let capacity = match probation {
+            Some(p) => ((cfg.burst_capacity as f64 * p.bucket_fraction) as u32).max(1),
+            None => cfg.burst_capacity,
+        };
+        self.bucket.capacity = capacity;
+        self.bucket.tokens = self.bucket.tokens.min(capacity as f64);
+        if probation.is_some() != self.probation {
+            tracing::info!(peer = %peer, score = self.score, probation = probation.is_some(), "probation changed");
         }
+        self.probation = probation.is_some();
     }
 }
 
+/// Application-level check on the payload of a `Good` message; `Err` carries the reject reason.
+pub type PayloadValidator = Box<dyn Fn(&[u8]) -> Result<(), &'static str> + Send>;
+
+/// Last accepted sequence number per author, bounded with LRU eviction: when full, the
+/// author whose state was looked up or updated longest ago is forgotten first.
 #[derive(Debug)]
+struct AuthorSeqs {
+    capacity: usize,
+    // author -> (last seq, recency stamp)
+    last_seq: HashMap<PeerId, (u64, u64)>,
+    // recency stamp -> author, least recently used first
+    recency: BTreeMap<u64, PeerId>,
+    clock: u64,
+}
+
+impl AuthorSeqs {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            last_seq: HashMap::new(),
+            recency: BTreeMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// The author's last seq, marking it as recently used.
+    fn get(&mut self, author: &PeerId) -> Option<u64> {
+        let (seq, stamp) = self.last_seq.get_mut(author)?;
+        self.recency.remove(stamp);
+        self.clock += 1;
+        *stamp = self.clock;
+        self.recency.insert(self.clock, *author);
+        Some(*seq)
+    }
+
+    fn resize(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        self.evict_to(self.capacity);
+    }
+
+    fn evict_to(&mut self, len: usize) {
+        while self.last_seq.len() > len {
+            match self.recency.pop_first() {
+                Some((_, old)) => {
+                    self.last_seq.remove(&old);
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn update(&mut self, author: &PeerId, seq: u64) {
+        if self.get(author).is_some() {
+            if let Some((last, _)) = self.last_seq.get_mut(author) {
+                *last = seq;
+            }
+            return;
+        }
+        self.evict_to(self.capacity - 1);
+        self.clock += 1;
+        self.last_seq.insert(*author, (seq, self.clock));
+        self.recency.insert(self.clock, *author);
+    }
+
+    /// (author, last seq), least recently used first.
+    fn iter(&self) -> impl Iterator<Item = (&PeerId, u64)> {
+        self.recency.values().filter_map(|a| self.last_seq.get(a).map(|(seq, _)| (a, *seq)))
+    }
+}
+
+/// Why the validator reached a decision. Serializes as its snake_case name (e.g. `"oversize"`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RejectReason {
+    Ok,
+    Oversize,
+    RateLimited,
+    DecodeError,
+    Truncated,
+    TooLarge,
+    TrailingBytes,
+    Duplicate,
+    EmptyPayload,
+    ReplayOrOldSeq,
+    MaliciousPayload,
+    ForwarderQuarantined,
+    ValidationFailed,
+    /// Author publishes too fast and too regularly (score nudge only; the message is accepted).
+    IntervalAnomaly,
+    /// Author floods small well-formed messages (score nudge only; the message is accepted).
+    WellFormedFlood,
+    /// Rejected by the embedder's payload validator, with its reason.
+    Custom(&'static str),
+}
+
+impl RejectReason {
+    /// Every reason the validator produces itself; embedders add `Custom` ones.
+    pub const BUILTIN: [RejectReason; 15] = [
+        RejectReason::Ok,
+        RejectReason::Oversize,
+        RejectReason::RateLimited,
+        RejectReason::DecodeError,
+        RejectReason::Truncated,
+        RejectReason::TooLarge,
+        RejectReason::TrailingBytes,
+        RejectReason::Duplicate,
+        RejectReason::EmptyPayload,
+        RejectReason::ReplayOrOldSeq,
+        RejectReason::MaliciousPayload,
+        RejectReason::ForwarderQuarantined,
+        RejectReason::ValidationFailed,
+        RejectReason::IntervalAnomaly,
+        RejectReason::WellFormedFlood,
+    ];
+
+    /// Whether the message is ever refused for this reason. `Ok` and the behavioural nudges
+    /// (`IntervalAnomaly`, `WellFormedFlood`) always accept it.
+    pub fn refuses(&self) -> bool {
+        !matches!(self, RejectReason::Ok | RejectReason::IntervalAnomaly | RejectReason::WellFormedFlood)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RejectReason::Ok => "ok",
+            RejectReason::Oversize => "oversize",
+            RejectReason::RateLimited => "rate_limited",
+            RejectReason::DecodeError => "decode_error",
+            RejectReason::Truncated => "truncated",
+            RejectReason::TooLarge => "too_large",
+            RejectReason::TrailingBytes => "trailing_bytes",
+            RejectReason::Duplicate => "duplicate",
+            RejectReason::EmptyPayload => "empty_payload",
+            RejectReason::ReplayOrOldSeq => "replay_or_old_seq",
+            RejectReason::MaliciousPayload => "malicious_payload",
+            RejectReason::ForwarderQuarantined => "forwarder_quarantined",
+            RejectReason::ValidationFailed => "validation_failed",
+            RejectReason::IntervalAnomaly => "interval_anomaly",
+            RejectReason::WellFormedFlood => "well_formed_flood",
+            RejectReason::Custom(reason) => reason,
+        }
+    }
+}
+
+impl std::fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for RejectReason {
+    type Err = String;
+
+    /// One of the `BUILTIN` reasons by its `as_str` name.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        RejectReason::BUILTIN
+            .into_iter()
+            .find(|r| r.as_str() == s)
+            .ok_or_else(|| format!("unknown reason `{s}`"))
+    }
+}
+
+impl PartialEq<&str> for RejectReason {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl Serialize for RejectReason {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// Lower-case name of a gossipsub acceptance, for logs and serialized decisions.
+pub fn acceptance_str(acceptance: &MessageAcceptance) -> &'static str {
+    match acceptance {
+        MessageAcceptance::Accept => "accept",
+        MessageAcceptance::Reject => "reject",
+        MessageAcceptance::Ignore => "ignore",
+    }
+}
+
+fn serialize_acceptance<S: Serializer>(acceptance: &MessageAcceptance, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(acceptance_str(acceptance))
+}
+
+#[derive(Debug, Serialize)]
 pub struct Decision {
+    #[serde(serialize_with = "serialize_acceptance")]
     pub acceptance: MessageAcceptance,
-    pub reason: &'static str,
+    pub reason: RejectReason,
     pub score_delta: f64,
 }
 
+/// What the rate limiter did with the messages one peer forwarded: of all `attempts`,
+/// `throttled` were rejected by its token bucket and `passed` went on to content validation.
+/// The rest were ignored before the rate limit (quarantined forwarder), were oversize or
+/// drained their author's bucket.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ThrottleStats {
+    pub attempts: u64,
+    pub throttled: u64,
+    pub passed: u64,
+}
+
+/// A peer's standing as reported by `Validator::explain`. `Display` renders a one-line summary.
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerExplanation {
+    #[serde(serialize_with = "serialize_peer")]
+    pub peer: PeerId,
+    /// Whether the validator has any state for this peer.
+    pub tracked: bool,
+    pub score: f64,
+    pub offences: u32,
+    /// (reason, score delta) of the most recent penalties, oldest first.
+    pub recent_penalties: Vec<(RejectReason, f64)>,
+    /// Rate-limit tokens currently available.
+    pub tokens: f64,
+    /// Quarantine lasts until the score climbs back over the threshold; it has no fixed expiry.
+    pub quarantined: bool,
+}
+
+impl std::fmt::Display for PeerExplanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.tracked {
+            return write!(f, "{}: not tracked", self.peer);
+        }
+        write!(
+            f,
+            "{}: score {:.1}, {} offences, {:.0} tokens, {}",
+            self.peer,
+            self.score,
+            self.offences,
+            self.tokens,
+            if self.quarantined { "quarantined" } else { "not quarantined" },
+        )?;
+        if !self.recent_penalties.is_empty() {
+            let recent: Vec<String> = self.recent_penalties.iter().map(|(r, d)| format!("{r} ({d:+.1})")).collect();
+            write!(f, "; recent: {}", recent.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+fn serialize_peer<S: Serializer>(peer: &PeerId, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(peer)
+}
+
+/// Serializable validator state for persistence across restarts. Peer ids are stored as
+/// their byte encoding.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ValidatorSnapshot {
+    /// Wall-clock time the snapshot was taken, in milliseconds since the Unix epoch.
+    pub taken_at_ms: u64,
+    pub peers: Vec<PeerSnapshot>,
+    /// (author, last accepted sequence number), oldest first.
+    pub authors: Vec<(Vec<u8>, u64)>,
+    /// (dedupe key, age in ms at snapshot time), oldest first. Empty unless
+    /// `max_persisted_dedupe` is set.
+    pub dedupe: Vec<([u8; 32], u64)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerSnapshot {
+    pub peer: Vec<u8>,
+    pub score: f64,
+    pub quarantined: bool,
+    pub offences: u32,
+}
+
+fn unix_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+// say so loudly whenever a config tolerates invalid messages
+fn warn_accept_despite(cfg: &ValidatorConfig) {
+    if !cfg.accept_despite.is_empty() {
+        let reasons: Vec<&str> = cfg.accept_despite.iter().map(|r| r.as_str()).collect();
+        tracing::warn!(?reasons, "accept_despite is set: invalid messages with these reasons will be accepted; testing only");
+    }
+}
+
 // --- Validator struct now includes offences map ---
 pub struct Validator {
     cfg: ValidatorConfig,
     peers: HashMap<PeerId, PeerState>,
-    // small bounded dedupe
-    dedupe_cache: VecDeque<[u8; 32]>,
+    // replay protection state, keyed by author
+    authors: AuthorSeqs,
+    // small bounded dedupe, oldest first, with when each key was first seen
+    dedupe_cache: VecDeque<([u8; 32], Instant)>,
     dedupe_set: HashSet<[u8; 32]>,
     // offences counts per forwarder (escalate repeated malicious events)
     offences: HashMap<PeerId, u32>,
     // app scores for libp2p integration
     app_scores: HashMap<PeerId, f64>,
+    // added to the wall clock; lets tests and offline replays move time forward
+    clock_offset: Duration,
+    // set by `freeze_clock`; the instant the clock stopped at
+    frozen_at: Option<Instant>,
+    // authors exempt from content penalties
+    trusted_authors: HashSet<PeerId>,
+    payload_validator: Option<PayloadValidator>,
+    latency: LatencyHistogram,
+    // peers dropped because the peer table was full
+    evictions: u64,
+    // decisions made, by reason
+    decisions: BTreeMap<&'static str, u64>,
+    // when this validator was created, for `time_to_first_accept`
+    started: Instant,
+    // last known score per remote address, under `bind_reputation_to_address`
+    address_scores: HashMap<IpAddr, f64>,
 }
 
 impl Validator {
-    pub fn new(cfg: ValidatorConfig) -> Self {
-        Self {
+    pub fn new(mut cfg: ValidatorConfig) -> Self {
+        cfg.apply_memory_budget();
+        warn_accept_despite(&cfg);
+        let mut validator = Self {
+            authors: AuthorSeqs::new(cfg.max_tracked_authors),
             cfg,
             peers: HashMap::new(),
             dedupe_cache: VecDeque::new(),
             dedupe_set: HashSet::new(),
             offences: HashMap::new(),
             app_scores: HashMap::new(),
-        }
+            clock_offset: Duration::ZERO,
+            frozen_at: None,
+            trusted_authors: HashSet::new(),
+            payload_validator: None,
+            latency: LatencyHistogram::default(),
+            evictions: 0,
+            decisions: BTreeMap::new(),
+            started: Instant::now(),
+            address_scores: HashMap::new(),
+        };
+        // measured on the validator's own clock, like every other timestamp it keeps
+        validator.started = validator.now();
+        validator
+    }
+
+    /// Current time as seen by the validator.
+    fn now(&self) -> Instant {
+        self.frozen_at.unwrap_or_else(Instant::now) + self.clock_offset
+    }
+
+    /// Stop the validator's clock at the current instant; afterwards only `advance_clock`
+    /// moves it. Makes idleness and rate limiting reproducible in tests and replays.
+    pub fn freeze_clock(&mut self) {
+        self.frozen_at = Some(Instant::now());
+    }
+
+    /// Move the validator's clock forward without sleeping, e.g. to simulate idle time in tests.
+    pub fn advance_clock(&mut self, by: Duration) {
+        self.clock_offset += by;
     }
 
     /// Validate a message. `author` is the original message publisher (message.source),
     /// `propagation_source` is the peer that forwarded the message to us.
+    ///
+    /// With the `spans` feature each call runs in a `validate` span carrying `peer`, `author`
+    /// and `len`, plus the outcome's `reason` and `acceptance` once decided.
     pub fn validate(&mut self, propagation_source: &PeerId, author: Option<&PeerId>, bytes: &[u8]) -> Decision {
+        self.decide(propagation_source, author, bytes.len(), |v| v.validate_inner(propagation_source, author, bytes))
+    }
+
+    /// Validate a message the caller already decoded from `frame`, skipping our own
+    /// decompress and decode steps. `frame` is the message as received, compressed or not:
+    /// it is what the oversize check measures and what dedupe hashes, as in `validate`. The
+    /// decoded message gets the same expanded-size check a compressed frame would. Everything
+    /// else runs exactly as in `validate`.
+    pub fn validate_decoded(
+        &mut self,
+        propagation_source: &PeerId,
+        author: Option<&PeerId>,
+        msg: &WireMessage,
+        frame: &[u8],
+    ) -> Decision {
+        self.decide(propagation_source, author, frame.len(), |v| {
+            if let Some(decision) = v.admit(propagation_source, author, frame.len()) {
+                return decision;
+            }
+            if encoded_len(msg) > v.cfg.max_message_bytes {
+                let target = author.unwrap_or(propagation_source);
+                return v.reject_content(target, RejectReason::Oversize, v.cfg.penalties.oversize);
+            }
+            v.judge(propagation_source, author, msg, frame)
+        })
+    }
+
+    // run `inner` for one message and do the bookkeeping and overrides common to every entry point
+    fn decide(
+        &mut self,
+        propagation_source: &PeerId,
+        author: Option<&PeerId>,
+        len: usize,
+        inner: impl FnOnce(&mut Self) -> Decision,
+    ) -> Decision {
+        #[cfg(feature = "spans")]
+        let span = tracing::info_span!(
+            "validate",
+            peer = %propagation_source,
+            author = author.map(tracing::field::display),
+            len,
+            reason = tracing::field::Empty,
+            acceptance = tracing::field::Empty,
+        )
+        .entered();
+        #[cfg(not(feature = "spans"))]
+        let _ = len;
+        let started = Instant::now();
+        let decision = inner(self);
+        self.latency.record(started.elapsed());
+        *self.decisions.entry(decision.reason.as_str()).or_default() += 1;
+        // rejections count toward the author where they are charged (see `note_rejection`),
+        // so forwarder-blamed ones like rate limits and duplicates stay out of its ratio
+        let target = author.unwrap_or(propagation_source);
+        if matches!(decision.acceptance, MessageAcceptance::Accept) {
+            let now = self.now();
+            let state = self.peer_state_mut(target);
+            state.accepted += 1;
+            state.first_accept.get_or_insert(now);
+        }
+        let decision = if self.cfg.observe_only && matches!(decision.acceptance, MessageAcceptance::Reject) {
+            tracing::debug!(peer = %target, reason = %decision.reason, "observe-only: accepting a message we would reject");
+            Decision { acceptance: MessageAcceptance::Accept, ..decision }
+        } else {
+            decision
+        };
+        let decision = if !matches!(decision.acceptance, MessageAcceptance::Accept)
+            && self.cfg.accept_despite.contains(&decision.reason)
+        {
+            tracing::warn!(peer = %target, reason = %decision.reason, "accept_despite: accepting a message that failed validation (testing only)");
+            Decision { acceptance: MessageAcceptance::Accept, ..decision }
+        } else {
+            decision
+        };
+        #[cfg(feature = "spans")]
+        {
+            span.record("reason", decision.reason.as_str());
+            span.record("acceptance", acceptance_str(&decision.acceptance));
+        }
+        decision
+    }
+
+    /// Wall-clock time spent in `validate`, one sample per call.
+    pub fn latency_histogram(&self) -> &LatencyHistogram {
+        &self.latency
+    }
+
+    fn validate_inner(&mut self, propagation_source: &PeerId, author: Option<&PeerId>, bytes: &[u8]) -> Decision {
+        if let Some(decision) = self.admit(propagation_source, author, bytes.len()) {
+            return decision;
+        }
+
+        // Compressed frames get a second size check on what they expand to, enforced while
+        // decompressing so a tiny frame can't balloon before we look at it
+        let frame = match decompress(bytes, self.cfg.max_message_bytes) {
+            Ok(frame) => frame,
+            Err(e) => {
+                let target = author.unwrap_or(propagation_source);
+                let (reason, penalty) = match e {
+                    CodecError::TooLarge => (RejectReason::Oversize, self.cfg.penalties.oversize),
+                    e => return self.reject_codec(target, e),
+                };
+                return self.reject_content(target, reason, penalty);
+            }
+        };
+
+        // Decode
+        let decoded = if self.cfg.strict_decode { decode_strict(&frame) } else { decode(&frame) };
+        let msg = match decoded {
+            Ok(m) => m,
+            Err(e) => {
+                // decode failures -> blame author (malformed payload)
+                let target = author.unwrap_or(propagation_source);
+                return self.reject_codec(target, e);
+            }
+        };
+
+        self.judge(propagation_source, author, &msg, bytes)
+    }
+
+    // Checks that need only the frame's size: quarantine, oversize and rate limits. `None`
+    // lets the message through to decoding.
+    fn admit(&mut self, propagation_source: &PeerId, author: Option<&PeerId>, len: usize) -> Option<Decision> {
         // Helpful debug: record incoming validation attempt
-        tracing::debug!(?author, %propagation_source, len = bytes.len(), "validate called");
-        // If forwarder quarantined, silently ignore
+        tracing::debug!(?author, %propagation_source, len, "validate called");
+        self.touch(propagation_source);
+        self.peer_state_mut(propagation_source).throttle.attempts += 1;
+        self.apply_deferred_quarantine(propagation_source);
+        let now = self.now();
+        if let Some(state) = author.and_then(|a| self.peers.get_mut(a)) {
+            state.last_activity = now;
+        }
+        // If forwarder quarantined, silently ignore -- unless it is relaying someone else's
+        // message and we were asked to judge that on its own merits
         if self.is_quarantined(propagation_source) {
-            return Decision {
-                acceptance: MessageAcceptance::Ignore,
-                reason: "forwarder_quarantined",
-                score_delta: 0.0,
-            };
+            let judge_content = self.cfg.accept_via_quarantined_forwarder
+                && author.is_some_and(|a| a != propagation_source && !self.is_quarantined(a));
+            if !judge_content {
+                return Some(self.ignore(propagation_source, RejectReason::ForwarderQuarantined));
+            }
         }
 
         // Oversize check (blame the author for content size)
-        if bytes.len() > self.cfg.max_message_bytes {
-            let base = -60.0;
+        if len > self.cfg.max_message_bytes {
             let target = author.unwrap_or(propagation_source);
-            self.record_offence_and_update(target, base);
-            return Decision {
-                acceptance: MessageAcceptance::Reject,
-                reason: "oversize",
-                score_delta: base,
-            };
+            return Some(self.reject_content(target, RejectReason::Oversize, self.cfg.penalties.oversize));
         }
 
-        // Rate limit check on forwarder
-        self.ensure_peer_exists(propagation_source);
-        if !self.peers.get_mut(propagation_source).unwrap().bucket.try_consume(1) {
-            // gentle penalty for short bursts; don't kill honest forwarders
-            let base = -5.0;
-            self.record_offence_and_update(propagation_source, base);
-            return Decision {
+        // Rate limit check on forwarder; our own traffic doesn't count against us
+        let own = self.is_local(propagation_source) || author.is_some_and(|a| self.is_local(a));
+        if !own && !self.peer_state_mut(propagation_source).bucket.try_consume(now, 1) {
+            self.peer_state_mut(propagation_source).throttle.throttled += 1;
+            let base = self.rate_limit_penalty(propagation_source, now);
+            let applied = self.record_offence_and_update(propagation_source, base);
+            self.note_penalty(propagation_source, RejectReason::RateLimited, applied);
+            return Some(Decision {
                 acceptance: MessageAcceptance::Reject,
-                reason: "rate_limited",
+                reason: RejectReason::RateLimited,
                 score_delta: base,
-            };
+            });
         }
-
-        // Decode
-        let msg = match decode(bytes) {
-            Ok(m) => m,
-            Err(_) => {
-                // decode failures -> blame author (malformed payload)
-                let base = -30.0;
-                let target = author.unwrap_or(propagation_source);
-                self.record_offence_and_update(target, base);
-                return Decision {
-                    acceptance: MessageAcceptance::Reject,
-                    reason: "decode_error",
-                    score_delta: base,
-                };
+        if let Some(limit) = self.cfg.author_rate_limit.clone().filter(|_| !own) {
+            if let Some(author) = author {
+                let bucket = self
+                    .peer_state_mut(author)
+                    .author_bucket
+                    .get_or_insert_with(|| TokenBucket::new(now, limit.burst_capacity, limit.sustained_rate));
+                if !bucket.try_consume(now, 1) {
+                    self.note_rejection(author);
+                    let base = self.rate_limit_penalty(author, now);
+                    let applied = self.record_offence_and_update(author, base);
+                    self.note_penalty(author, RejectReason::RateLimited, applied);
+                    return Some(Decision {
+                        acceptance: MessageAcceptance::Reject,
+                        reason: RejectReason::RateLimited,
+                        score_delta: base,
+                    });
+                }
             }
-        };
+        }
+        self.peer_state_mut(propagation_source).throttle.passed += 1;
+        None
+    }
 
-        // Deduplicate by content hash
-        let mut hasher = Sha256::new();
-        hasher.update(b"gossipsub-v1.1:");
-        hasher.update(bytes);
-        let hash = hasher.finalize();
-        let mut key = [0u8; 32];
-        key.copy_from_slice(&hash);
-        if self.is_dupe(&key) {
-            // dedupe -> ignore (no penalty)
-            return Decision {
-                acceptance: MessageAcceptance::Ignore,
-                reason: "duplicate",
-                score_delta: 0.0,
-            };
+    // Checks on a message decoded from `frame`: dedupe by the frame's hash, then content,
+    // sequence and behaviour.
+    fn judge(&mut self, propagation_source: &PeerId, author: Option<&PeerId>, msg: &WireMessage, frame: &[u8]) -> Decision {
+        // Deduplicate by content hash (can be switched off to measure what it suppresses)
+        if self.cfg.dedupe_enabled {
+            let key = content_hash(&self.cfg.message_domain, frame);
+            if self.is_dupe(&key) {
+                // a peer we already distrust that keeps sending dupes is wasting our bandwidth
+                if let Some(threshold) = self.cfg.reject_duplicates_below {
+                    if self.get_peer_score(propagation_source) < threshold {
+                        let base = -self.cfg.duplicate_penalty.abs();
+                        let applied = self.record_offence_and_update(propagation_source, base);
+                        self.note_penalty(propagation_source, RejectReason::Duplicate, applied);
+                        return Decision {
+                            acceptance: MessageAcceptance::Reject,
+                            reason: RejectReason::Duplicate,
+                            score_delta: base,
+                        };
+                    }
+                }
+                // dedupe -> ignore (no penalty unless ignore_penalty is set)
+                return self.ignore(propagation_source, RejectReason::Duplicate);
+            }
+            // add to dedupe cache
+            self.add_to_dedupe(key);
         }
-        // add to dedupe cache
-        self.add_to_dedupe(key);
 
         // Content-specific checks
         match msg {
             WireMessage::Good { seq, payload } => {
+                let seq = *seq;
                 if payload.is_empty() {
-                    let base = -30.0;
                     let target = author.unwrap_or(propagation_source);
-                    self.record_offence_and_update(target, base);
-                    return Decision {
-                        acceptance: MessageAcceptance::Reject,
-                        reason: "empty_payload",
-                        score_delta: base,
-                    };
+                    return self.reject_content(target, RejectReason::EmptyPayload, self.cfg.penalties.empty_payload);
+                }
+
+                // Application-specific payload checks, if the embedder installed any
+                if let Some(Err(reason)) = self.payload_validator.as_ref().map(|f| f(payload)) {
+                    let target = author.unwrap_or(propagation_source);
+                    return self.reject_content(target, RejectReason::Custom(reason), self.cfg.penalties.decode_error);
                 }
 
                 // Replay/sequence validation keyed by *author*
                 let target = author.unwrap_or(propagation_source);
-                let last = self.get_peer_last_seq(target).unwrap_or(0);
+                let last = self.authors.get(target).unwrap_or(0);
                 if seq <= last {
+                    if let Some(policy) = self.cfg.replay_policy.clone() {
+                        let now = self.now();
+                        let state = self.peer_state_mut(target);
+                        if now.saturating_duration_since(state.replay_window_start) > policy.window {
+                            state.replays = 0;
+                            state.replay_window_start = now;
+                        }
+                        state.replays += 1;
+                        if state.replays > policy.max_replays {
+                            // persistent replaying is abuse, not retransmission
+                            return self.reject_content(target, RejectReason::ReplayOrOldSeq, -policy.penalty.abs());
+                        }
+                    }
                     // leave as IGNORE so forwarders are not punished for possible retransmits
-                    return Decision {
-                        acceptance: MessageAcceptance::Ignore,
-                        reason: "replay_or_old_seq",
-                        score_delta: 0.0,
-                    };
+                    return self.ignore(propagation_source, RejectReason::ReplayOrOldSeq);
                 }
                 // Update last seq for author
-                self.update_peer_last_seq(target, seq);
+                self.authors.update(target, seq);
+                // a quarantined relay earns nothing for delivering a good message
+                if !self.is_quarantined(propagation_source) {
+                    self.peer_state_mut(propagation_source).rate_limit_streak = 0;
+                }
+                // behavioural nudges accept the message but still cost the author
+                let mut score_delta = 0.0;
+                if let Some(anomaly) = self.cfg.interval_anomaly.clone().filter(|_| !self.is_local(target)) {
+                    let now = self.now();
+                    let stats = &mut self.peer_state_mut(target).intervals;
+                    stats.observe(now);
+                    if stats.is_anomalous(&anomaly) {
+                        tracing::debug!(author = %target, mean_s = stats.mean, "implausibly regular publish interval");
+                        let delta = -anomaly.penalty.abs();
+                        self.update_peer_score(target, delta);
+                        self.note_penalty(target, RejectReason::IntervalAnomaly, delta);
+                        score_delta += delta;
+                    }
+                }
+                if let Some(flood) = self.cfg.well_formed_flood.clone().filter(|_| !self.is_local(target)) {
+                    if payload.len() <= flood.max_low_value_bytes {
+                        let now = self.now();
+                        let state = self.peer_state_mut(target);
+                        if now.saturating_duration_since(state.low_value_window_start) > flood.window {
+                            state.low_value = 0;
+                            state.low_value_window_start = now;
+                        }
+                        state.low_value += 1;
+                        if state.low_value > flood.max_per_window {
+                            let delta = -flood.penalty.abs();
+                            self.update_peer_score(target, delta);
+                            self.note_penalty(target, RejectReason::WellFormedFlood, delta);
+                            score_delta += delta;
+                        }
+                    }
+                }
+
+                self.forgive_on_accept(target);
 
                 // Accept valid message
-                return Decision {
+                Decision {
                     acceptance: MessageAcceptance::Accept,
-                    reason: "ok",
-                    score_delta: 0.0,
-                };
+                    reason: RejectReason::Ok,
+                    score_delta,
+                }
             }
-            WireMessage::Bad => {
+            WireMessage::Malicious => {
                 // clearly malicious payload — blame author and escalate
-                let base = -80.0;
                 let target = author.unwrap_or(propagation_source);
-                self.record_offence_and_update(target, base);
-                return Decision {
-                    acceptance: MessageAcceptance::Reject,
-                    reason: "malicious_payload",
-                    score_delta: base,
-                };
+                self.reject_content(target, RejectReason::MaliciousPayload, self.cfg.penalties.malicious_payload)
+            }
+        }
+    }
+
+    /// Install an application-level payload check, run on every well-formed `Good` message.
+    /// A failing check rejects the message with the returned reason and the decode-error
+    /// penalty, so embedders can enforce their own payload format.
+    pub fn set_payload_validator(&mut self, f: PayloadValidator) {
+        self.payload_validator = Some(f);
+    }
+
+    /// Like `validate`, but also answers the plain question "should this be forwarded?"
+    /// for pipelines that don't speak gossipsub's `MessageAcceptance`.
+    pub fn should_forward(&mut self, propagation_source: &PeerId, author: Option<&PeerId>, bytes: &[u8]) -> (bool, Decision) {
+        let decision = self.validate(propagation_source, author, bytes);
+        let forward = matches!(decision.acceptance, MessageAcceptance::Accept);
+        (forward, decision)
+    }
+
+    /// Reject a message for a content violation, blaming `target` (its author). Trusted
+    /// authors are still rejected but their score is left alone.
+    fn reject_content(&mut self, target: &PeerId, reason: RejectReason, base: f64) -> Decision {
+        self.note_rejection(target);
+        let score_delta = if self.trusted_authors.contains(target) || self.is_local(target) {
+            tracing::debug!(peer = %target, %reason, "content violation from trusted author; not penalizing");
+            0.0
+        } else {
+            let applied = self.record_offence_and_update(target, base);
+            self.note_penalty(target, reason, applied);
+            base
+        };
+        Decision {
+            acceptance: MessageAcceptance::Reject,
+            reason,
+            score_delta,
+        }
+    }
+
+    // one more of `author`'s own messages rejected, for `classify`'s acceptance ratio
+    fn note_rejection(&mut self, author: &PeerId) {
+        self.peer_state_mut(author).rejected += 1;
+    }
+
+    fn is_local(&self, peer: &PeerId) -> bool {
+        self.cfg.local_peer_id.as_ref() == Some(peer)
+    }
+
+    /// Mark `author` as a privileged publisher: its malformed messages are still rejected,
+    /// but never cost it score. This is about authors, not forwarders.
+    pub fn add_trusted_author(&mut self, author: PeerId) {
+        self.trusted_authors.insert(author);
+    }
+
+    pub fn remove_trusted_author(&mut self, author: &PeerId) -> bool {
+        self.trusted_authors.remove(author)
+    }
+
+    /// Build an `Ignore` decision, charging the forwarder the configured `ignore_penalty`.
+    /// The penalty bypasses offence escalation so it stays a soft signal, and a forwarder
+    /// already in quarantine isn't charged: its traffic is dropped anyway.
+    fn ignore(&mut self, propagation_source: &PeerId, reason: RejectReason) -> Decision {
+        let exempt = self.is_local(propagation_source) || self.is_quarantined(propagation_source);
+        let delta = if exempt { 0.0 } else { -self.cfg.ignore_penalty.abs() };
+        if delta != 0.0 {
+            self.update_peer_score(propagation_source, delta);
+            self.note_penalty(propagation_source, reason, delta);
+        }
+        Decision {
+            acceptance: MessageAcceptance::Ignore,
+            reason,
+            score_delta: delta,
+        }
+    }
+
+    /// Apply a reputation report from `reporter` claiming `victim` deserves `reported_delta`.
+    ///
+    /// The report is weighted by the reporter's own local record: unknown, quarantined or
+    /// low-scored reporters are ignored, the weight falls linearly from 1.0 at a score of
+    /// zero to 0.0 at `min_reporter_score`, and it is scaled down further until the reporter
+    /// has delivered `min_reporter_accepts` accepted messages, so fresh Sybil identities
+    /// count for nothing. The net influence of all reports on one victim is capped at
+    /// `max_external_influence` downward and `max_external_boost` upward, so a colluding
+    /// group can neither sink an honest peer nor whitewash an attacker.
+    /// Returns the delta actually applied to the victim's score.
+    pub fn apply_reputation_report(&mut self, reporter: &PeerId, victim: &PeerId, reported_delta: f64) -> f64 {
+        if reporter == victim {
+            return 0.0;
+        }
+        let (reporter_score, reporter_accepts) = match self.peers.get(reporter) {
+            Some(s) if !s.quarantined => (s.score, s.accepted),
+            _ => {
+                tracing::debug!(%reporter, %victim, "ignoring reputation report from unknown or quarantined reporter");
+                return 0.0;
             }
+        };
+        let rep = &self.cfg.reputation;
+        if reporter_score < rep.min_reporter_score {
+            tracing::debug!(%reporter, %victim, reporter_score, "ignoring reputation report from low-scored reporter");
+            return 0.0;
+        }
+        let standing = if rep.min_reporter_score < 0.0 {
+            (1.0 - reporter_score / rep.min_reporter_score).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        let track_record = if rep.min_reporter_accepts > 0 {
+            (reporter_accepts as f64 / rep.min_reporter_accepts as f64).min(1.0)
+        } else {
+            1.0
+        };
+        let weight = standing * track_record;
+        if weight == 0.0 {
+            tracing::debug!(%reporter, %victim, reporter_accepts, "ignoring reputation report from reporter without a track record");
+            return 0.0;
+        }
+
+        let (down, up) = (rep.max_external_influence.abs(), rep.max_external_boost.abs());
+        let state = self.peer_state_mut(victim);
+        let current = state.external_delta;
+        let target = (current + reported_delta * weight).clamp(-down, up);
+        let applied = target - current;
+        if applied == 0.0 {
+            return 0.0;
         }
+        state.external_delta = target;
+        self.update_peer_score(victim, applied);
+        tracing::info!(%reporter, %victim, reported = reported_delta, weight, applied, "reputation report applied");
+        applied
     }
 
     pub fn get_peer_score(&self, peer: &PeerId) -> f64 {
         self.peers.get(peer).map(|p| p.score).unwrap_or(0.0)
     }
 
+    /// The score to hand to gossipsub's `set_application_score`, clamped to `app_score_floor`.
     pub fn get_app_score_option(&self, peer: &PeerId) -> Option<f64> {
-        self.app_scores.get(peer).copied()
+        let mut score = self.app_scores.get(peer).copied()?;
+        if let (Some(p), Some(true)) = (&self.cfg.probation, self.peers.get(peer).map(|s| s.probation)) {
+            score += p.app_score_penalty;
+        }
+        Some(self.cfg.app_score_floor.map_or(score, |floor| score.max(floor)))
     }
 
     pub fn is_quarantined(&self, peer: &PeerId) -> bool {
@@ -243,46 +1520,436 @@ impl Validator {
         self.peers.values().filter(|p| p.quarantined).count()
     }
 
+    /// Judge `peer` from its behaviour alone: score, offence count and the share of its
+    /// messages that were accepted, against `ValidatorConfig::classification`. Unknown peers
+    /// are `Honest`.
+    pub fn classify(&self, peer: &PeerId) -> Classification {
+        let Some(state) = self.peers.get(peer) else {
+            return Classification::Honest;
+        };
+        let t = &self.cfg.classification;
+        let offences = self.offences.get(peer).copied().unwrap_or(0);
+        let judged = state.accepted + state.rejected;
+        let ratio = (judged >= t.min_judged.max(1)).then(|| state.accepted as f64 / judged as f64);
+        if state.score <= t.malicious_score
+            || offences >= t.malicious_offences
+            || ratio.is_some_and(|r| r < t.malicious_acceptance)
+        {
+            Classification::Malicious
+        } else if state.score <= t.suspicious_score
+            || offences >= t.suspicious_offences
+            || ratio.is_some_and(|r| r < t.suspicious_acceptance)
+        {
+            Classification::Suspicious
+        } else {
+            Classification::Honest
+        }
+    }
+
+    /// `classify` for every tracked peer.
+    pub fn classify_all(&self) -> Vec<(PeerId, Classification)> {
+        self.peers.keys().map(|p| (*p, self.classify(p))).collect()
+    }
+
+    /// Rate-limiter outcomes for messages `peer` forwarded; zero for unknown peers.
+    pub fn throttle_stats(&self, peer: &PeerId) -> ThrottleStats {
+        self.peers.get(peer).map(|s| s.throttle).unwrap_or_default()
+    }
+
+    /// Tokens per second `peer` currently earns back: its forwarder bucket's refill rate,
+    /// capped by its author bucket's when `author_rate_limit` is set, since its own messages
+    /// must pass both. Untracked peers get the configured rates.
+    pub fn effective_rate(&self, peer: &PeerId) -> f64 {
+        let author_rate = self.cfg.author_rate_limit.as_ref().map(|l| l.sustained_rate);
+        let (forwarder, author) = match self.peers.get(peer) {
+            Some(s) => (s.bucket.refill_rate, s.author_bucket.as_ref().map(|b| b.refill_rate).or(author_rate)),
+            None => (self.cfg.sustained_rate, author_rate),
+        };
+        author.map_or(forwarder, |a| forwarder.min(a))
+    }
+
+    /// Burst capacity of `peer`'s forwarder bucket, shrunk while it is on probation. Untracked
+    /// peers get the configured capacity.
+    pub fn bucket_capacity(&self, peer: &PeerId) -> u32 {
+        self.peers.get(peer).map_or(self.cfg.burst_capacity, |s| s.bucket.capacity)
+    }
+
+    /// `throttle_stats` for every tracked peer that forwarded anything.
+    pub fn throttle_stats_all(&self) -> Vec<(PeerId, ThrottleStats)> {
+        self.peers.iter().filter(|(_, s)| s.throttle.attempts > 0).map(|(p, s)| (*p, s.throttle)).collect()
+    }
+
+    /// Time from the first time `peer` lost score (any penalty, offence or not) to its first
+    /// quarantine; `None` unless both happened.
+    pub fn time_to_quarantine(&self, peer: &PeerId) -> Option<Duration> {
+        let state = self.peers.get(peer)?;
+        Some(state.quarantined_at?.saturating_duration_since(state.first_offence?))
+    }
+
+    /// `time_to_quarantine` for every tracked peer that has one.
+    pub fn time_to_quarantine_all(&self) -> Vec<(PeerId, Duration)> {
+        self.peers.keys().filter_map(|p| Some((*p, self.time_to_quarantine(p)?))).collect()
+    }
+
+    /// Time from this validator's creation to the first accepted message blamed on `peer`
+    /// (its author, else forwarder): how long it took to reach us through the mesh.
+    pub fn time_to_first_accept(&self, peer: &PeerId) -> Option<Duration> {
+        Some(self.peers.get(peer)?.first_accept?.saturating_duration_since(self.started))
+    }
+
+    /// `time_to_first_accept` for every tracked peer that has one.
+    pub fn time_to_first_accept_all(&self) -> Vec<(PeerId, Duration)> {
+        self.peers.keys().filter_map(|p| Some((*p, self.time_to_first_accept(p)?))).collect()
+    }
+
+    /// Forget the per-reason decision counts, leaving peer state alone.
+    pub fn reset_decision_counts(&mut self) {
+        self.decisions.clear();
+    }
+
     pub fn dump_peer_states(&self) -> Vec<(libp2p::PeerId, f64, bool)> {
         self.peers.iter().map(|(p,s)| (*p, s.score, s.quarantined)).collect()
     }
 
+    /// Render the validator's own health in the Prometheus text exposition format, labelled
+    /// with `node`: peer table and dedupe sizes, evictions, a histogram of peer scores and
+    /// decisions by reason.
+    pub fn to_prometheus_text(&self, node_idx: usize) -> String {
+        let mut out = String::new();
+        for (name, value) in [
+            ("peers", self.peers.len()),
+            ("quarantined_peers", self.get_quarantined_count()),
+            ("dedupe_entries", self.dedupe_cache.len()),
+        ] {
+            let _ = writeln!(out, "# TYPE gossipsub_validator_{name} gauge");
+            let _ = writeln!(out, "gossipsub_validator_{name}{{node=\"{node_idx}\"}} {value}");
+        }
+        let _ = writeln!(out, "# TYPE gossipsub_validator_evictions_total counter");
+        let _ = writeln!(out, "gossipsub_validator_evictions_total{{node=\"{node_idx}\"}} {}", self.evictions);
+
+        let _ = writeln!(out, "# TYPE gossipsub_validator_peer_score histogram");
+        for le in SCORE_BUCKETS {
+            let n = self.peers.values().filter(|s| s.score <= le).count();
+            let _ = writeln!(out, "gossipsub_validator_peer_score_bucket{{node=\"{node_idx}\",le=\"{le}\"}} {n}");
+        }
+        let sum: f64 = self.peers.values().map(|s| s.score).sum();
+        let count = self.peers.len();
+        let _ = writeln!(out, "gossipsub_validator_peer_score_bucket{{node=\"{node_idx}\",le=\"+Inf\"}} {count}");
+        let _ = writeln!(out, "gossipsub_validator_peer_score_sum{{node=\"{node_idx}\"}} {sum}");
+        let _ = writeln!(out, "gossipsub_validator_peer_score_count{{node=\"{node_idx}\"}} {count}");
+
+        let _ = writeln!(out, "# TYPE gossipsub_validator_decisions_total counter");
+        for (reason, value) in &self.decisions {
+            let _ = writeln!(
+                out,
+                "gossipsub_validator_decisions_total{{node=\"{node_idx}\",reason=\"{reason}\"}} {value}"
+            );
+        }
+        out
+    }
+
+    /// Tracked peers for which `pred(peer, score, status)` holds, in no particular order.
+    pub fn peers_where(&self, pred: impl Fn(&PeerId, f64, PeerStatus) -> bool) -> Vec<PeerId> {
+        self.peers
+            .iter()
+            .filter(|(p, s)| pred(p, s.score, PeerStatus::of(s)))
+            .map(|(p, _)| *p)
+            .collect()
+    }
+
+    /// The configuration currently in force.
+    pub fn config(&self) -> &ValidatorConfig {
+        &self.cfg
+    }
+
+    /// Swap in a new configuration, keeping accumulated scores and offences. Quarantine flags
+    /// are recomputed under the new threshold, and the dedupe cache and author table are
+    /// trimmed (oldest first) if their capacity shrank.
+    pub fn update_config(&mut self, mut cfg: ValidatorConfig) {
+        cfg.apply_memory_budget();
+        warn_accept_despite(&cfg);
+        let now = self.now();
+        for (peer, state) in self.peers.iter_mut() {
+            let forced = self.offences.get(peer).is_some_and(|&n| n > MAX_OFFENCES);
+            let past_grace = now.saturating_duration_since(state.first_seen) >= cfg.min_age_before_quarantine;
+            let quarantined = past_grace && (cfg.below_quarantine_line(state.quarantined, state.score) || forced);
+            if quarantined != state.quarantined {
+                tracing::info!(peer = %peer, score = state.score, quarantined, "quarantine changed by config update");
+            }
+            state.set_quarantined(quarantined, now);
+            state.bucket.capacity = cfg.burst_capacity;
+            state.bucket.refill_rate = cfg.sustained_rate;
+            state.bucket.tokens = state.bucket.tokens.min(cfg.burst_capacity as f64);
+            state.refresh_probation(peer, &cfg);
+            match (&cfg.author_rate_limit, &mut state.author_bucket) {
+                (Some(limit), Some(bucket)) => {
+                    bucket.capacity = limit.burst_capacity;
+                    bucket.refill_rate = limit.sustained_rate;
+                    bucket.tokens = bucket.tokens.min(limit.burst_capacity as f64);
+                }
+                (None, bucket) => *bucket = None,
+                _ => {}
+            }
+        }
+        while self.dedupe_cache.len() > cfg.max_dedupe_entries {
+            if let Some((old, _)) = self.dedupe_cache.pop_front() {
+                self.dedupe_set.remove(&old);
+            }
+        }
+        self.authors.resize(cfg.max_tracked_authors);
+        self.cfg = cfg;
+    }
+
     fn update_peer_score(&mut self, peer: &PeerId, delta: f64) {
-        self.ensure_peer_exists(peer);
-        let state = self.peers.get_mut(peer).unwrap();
+        if self.cfg.observe_only {
+            tracing::debug!(peer = %peer, delta, "observe-only: score change not applied");
+            return;
+        }
+        let past_grace = self.past_grace(peer);
+        let forced = self.offences.get(peer).is_some_and(|&n| n > MAX_OFFENCES);
+        let now = self.now();
+        let state = self.peer_state_mut(peer);
         state.score += delta;
-        let was_quarantined = state.quarantined;
-        state.quarantined = state.score <= QUARANTINE_THRESHOLD;
+        // any loss starts the detection clock, whether or not it counts as an offence
+        if delta < 0.0 {
+            state.first_offence.get_or_insert(now);
+        }
+        let (was_quarantined, score) = (state.quarantined, state.score);
+        let below = self.cfg.below_quarantine_line(was_quarantined, score);
+        let state = self.peer_state_mut(peer);
+        state.set_quarantined((below || forced) && past_grace, now);
+        let Some(state) = self.peers.get_mut(peer) else { return };
+        state.refresh_probation(peer, &self.cfg);
+        let (score, quarantined, address) = (state.score, state.quarantined, state.address);
 
         // Update app score for libp2p integration
-        self.app_scores.insert(*peer, state.score);
+        self.app_scores.insert(*peer, score);
+        if let Some(ip) = address {
+            self.remember_address_score(ip, score);
+        }
 
         // Log score updates and transitions so we can debug why peers are quarantined
-        tracing::info!(peer = %peer, new_score = state.score, delta = delta, quarantined = state.quarantined, "peer score updated");
-        if !was_quarantined && state.quarantined {
-            tracing::warn!(peer = %peer, new_score = state.score, "peer entered quarantine");
+        tracing::info!(peer = %peer, new_score = score, delta = delta, quarantined = quarantined, "peer score updated");
+        if !was_quarantined && quarantined {
+            tracing::warn!(peer = %peer, new_score = score, "peer entered quarantine");
+        }
+    }
+
+    /// State for `peer`, inserting a fresh entry (and evicting another peer if the table is
+    /// full) when it isn't tracked. Never fails, so callers don't need to re-look it up.
+    fn peer_state_mut(&mut self, peer: &PeerId) -> &mut PeerState {
+        if !self.peers.contains_key(peer) && self.peers.len() >= self.cfg.max_peers {
+            // evict the most idle peer; equally idle peers go lowest PeerId first so the
+            // outcome doesn't depend on HashMap iteration order
+            let victim = self
+                .peers
+                .iter()
+                .min_by_key(|(p, s)| (s.last_activity, **p))
+                .map(|(p, _)| *p);
+            if let Some(old) = victim {
+                self.peers.remove(&old);
+                self.offences.remove(&old);
+                self.app_scores.remove(&old);
+                self.evictions += 1;
+            }
         }
+        let now = self.now();
+        let cfg = &self.cfg;
+        self.peers.entry(*peer).or_insert_with(|| PeerState::new(now, cfg))
     }
 
-    fn get_peer_last_seq(&self, peer: &PeerId) -> Option<u64> {
-        self.peers.get(peer).and_then(|p| p.last_seq)
+    fn touch(&mut self, peer: &PeerId) {
+        let now = self.now();
+        self.peer_state_mut(peer).last_activity = now;
     }
 
-    fn update_peer_last_seq(&mut self, peer: &PeerId, seq: u64) {
-        self.ensure_peer_exists(peer);
-        if let Some(state) = self.peers.get_mut(peer) {
-            state.last_seq = Some(seq);
+    /// Shrink every score toward zero by `factor` (clamped to 0..=1). Quarantine expires for
+    /// peers whose score climbs back over the exit threshold, unless their offence count
+    /// still forces it (see `decay_offences`).
+    pub fn decay_scores(&mut self, factor: f64) {
+        let factor = factor.clamp(0.0, 1.0);
+        for (peer, state) in self.peers.iter_mut() {
+            state.score *= factor;
+            let forced = self.offences.get(peer).copied().unwrap_or(0) > MAX_OFFENCES;
+            if state.quarantined && !self.cfg.below_quarantine_line(true, state.score) && !forced {
+                state.quarantined = false;
+                tracing::info!(peer = %peer, score = state.score, "peer released from quarantine");
+            }
+            state.refresh_probation(peer, &self.cfg);
+            self.app_scores.insert(*peer, state.score);
+        }
+        for score in self.address_scores.values_mut() {
+            *score *= factor;
         }
     }
 
-    fn ensure_peer_exists(&mut self, peer: &PeerId) {
-        if self.peers.len() >= MAX_PEERS {
-            // remove a random/first key to bound memory
-            if let Some(old) = self.peers.keys().next().cloned() {
-                self.peers.remove(&old);
+    /// Record that `peer` is connected from `ip`. Under `bind_reputation_to_address` a peer
+    /// scoring above the address's last known score drops to it (quarantine included), so
+    /// a fresh key doesn't launder a bad reputation; otherwise a no-op.
+    pub fn note_peer_address(&mut self, peer: &PeerId, ip: IpAddr) {
+        if !self.cfg.bind_reputation_to_address {
+            return;
+        }
+        let state = self.peer_state_mut(peer);
+        state.address = Some(ip);
+        let own = state.score;
+        match self.address_scores.get(&ip).copied() {
+            Some(inherited) if inherited < own => {
+                tracing::info!(peer = %peer, %ip, score = inherited, "peer inherits the score of its address");
+                self.update_peer_score(peer, inherited - own);
+            }
+            Some(_) => {}
+            None => self.remember_address_score(ip, own),
+        }
+    }
+
+    // fold `score` into `ip`'s reputation, keeping the lower of the two so a fresh key on the
+    // same address can't wash it clean; it only recovers through `decay_scores`. When the
+    // table is full, forget the best-scored address, which has the least to pass on
+    fn remember_address_score(&mut self, ip: IpAddr, score: f64) {
+        if !self.address_scores.contains_key(&ip) && self.address_scores.len() >= self.cfg.max_peers {
+            let best = self
+                .address_scores
+                .iter()
+                .max_by(|a, b| a.1.total_cmp(b.1).then(b.0.cmp(a.0)))
+                .map(|(ip, _)| *ip);
+            if let Some(best) = best {
+                self.address_scores.remove(&best);
+            }
+        }
+        let kept = self.address_scores.get(&ip).map_or(score, |existing| existing.min(score));
+        self.address_scores.insert(ip, kept);
+    }
+
+    // earn back `offences_forgiven_per_accept` of an offence for an accepted message
+    fn forgive_on_accept(&mut self, peer: &PeerId) {
+        let per_accept = self.cfg.offences_forgiven_per_accept.clamp(0.0, 1.0);
+        if per_accept == 0.0 || !self.offences.contains_key(peer) {
+            return;
+        }
+        let state = self.peer_state_mut(peer);
+        state.forgiveness += per_accept;
+        if state.forgiveness < 1.0 {
+            return;
+        }
+        state.forgiveness -= 1.0;
+        if let Some(count) = self.offences.get_mut(peer) {
+            *count -= 1;
+            if *count == 0 {
+                self.offences.remove(peer);
+                self.peer_state_mut(peer).forgiveness = 0.0;
+            }
+        }
+    }
+
+    /// Forgive one offence per peer, so the repeat-offence multiplier and forced quarantine
+    /// wear off for peers that stop misbehaving.
+    pub fn decay_offences(&mut self) {
+        self.offences.retain(|_, count| {
+            *count -= 1;
+            *count > 0
+        });
+    }
+
+    /// Forget peers we haven't seen forward or author anything for `max_idle`, including
+    /// their offence history and app score. Returns how many peers were removed.
+    pub fn prune_idle(&mut self, max_idle: Duration) -> usize {
+        let now = self.now();
+        let idle: Vec<PeerId> = self
+            .peers
+            .iter()
+            .filter(|(_, s)| now.saturating_duration_since(s.last_activity) > max_idle)
+            .map(|(p, _)| *p)
+            .collect();
+        for peer in &idle {
+            self.peers.remove(peer);
+            self.offences.remove(peer);
+            self.app_scores.remove(peer);
+        }
+        if !idle.is_empty() {
+            tracing::debug!(pruned = idle.len(), remaining = self.peers.len(), "pruned idle peers");
+        }
+        idle.len()
+    }
+
+    /// Content hashes currently held by the dedupe cache (at most `max_dedupe_entries`).
+    pub fn dedupe_len(&self) -> usize {
+        self.dedupe_cache.len()
+    }
+
+    /// Check that the internal tables agree with each other: the dedupe set and queue hold
+    /// the same keys, every app score belongs to a tracked peer and matches its score, the
+    /// peer table is within `max_peers`, and no free peer sits below the quarantine line.
+    /// That last check is skipped under `min_age_before_quarantine`, where quarantine is
+    /// applied lazily on the peer's next message. Quarantined peers above the line are
+    /// allowed, since offences can force quarantine at any score. Meant for tests and
+    /// fuzzing; returns the first violation found.
+    pub fn check_invariants(&self) -> Result<(), String> {
+        if self.dedupe_set.len() != self.dedupe_cache.len() {
+            return Err(format!(
+                "dedupe set holds {} keys but the queue {}",
+                self.dedupe_set.len(),
+                self.dedupe_cache.len()
+            ));
+        }
+        if let Some((key, _)) = self.dedupe_cache.iter().find(|(key, _)| !self.dedupe_set.contains(key)) {
+            return Err(format!("dedupe key {} queued but not in the set", hex::encode(key)));
+        }
+        for (peer, app_score) in &self.app_scores {
+            match self.peers.get(peer) {
+                None => return Err(format!("app score kept for untracked peer {peer}")),
+                Some(s) if s.score != *app_score => {
+                    return Err(format!("app score {app_score} for {peer} differs from its score {}", s.score))
+                }
+                Some(_) => {}
+            }
+        }
+        if let Some(peer) = self.offences.keys().find(|p| !self.peers.contains_key(p)) {
+            return Err(format!("offences kept for untracked peer {peer}"));
+        }
+        if self.peers.len() > self.cfg.max_peers {
+            return Err(format!("{} peers tracked, over max_peers {}", self.peers.len(), self.cfg.max_peers));
+        }
+        if self.cfg.min_age_before_quarantine.is_zero() {
+            for (peer, s) in &self.peers {
+                if !s.quarantined && self.cfg.below_quarantine_line(false, s.score) {
+                    return Err(format!("{peer} at score {} is below the quarantine line but free", s.score));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Make the validator consistent again after a `validate` of `bytes` panicked part-way.
+    /// The message's dedupe entry is dropped so a retransmission is judged afresh, the
+    /// indexes kept beside the peer table and dedupe queue are re-derived from them, and a
+    /// peer left below the quarantine line is quarantined. Score changes the interrupted call
+    /// already made are kept: each is a whole penalty for something the peer did, no worse
+    /// than the call running to the end.
+    pub fn recover_from_panic(&mut self, bytes: &[u8]) {
+        let key = content_hash(&self.cfg.message_domain, bytes);
+        self.dedupe_cache.retain(|(k, _)| *k != key);
+        self.dedupe_set = self.dedupe_cache.iter().map(|(k, _)| *k).collect();
+        let peers = &self.peers;
+        self.app_scores.retain(|peer, _| peers.contains_key(peer));
+        self.offences.retain(|peer, _| peers.contains_key(peer));
+        for (peer, app_score) in self.app_scores.iter_mut() {
+            *app_score = peers[peer].score;
+        }
+        let now = self.now();
+        let below: Vec<PeerId> = self
+            .peers
+            .iter()
+            .filter(|(_, s)| !s.quarantined && self.cfg.below_quarantine_line(false, s.score))
+            .map(|(peer, _)| *peer)
+            .collect();
+        for peer in below {
+            if self.past_grace(&peer) {
+                if let Some(s) = self.peers.get_mut(&peer) {
+                    s.set_quarantined(true, now);
+                }
             }
         }
-        self.peers.entry(*peer).or_insert_with(PeerState::default);
     }
 
     fn is_dupe(&self, hash: &[u8; 32]) -> bool {
@@ -290,37 +1957,245 @@ impl Validator {
     }
 
     fn add_to_dedupe(&mut self, hash: [u8; 32]) {
-        if self.dedupe_cache.len() >= MAX_DEDUPE_SIZE {
-            if let Some(old) = self.dedupe_cache.pop_front() {
+        while !self.dedupe_cache.is_empty() && self.dedupe_cache.len() >= self.cfg.max_dedupe_entries {
+            if let Some((old, _)) = self.dedupe_cache.pop_front() {
                 self.dedupe_set.remove(&old);
             }
         }
-        self.dedupe_cache.push_back(hash);
+        let now = self.now();
+        self.dedupe_cache.push_back((hash, now));
         self.dedupe_set.insert(hash);
     }
 
+    /// Capture scores, offences, quarantine flags and replay state, plus the newest
+    /// `max_persisted_dedupe` dedupe keys, so a restarted node can pick up where it left off.
+    pub fn snapshot(&self) -> ValidatorSnapshot {
+        let now = self.now();
+        let peers = self
+            .peers
+            .iter()
+            .map(|(peer, s)| PeerSnapshot {
+                peer: peer.to_bytes(),
+                score: s.score,
+                quarantined: s.quarantined,
+                offences: self.offences.get(peer).copied().unwrap_or(0),
+            })
+            .collect();
+        let authors = self.authors.iter().map(|(a, seq)| (a.to_bytes(), seq)).collect();
+        let skip = self.dedupe_cache.len().saturating_sub(self.cfg.max_persisted_dedupe);
+        let dedupe = self
+            .dedupe_cache
+            .iter()
+            .skip(skip)
+            .map(|(key, seen)| (*key, now.saturating_duration_since(*seen).as_millis() as u64))
+            .collect();
+        ValidatorSnapshot {
+            taken_at_ms: unix_ms(),
+            peers,
+            authors,
+            dedupe,
+        }
+    }
+
+    /// Build a validator from `snapshot` under `cfg`. Dedupe keys `cfg.dedupe_ttl` or older
+    /// (counting the time since the snapshot was taken) are dropped; entries with unparsable
+    /// peer ids are skipped.
+    pub fn restore(cfg: ValidatorConfig, snapshot: ValidatorSnapshot) -> Self {
+        let mut v = Validator::new(cfg);
+        let now = v.now();
+        let since_snapshot = unix_ms().saturating_sub(snapshot.taken_at_ms);
+        for p in snapshot.peers {
+            let Ok(peer) = PeerId::from_bytes(&p.peer) else { continue };
+            let state = v.peer_state_mut(&peer);
+            state.score = p.score;
+            state.quarantined = p.quarantined;
+            if let Some(state) = v.peers.get_mut(&peer) {
+                state.refresh_probation(&peer, &v.cfg);
+            }
+            if p.offences > 0 {
+                v.offences.insert(peer, p.offences);
+            }
+            v.app_scores.insert(peer, p.score);
+        }
+        for (author, seq) in snapshot.authors {
+            if let Ok(author) = PeerId::from_bytes(&author) {
+                v.authors.update(&author, seq);
+            }
+        }
+        let ttl_ms = v.cfg.dedupe_ttl.as_millis() as u64;
+        for (key, age_ms) in snapshot.dedupe {
+            let age_ms = age_ms.saturating_add(since_snapshot);
+            if age_ms >= ttl_ms {
+                continue;
+            }
+            if v.is_dupe(&key) {
+                continue;
+            }
+            let seen = now.checked_sub(Duration::from_millis(age_ms)).unwrap_or(now);
+            v.add_to_dedupe(key);
+            if let Some(last) = v.dedupe_cache.back_mut() {
+                last.1 = seen;
+            }
+        }
+        v
+    }
+
+    // whether `peer` has been known long enough to be quarantined (unknown peers are new)
+    fn past_grace(&self, peer: &PeerId) -> bool {
+        let min_age = self.cfg.min_age_before_quarantine;
+        min_age.is_zero()
+            || self
+                .peers
+                .get(peer)
+                .is_some_and(|s| self.now().saturating_duration_since(s.first_seen) >= min_age)
+    }
+
+    // apply a quarantine that was deferred while `peer` was in its grace period
+    fn apply_deferred_quarantine(&mut self, peer: &PeerId) {
+        if self.cfg.min_age_before_quarantine.is_zero() || !self.past_grace(peer) {
+            return;
+        }
+        let forced = self.get_offence_count(peer) > MAX_OFFENCES;
+        let threshold = self.cfg.quarantine_threshold;
+        let now = self.now();
+        if let Some(s) = self.peers.get_mut(peer) {
+            if !s.quarantined && (s.score <= threshold || forced) {
+                s.set_quarantined(true, now);
+                tracing::warn!(peer = %peer, score = s.score, "grace period over, peer entered quarantine");
+            }
+        }
+    }
+
+    // remember why `peer` lost score, for `explain`
+    fn note_penalty(&mut self, peer: &PeerId, reason: RejectReason, delta: f64) {
+        let recent = &mut self.peer_state_mut(peer).recent_penalties;
+        if recent.len() >= RECENT_PENALTIES {
+            recent.pop_front();
+        }
+        recent.push_back((reason, delta));
+    }
+
+    /// Why `peer` stands where it does: score, offences, its last few penalties (oldest
+    /// first), rate-limit tokens available right now and quarantine status. Unknown peers get
+    /// a blank explanation with `tracked: false`.
+    pub fn explain(&self, peer: &PeerId) -> PeerExplanation {
+        let offences = self.offences.get(peer).copied().unwrap_or(0);
+        let Some(state) = self.peers.get(peer) else {
+            return PeerExplanation {
+                peer: *peer,
+                tracked: false,
+                score: 0.0,
+                offences,
+                recent_penalties: Vec::new(),
+                tokens: self.cfg.burst_capacity as f64,
+                quarantined: false,
+            };
+        };
+        let bucket = &state.bucket;
+        let elapsed = self.now().saturating_duration_since(bucket.last).as_secs_f64();
+        PeerExplanation {
+            peer: *peer,
+            tracked: true,
+            score: state.score,
+            offences,
+            recent_penalties: state.recent_penalties.iter().copied().collect(),
+            tokens: (bucket.tokens + elapsed * bucket.refill_rate).min(bucket.capacity as f64),
+            quarantined: state.quarantined,
+        }
+    }
+
+    // base penalty for a rate-limited message, escalated once the forwarder's streak is long enough
+    fn rate_limit_penalty(&mut self, peer: &PeerId, now: Instant) -> f64 {
+        let base = self.cfg.penalties.rate_limited;
+        let Some(esc) = self.cfg.rate_limit_escalation.clone() else {
+            return base;
+        };
+        let state = self.peer_state_mut(peer);
+        if state.rate_limit_streak == 0 || now.duration_since(state.streak_started) > esc.window {
+            state.rate_limit_streak = 0;
+            state.streak_started = now;
+        }
+        state.rate_limit_streak += 1;
+        if state.rate_limit_streak >= esc.hits {
+            state.rate_limit_streak = 0;
+            tracing::warn!(peer = %peer, hits = esc.hits, "sustained rate-limit violations, escalating penalty");
+            esc.penalty
+        } else {
+            base
+        }
+    }
+
     // increments offences count, computes scaled delta, updates score and returns the effective delta
     pub fn record_offence_and_update(&mut self, peer: &PeerId, base_delta: f64) -> f64 {
+        if self.cfg.observe_only {
+            // what the offence would have cost, without counting it
+            let count = self.get_offence_count(peer) + 1;
+            let effective_delta = base_delta * (1.0 + (count as f64 - 1.0) * 0.5);
+            tracing::debug!(peer = %peer, offences = count, effective = effective_delta, "observe-only: offence not recorded");
+            return effective_delta;
+        }
         // increment offence count
         let count = self.offences.entry(*peer).or_insert(0);
         *count += 1;
         let count_val = *count;
+        let now = self.now();
         // scaling factor (each extra offence increases delta by 50%)
         let scale = 1.0 + ((count_val as f64 - 1.0) * 0.5).max(0.0);
         let effective_delta = base_delta * scale;
         self.update_peer_score(peer, effective_delta);
         tracing::info!(peer = %peer, offences = count_val, base = base_delta, effective = effective_delta, "offence recorded and score updated");
-        // if offences exceed 4, immediately quarantine
-        if count_val > 4 {
+        // if offences exceed the limit, immediately quarantine
+        if count_val > MAX_OFFENCES && self.past_grace(peer) {
             if let Some(s) = self.peers.get_mut(peer) {
-                s.quarantined = true;
+                s.set_quarantined(true, now);
                 tracing::warn!(peer = %peer, score = s.score, "peer forced into quarantine due to repeated offences");
             }
         }
         effective_delta
     }
 
-    #[allow(dead_code)]
+    // reject a frame we couldn't decode, softened by `decode_error_grace` for new peers
+    fn reject_codec(&mut self, target: &PeerId, e: CodecError) -> Decision {
+        let graced = !matches!(e, CodecError::TooLarge);
+        let (reason, penalty) = self.codec_rejection(e);
+        let Some(grace) = self.cfg.decode_error_grace.clone().filter(|_| graced) else {
+            return self.reject_content(target, reason, penalty);
+        };
+        let now = self.now();
+        let state = self.peer_state_mut(target);
+        if now.saturating_duration_since(state.first_seen) >= grace.window || state.graced_errors >= grace.max_errors {
+            return self.reject_content(target, reason, penalty);
+        }
+        state.graced_errors += 1;
+        self.note_rejection(target);
+        let delta = if self.trusted_authors.contains(target) || self.is_local(target) {
+            0.0
+        } else {
+            penalty * grace.penalty_factor.clamp(0.0, 1.0)
+        };
+        if delta != 0.0 {
+            self.update_peer_score(target, delta);
+            self.note_penalty(target, reason, delta);
+        }
+        tracing::debug!(peer = %target, %reason, delta, "codec error from new peer softened");
+        Decision {
+            acceptance: MessageAcceptance::Reject,
+            reason,
+            score_delta: delta,
+        }
+    }
+
+    /// Reason and base penalty for a message that failed to decode.
+    fn codec_rejection(&self, e: CodecError) -> (RejectReason, f64) {
+        let p = &self.cfg.penalties;
+        match e {
+            CodecError::Truncated => (RejectReason::Truncated, p.truncated),
+            CodecError::TooLarge => (RejectReason::TooLarge, p.too_large),
+            CodecError::TrailingBytes => (RejectReason::TrailingBytes, p.decode_error),
+            CodecError::UnknownVersion(_) | CodecError::InvalidEncoding => (RejectReason::DecodeError, p.decode_error),
+        }
+    }
+
     fn get_offence_count(&self, peer: &PeerId) -> u32 {
         *self.offences.get(peer).unwrap_or(&0)
     }
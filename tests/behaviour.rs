@@ -0,0 +1,40 @@
+use gossipsub_score_sim::behaviour::{Authenticity, Behaviour, MeshConfig, ValidationMode};
+use gossipsub_score_sim::codec::DEFAULT_MESSAGE_DOMAIN;
+use libp2p::identity::Keypair;
+
+#[test]
+fn behaviour_builds_in_every_compatible_validation_mode() {
+    use ValidationMode::*;
+    let cases = [
+        (Authenticity::Signed, Strict),
+        (Authenticity::Signed, Permissive),
+        (Authenticity::Signed, None),
+        (Authenticity::Anonymous, Permissive),
+        (Authenticity::Anonymous, Anonymous),
+        (Authenticity::Anonymous, None),
+    ];
+    for (authenticity, mode) in cases {
+        assert!(
+            Behaviour::new(Keypair::generate_ed25519(), "t", authenticity, mode, DEFAULT_MESSAGE_DOMAIN, &MeshConfig::default()).is_ok(),
+            "{authenticity:?} with {mode:?}"
+        );
+    }
+}
+
+#[test]
+fn contradictory_validation_mode_is_an_error() {
+    let key = Keypair::generate_ed25519();
+    let (domain, mesh) = (DEFAULT_MESSAGE_DOMAIN, MeshConfig::default());
+    assert!(Behaviour::new(key.clone(), "t", Authenticity::Anonymous, ValidationMode::Strict, domain, &mesh).is_err());
+    assert!(Behaviour::new(key, "t", Authenticity::Signed, ValidationMode::Anonymous, domain, &mesh).is_err());
+}
+
+#[test]
+fn behaviour_builds_with_custom_history_and_gossip() {
+    let build = |mesh: MeshConfig| {
+        Behaviour::new(Keypair::generate_ed25519(), "t", Authenticity::Signed, ValidationMode::Strict, DEFAULT_MESSAGE_DOMAIN, &mesh)
+    };
+    assert!(build(MeshConfig { history_length: 12, history_gossip: 8, gossip_lazy: 2 }).is_ok());
+    assert!(build(MeshConfig { history_length: 2, history_gossip: 2, gossip_lazy: 0 }).is_ok());
+    assert!(build(MeshConfig { history_length: 2, history_gossip: 3, ..Default::default() }).is_err());
+}
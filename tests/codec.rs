@@ -0,0 +1,60 @@
+use gossipsub_score_sim::codec::{decode, decode_strict, encode, CodecError, WireMessage, MAX_PAYLOAD_BYTES, WIRE_VERSION};
+
+#[test]
+fn crafted_inputs_map_to_each_codec_error() {
+    let good = encode(&WireMessage::Good { seq: 7, payload: vec![1, 2, 3] });
+    assert_eq!(good[0], WIRE_VERSION);
+    assert!(matches!(decode_strict(&good), Ok(WireMessage::Good { seq: 7, .. })));
+
+    assert_eq!(decode(&[]).unwrap_err(), CodecError::Truncated);
+    assert_eq!(decode(&good[..good.len() - 1]).unwrap_err(), CodecError::Truncated);
+
+    let mut future = good.clone();
+    future[0] = WIRE_VERSION + 1;
+    assert_eq!(decode(&future).unwrap_err(), CodecError::UnknownVersion(WIRE_VERSION + 1));
+
+    // variant tag 9 doesn't exist
+    assert_eq!(decode(&[WIRE_VERSION, 9, 0, 0, 0]).unwrap_err(), CodecError::InvalidEncoding);
+
+    // seq 1, then a length prefix far beyond the limit with no payload behind it
+    let mut huge = vec![WIRE_VERSION, 0, 0, 0, 0];
+    huge.extend_from_slice(&1u64.to_le_bytes());
+    huge.extend_from_slice(&(MAX_PAYLOAD_BYTES + 1).to_le_bytes());
+    assert_eq!(decode(&huge).unwrap_err(), CodecError::TooLarge);
+
+    let mut tail = good.clone();
+    tail.push(0);
+    assert!(decode(&tail).is_ok());
+    assert_eq!(decode_strict(&tail).unwrap_err(), CodecError::TrailingBytes);
+}
+
+#[test]
+fn compressed_frames_round_trip_within_the_limit() {
+    use gossipsub_score_sim::codec::{decompress, encode_compressed, COMPRESSED_FLAG};
+
+    let msg = WireMessage::Good { seq: 3, payload: vec![0; 4096] };
+    let packed = encode_compressed(&msg);
+    assert_eq!(packed[0], WIRE_VERSION | COMPRESSED_FLAG);
+    assert!(packed.len() < 100, "{} bytes", packed.len());
+
+    let frame = decompress(&packed, 8192).unwrap();
+    assert_eq!(&frame[..], &encode(&msg)[..]);
+    assert_eq!(decompress(&packed, 1024).unwrap_err(), CodecError::TooLarge);
+    assert_eq!(decompress(&packed[..packed.len() - 1], 8192).unwrap_err(), CodecError::Truncated);
+}
+
+#[test]
+fn junk_with_a_high_first_byte_is_a_decode_error_not_oversize() {
+    use gossipsub_score_sim::codec::decompress;
+    use gossipsub_score_sim::testing::deterministic_peer_id;
+    use gossipsub_score_sim::validator::{Validator, ValidatorConfig};
+
+    // even-length junk used to be read as run-length pairs and expand past the limit
+    let junk = [0xff; 64];
+    assert_eq!(&decompress(&junk, 128).unwrap()[..], &junk[..]);
+    assert_eq!(decode(&junk).unwrap_err(), CodecError::UnknownVersion(0xff));
+
+    let mut v = Validator::new(ValidatorConfig { max_message_bytes: 128, ..Default::default() });
+    let p = deterministic_peer_id(0);
+    assert_eq!(v.validate(&p, Some(&p), &junk).reason, "decode_error");
+}
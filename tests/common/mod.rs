@@ -0,0 +1,39 @@
+use std::sync::{Arc, Mutex};
+
+use tracing::field::{Field, Visit};
+use tracing::subscriber::DefaultGuard;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::Layer;
+
+/// Collects one field of every event logged on this thread, formatted as a string.
+struct FieldLog {
+    field: &'static str,
+    values: Arc<Mutex<Vec<String>>>,
+}
+
+impl Visit for FieldLog {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        if field.name() == self.field {
+            self.values.lock().unwrap().push(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == self.field {
+            self.values.lock().unwrap().push(format!("{value:?}"));
+        }
+    }
+}
+
+impl<S: tracing::Subscriber> Layer<S> for FieldLog {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        event.record(&mut FieldLog { field: self.field, values: self.values.clone() });
+    }
+}
+
+/// Capture `field` from events until the guard is dropped.
+pub fn capture_field(field: &'static str) -> (DefaultGuard, Arc<Mutex<Vec<String>>>) {
+    let values = Arc::new(Mutex::new(Vec::new()));
+    let layer = FieldLog { field, values: values.clone() };
+    (tracing::subscriber::set_default(tracing_subscriber::registry().with(layer)), values)
+}
@@ -0,0 +1,44 @@
+use gossipsub_score_sim::metrics::Counters;
+
+#[test]
+fn prometheus_text_contains_counters_and_reasons() {
+    let mut c = Counters {
+        accepted: 12,
+        rejected: 3,
+        ignored: 4,
+        ..Default::default()
+    };
+    c.by_reason.insert("ok".to_string(), 12);
+    c.by_reason.insert("oversize".to_string(), 3);
+
+    let text = c.to_prometheus_text(2);
+    assert!(text.contains("# TYPE gossipsub_messages_accepted_total counter"));
+    assert!(text.contains("gossipsub_messages_accepted_total{node=\"2\"} 12"));
+    assert!(text.contains("gossipsub_messages_rejected_total{node=\"2\"} 3"));
+    assert!(text.contains("gossipsub_messages_ignored_total{node=\"2\"} 4"));
+    assert!(text.contains("# TYPE gossipsub_decisions_total counter"));
+    assert!(text.contains("gossipsub_decisions_total{node=\"2\",reason=\"oversize\"} 3"));
+}
+
+#[test]
+fn validator_prometheus_text_reports_state_gauges() {
+    use gossipsub_score_sim::codec::{encode, WireMessage};
+    use gossipsub_score_sim::validator::{Validator, ValidatorConfig};
+    use libp2p::PeerId;
+
+    let mut v = Validator::new(ValidatorConfig::default());
+    let p = PeerId::random();
+    v.validate(&p, Some(&p), &encode(&WireMessage::Good { seq: 1, payload: vec![1] }));
+    let q = PeerId::random();
+    v.validate(&q, Some(&q), &encode(&WireMessage::Malicious));
+
+    let text = v.to_prometheus_text(3);
+    assert!(text.contains("# TYPE gossipsub_validator_peers gauge"));
+    assert!(text.contains("gossipsub_validator_peers{node=\"3\"} 2"));
+    assert!(text.contains("gossipsub_validator_quarantined_peers{node=\"3\"} 1"));
+    assert!(text.contains("gossipsub_validator_dedupe_entries{node=\"3\"} 2"));
+    assert!(text.contains("gossipsub_validator_evictions_total{node=\"3\"} 0"));
+    assert!(text.contains("gossipsub_validator_peer_score_bucket{node=\"3\",le=\"-50\"} 1"));
+    assert!(text.contains("gossipsub_validator_peer_score_count{node=\"3\"} 2"));
+    assert!(text.contains("gossipsub_validator_decisions_total{node=\"3\",reason=\"malicious_payload\"} 1"));
+}
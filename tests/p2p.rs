@@ -0,0 +1,692 @@
+use gossipsub_score_sim::behaviour::Authenticity;
+use gossipsub_score_sim::codec::{encode, WireMessage};
+use gossipsub_score_sim::p2p::{
+    reported_acceptance, spawn_node, validate_guarded, NodeCommand, NodeConfig, NodeEvent, NodeHandle,
+    NodeSummary, ReportAs, SubscribeError,
+};
+use gossipsub_score_sim::validator::{RejectReason, Validator, ValidatorConfig};
+use libp2p::gossipsub::MessageAcceptance;
+use libp2p::PeerId;
+use libp2p::multiaddr::Protocol;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{sleep, timeout, Duration};
+
+fn free_port() -> u16 {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+fn node_config(idx: usize, listen_port: u16) -> NodeConfig {
+    NodeConfig {
+        idx,
+        listen_port,
+        ..Default::default()
+    }
+}
+
+async fn listen_addr(rx: &mut mpsc::Receiver<NodeEvent>) -> libp2p::Multiaddr {
+    timeout(Duration::from_secs(5), async {
+        loop {
+            if let Some(NodeEvent::NewListenAddr(a)) = rx.recv().await {
+                break a;
+            }
+        }
+    })
+    .await
+    .expect("listen address")
+}
+
+async fn summary(handle: &NodeHandle, rx: &mut mpsc::Receiver<NodeEvent>) -> NodeSummary {
+    handle.cmd.send(NodeCommand::Shutdown).await.unwrap();
+    timeout(Duration::from_secs(5), async {
+        loop {
+            match rx.recv().await {
+                Some(NodeEvent::Summary(s)) => break *s,
+                Some(_) => continue,
+                None => panic!("node stopped without a summary"),
+            }
+        }
+    })
+    .await
+    .expect("summary")
+}
+
+/// Spawn two nodes, connect the second to the first, subscribe both and give the mesh
+/// a moment to form.
+async fn connected_pair(
+    a: NodeConfig,
+    b: NodeConfig,
+) -> ((NodeHandle, mpsc::Receiver<NodeEvent>), (NodeHandle, mpsc::Receiver<NodeEvent>)) {
+    let (ha, mut ra) = spawn_node(a, vec![], None).unwrap();
+    let (hb, mut rb) = spawn_node(b, vec![], None).unwrap();
+    let addr = listen_addr(&mut ra).await;
+    listen_addr(&mut rb).await;
+    hb.cmd.send(NodeCommand::Dial { addr }).await.unwrap();
+    ha.cmd.send(NodeCommand::Subscribe).await.unwrap();
+    hb.cmd.send(NodeCommand::Subscribe).await.unwrap();
+    sleep(Duration::from_secs(2)).await;
+    ((ha, ra), (hb, rb))
+}
+
+#[tokio::test]
+async fn fixed_listen_port_is_reported() {
+    let port = free_port();
+    let (_handle, mut rx) = spawn_node(node_config(3, port), vec![], None).unwrap();
+
+    let addr = listen_addr(&mut rx).await;
+    assert!(addr.iter().any(|p| p == Protocol::Tcp(port)), "unexpected addr {addr}");
+}
+
+#[tokio::test]
+async fn port_in_use_names_the_node() {
+    let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = taken.local_addr().unwrap().port();
+
+    let err = spawn_node(node_config(7, port), vec![], None).err().expect("port is taken");
+    assert!(format!("{err:#}").contains("node 7"), "{err:#}");
+}
+
+#[test]
+fn failed_validation_still_yields_ignore() {
+    let (decision, completed) = validate_guarded(|| panic!("validator blew up"));
+    assert!(!completed);
+    assert!(matches!(decision.acceptance, MessageAcceptance::Ignore));
+    assert_eq!(decision.reason, "validation_failed");
+
+    let mut v = Validator::new(ValidatorConfig::default());
+    let p = PeerId::random();
+    let (decision, completed) = validate_guarded(|| v.validate(&p, Some(&p), &[0u8; 10]));
+    assert!(completed);
+    assert_eq!(decision.reason, "decode_error");
+}
+
+#[test]
+fn validator_is_consistent_after_a_panicking_validate() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    // the payload check runs after the message entered the dedupe cache
+    let mut v = Validator::new(ValidatorConfig::default());
+    let blow_up = AtomicBool::new(true);
+    v.set_payload_validator(Box::new(move |_| {
+        assert!(!blow_up.swap(false, Ordering::SeqCst), "payload check blew up");
+        Ok(())
+    }));
+    let p = PeerId::random();
+    let bytes = encode(&WireMessage::Good { seq: 1, payload: vec![1; 8] });
+    let (_, completed) = validate_guarded(|| v.validate(&p, Some(&p), &bytes));
+    assert!(!completed);
+
+    v.recover_from_panic(&bytes);
+    v.check_invariants().unwrap();
+    // a retransmission is judged on its merits, not dropped as a duplicate
+    assert_eq!(v.validate(&p, Some(&p), &bytes).reason, RejectReason::Ok);
+    v.check_invariants().unwrap();
+}
+
+#[test]
+fn reason_mapped_to_ignore_is_reported_as_ignore() {
+    let mut v = Validator::new(ValidatorConfig { max_message_bytes: 8, ..Default::default() });
+    let p = PeerId::random();
+    let decision = v.validate(&p, Some(&p), &[0u8; 64]);
+    assert_eq!(decision.reason, "oversize");
+    assert!(matches!(decision.acceptance, MessageAcceptance::Reject));
+
+    let report_as = [(RejectReason::Oversize, ReportAs::Ignore)].into_iter().collect();
+    assert!(matches!(reported_acceptance(&decision, &report_as), MessageAcceptance::Ignore));
+    // unmapped reasons report the validator's own verdict
+    let q = PeerId::random();
+    let decode = v.validate(&q, Some(&q), &[0u8; 4]);
+    assert_eq!(decode.reason, "decode_error");
+    assert!(matches!(reported_acceptance(&decode, &report_as), MessageAcceptance::Reject));
+}
+
+#[tokio::test]
+async fn anonymous_messages_are_attributed_to_forwarder() {
+    let anonymous = |idx| NodeConfig {
+        authenticity: Authenticity::Anonymous,
+        ..node_config(idx, 0)
+    };
+    let ((publisher, _prx), (receiver, mut rrx)) = connected_pair(anonymous(0), anonymous(1)).await;
+
+    // Mark the publisher as bad: with no source on the message, honesty must follow
+    // the forwarder (the publisher itself) just like scoring does.
+    receiver
+        .cmd
+        .send(NodeCommand::SetBadPeers { bad_peer_ids: vec![publisher.peer_id] })
+        .await
+        .unwrap();
+    for seq in 1..=3 {
+        let data = encode(&WireMessage::Good { seq, payload: vec![seq as u8; 16] });
+        publisher.cmd.send(NodeCommand::Publish { data }).await.unwrap();
+    }
+    sleep(Duration::from_millis(500)).await;
+
+    let s = summary(&receiver, &mut rrx).await;
+    assert_eq!(s.no_source_messages, 3);
+    assert_eq!(s.accepted, 3);
+    assert_eq!(s.honest_accepted, 0);
+}
+
+#[tokio::test]
+async fn summary_reports_mesh_and_connected_peers() {
+    let mut nodes = Vec::new();
+    for idx in 0..3 {
+        let (h, mut rx) = spawn_node(node_config(idx, 0), vec![], None).unwrap();
+        let addr = listen_addr(&mut rx).await;
+        nodes.push((h, rx, addr));
+    }
+    // Star around node 0.
+    for (h, _, _) in &nodes[1..] {
+        h.cmd.send(NodeCommand::Dial { addr: nodes[0].2.clone() }).await.unwrap();
+    }
+    for (h, _, _) in &nodes {
+        h.cmd.send(NodeCommand::Subscribe).await.unwrap();
+    }
+    sleep(Duration::from_secs(3)).await;
+
+    let (hub, mut hub_rx, _) = nodes.remove(0);
+    let s = summary(&hub, &mut hub_rx).await;
+    assert_eq!(s.connected_peer_count, 2);
+    assert!(s.mesh_peer_count >= 1 && s.mesh_peer_count <= s.connected_peer_count, "{s:?}");
+    for (h, mut rx, _) in nodes {
+        let s = summary(&h, &mut rx).await;
+        assert!(s.mesh_peer_count <= 1, "{s:?}");
+    }
+}
+
+#[tokio::test]
+async fn disconnect_command_emits_event() {
+    let ((a, mut arx), (b, _brx)) = connected_pair(node_config(0, 0), node_config(1, 0)).await;
+    a.cmd.send(NodeCommand::Disconnect { peer: b.peer_id }).await.unwrap();
+
+    let peer = timeout(Duration::from_secs(5), async {
+        loop {
+            match arx.recv().await {
+                Some(NodeEvent::PeerDisconnected(p)) => break p,
+                Some(_) => continue,
+                None => panic!("node stopped"),
+            }
+        }
+    })
+    .await
+    .expect("disconnect event");
+    assert_eq!(peer, b.peer_id);
+}
+
+#[tokio::test]
+async fn graylisted_node_pauses_publishing() {
+    let publisher = NodeConfig { min_publish_peers: 1, ..node_config(0, 0) };
+    let strict = NodeConfig { max_message_bytes: 64, ..node_config(1, 0) };
+    let ((a, mut arx), (_b, _brx)) = connected_pair(publisher, strict).await;
+
+    // One oversize message drives our score at the receiver past graylist; its next
+    // heartbeat prunes us and we lose our only mesh peer.
+    let big = encode(&WireMessage::Good { seq: 0, payload: vec![7; 256] });
+    a.cmd.send(NodeCommand::Publish { data: big }).await.unwrap();
+    timeout(Duration::from_secs(10), async {
+        loop {
+            let (reply, rx) = oneshot::channel();
+            a.cmd.send(NodeCommand::QueryState { reply }).await.unwrap();
+            if rx.await.unwrap().mesh_peer_count == 0 {
+                break;
+            }
+            sleep(Duration::from_millis(250)).await;
+        }
+    })
+    .await
+    .expect("receiver prunes us from its mesh");
+
+    for seq in 1..=3 {
+        let data = encode(&WireMessage::Good { seq, payload: vec![1] });
+        a.cmd.send(NodeCommand::Publish { data }).await.unwrap();
+    }
+    let s = summary(&a, &mut arx).await;
+    assert_eq!(s.mesh_peer_count, 0);
+    assert_eq!(s.paused_publishes, 3);
+    assert_eq!(s.honest_published, 1);
+}
+
+#[tokio::test]
+async fn summary_includes_gossipsub_composite_scores() {
+    let ((a, mut arx), (b, _brx)) = connected_pair(node_config(0, 0), node_config(1, 0)).await;
+    let s = summary(&a, &mut arx).await;
+    let score = s
+        .composite_scores
+        .iter()
+        .find(|(p, _)| *p == b.peer_id)
+        .map(|(_, score)| *score)
+        .expect("connected peer is scored");
+    assert!(score.is_finite());
+}
+
+#[tokio::test]
+async fn one_message_delivered_twice_counts_once_as_unique() {
+    let (publisher, mut prx) = spawn_node(node_config(0, 0), vec![], None).unwrap();
+    let addr = listen_addr(&mut prx).await;
+    let mut receivers = Vec::new();
+    for idx in 1..=2 {
+        let (h, mut rx) = spawn_node(node_config(idx, 0), vec![], None).unwrap();
+        listen_addr(&mut rx).await;
+        h.cmd.send(NodeCommand::Dial { addr: addr.clone() }).await.unwrap();
+        h.cmd.send(NodeCommand::Subscribe).await.unwrap();
+        receivers.push((h, rx));
+    }
+    publisher.cmd.send(NodeCommand::Subscribe).await.unwrap();
+    sleep(Duration::from_secs(2)).await;
+
+    let data = encode(&WireMessage::Good { seq: 1, payload: vec![1] });
+    publisher.cmd.send(NodeCommand::Publish { data }).await.unwrap();
+    sleep(Duration::from_secs(1)).await;
+
+    let mut summaries = vec![(0, summary(&publisher, &mut prx).await)];
+    for (idx, (h, rx)) in receivers.iter_mut().enumerate() {
+        summaries.push((idx + 1, summary(h, rx).await));
+    }
+    let report = gossipsub_score_sim::sim::build_report(summaries, 3, 0);
+    assert_eq!(report.total_accepted, 2);
+    assert_eq!(report.unique_messages, 1);
+}
+
+#[tokio::test]
+async fn decision_events_stream_validated_messages() {
+    let receiver = NodeConfig { emit_decisions: true, ..node_config(1, 0) };
+    let ((publisher, _prx), (_r, mut rrx)) = connected_pair(node_config(0, 0), receiver).await;
+
+    let data = encode(&WireMessage::Good { seq: 1, payload: vec![1] });
+    publisher.cmd.send(NodeCommand::Publish { data }).await.unwrap();
+
+    let (author, reason, acceptance) = timeout(Duration::from_secs(5), async {
+        loop {
+            match rrx.recv().await {
+                Some(NodeEvent::Decision { author, reason, acceptance, .. }) => break (author, reason, acceptance),
+                Some(_) => continue,
+                None => panic!("node stopped"),
+            }
+        }
+    })
+    .await
+    .expect("decision event");
+    assert_eq!(author, Some(publisher.peer_id));
+    assert_eq!(reason, "ok");
+    assert!(matches!(acceptance, MessageAcceptance::Accept));
+}
+
+#[tokio::test]
+async fn batched_app_scores_coalesce_per_interval() {
+    use gossipsub_score_sim::p2p::AppScoreUpdate;
+
+    let receiver = NodeConfig {
+        app_score_update: AppScoreUpdate::Batched,
+        ignore_penalty: 0.5,
+        ..node_config(1, 0)
+    };
+    let ((publisher, _prx), (r, mut rrx)) = connected_pair(node_config(0, 0), receiver).await;
+
+    // the first is accepted, the rest are replays: each costs the publisher a little score
+    for i in 0..=10u8 {
+        let data = encode(&WireMessage::Good { seq: 1, payload: vec![i] });
+        publisher.cmd.send(NodeCommand::Publish { data }).await.unwrap();
+    }
+    sleep(Duration::from_millis(1500)).await;
+
+    let s = summary(&r, &mut rrx).await;
+    assert_eq!(s.ignored, 10);
+    // per-message mode would make 10 calls; batching allows one per flush (two ticks + shutdown)
+    assert!((1..=3).contains(&s.app_score_updates), "{} updates", s.app_score_updates);
+}
+
+#[tokio::test]
+async fn batched_app_scores_also_coalesce_decay() {
+    use gossipsub_score_sim::p2p::AppScoreUpdate;
+    use gossipsub_score_sim::validator::DecayConfig;
+
+    let receiver = NodeConfig {
+        app_score_update: AppScoreUpdate::Batched,
+        score_decay: Some(DecayConfig { interval: Duration::from_millis(20), score_factor: 0.9 }),
+        ..node_config(1, 0)
+    };
+    let ((publisher, _prx), (r, mut rrx)) = connected_pair(node_config(0, 0), receiver).await;
+
+    // one offence to decay, then dozens of decay ticks before shutdown
+    publisher.cmd.send(NodeCommand::Publish { data: encode(&WireMessage::Malicious) }).await.unwrap();
+    sleep(Duration::from_millis(1500)).await;
+
+    let s = summary(&r, &mut rrx).await;
+    assert_eq!(s.rejected, 1);
+    // ~75 decay ticks, but decay goes through the same flush as message-driven updates: at
+    // most one per flush tick over the node's life (connecting included) plus shutdown
+    assert!((1..=5).contains(&s.app_score_updates), "{} updates", s.app_score_updates);
+}
+
+#[tokio::test]
+async fn nodes_sharing_a_message_domain_agree_on_ids() {
+    use gossipsub_score_sim::behaviour::message_id;
+
+    let domain = |idx| NodeConfig { message_domain: "test-domain:".to_string(), ..node_config(idx, 0) };
+    let ((a, mut arx), (b, mut brx)) = connected_pair(domain(0), domain(1)).await;
+
+    let from_a = encode(&WireMessage::Good { seq: 1, payload: vec![1] });
+    let from_b = encode(&WireMessage::Good { seq: 1, payload: vec![2] });
+    a.cmd.send(NodeCommand::Publish { data: from_a.clone() }).await.unwrap();
+    b.cmd.send(NodeCommand::Publish { data: from_b.clone() }).await.unwrap();
+    sleep(Duration::from_millis(500)).await;
+
+    let sa = summary(&a, &mut arx).await;
+    let sb = summary(&b, &mut brx).await;
+    assert!(sb.accepted_ids.contains(&message_id("test-domain:", &from_a)), "{:?}", sb.accepted_ids);
+    assert!(sa.accepted_ids.contains(&message_id("test-domain:", &from_b)), "{:?}", sa.accepted_ids);
+    assert_ne!(message_id("test-domain:", &from_a), message_id("other:", &from_a));
+}
+
+#[tokio::test]
+async fn quarantined_peer_reconnect_is_refused_during_backoff() {
+    let (a, mut arx) = spawn_node(NodeConfig { max_message_bytes: 64, ..node_config(0, 0) }, vec![], None).unwrap();
+    let (b, mut brx) = spawn_node(node_config(1, 0), vec![], None).unwrap();
+    let addr = listen_addr(&mut arx).await;
+    listen_addr(&mut brx).await;
+    b.cmd.send(NodeCommand::Dial { addr: addr.clone() }).await.unwrap();
+    a.cmd.send(NodeCommand::Subscribe).await.unwrap();
+    b.cmd.send(NodeCommand::Subscribe).await.unwrap();
+    sleep(Duration::from_secs(2)).await;
+
+    // one oversize message is enough to quarantine the publisher
+    let big = encode(&WireMessage::Good { seq: 0, payload: vec![7; 256] });
+    b.cmd.send(NodeCommand::Publish { data: big }).await.unwrap();
+    timeout(Duration::from_secs(5), async {
+        loop {
+            let (reply, rx) = oneshot::channel();
+            a.cmd.send(NodeCommand::QueryState { reply }).await.unwrap();
+            if rx.await.unwrap().peer_scores.iter().any(|(p, _, q)| *p == b.peer_id && *q) {
+                break;
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("publisher quarantined");
+
+    a.cmd.send(NodeCommand::Disconnect { peer: b.peer_id }).await.unwrap();
+    timeout(Duration::from_secs(5), async {
+        while !matches!(arx.recv().await, Some(NodeEvent::PeerDisconnected(_))) {}
+    })
+    .await
+    .expect("disconnect event");
+
+    b.cmd.send(NodeCommand::Dial { addr }).await.unwrap();
+    sleep(Duration::from_secs(1)).await;
+
+    let s = summary(&a, &mut arx).await;
+    assert!(s.refused_reconnects >= 1, "{s:?}");
+    assert_eq!(s.connected_peer_count, 0);
+}
+
+#[tokio::test]
+async fn ipv6_listen_addr_is_reported() {
+    let cfg = NodeConfig { listen_addr: "/ip6/::1/tcp/0".parse().unwrap(), ..node_config(0, 0) };
+    let (_handle, mut rx) = spawn_node(cfg, vec![], None).unwrap();
+
+    let addr = listen_addr(&mut rx).await;
+    assert!(addr.iter().any(|p| p == Protocol::Ip6(std::net::Ipv6Addr::LOCALHOST)), "unexpected addr {addr}");
+}
+
+#[tokio::test]
+async fn counters_can_be_fetched_mid_run() {
+    let ((publisher, _prx), (r, _rrx)) = connected_pair(node_config(0, 0), node_config(1, 0)).await;
+
+    for seq in 1..=3 {
+        let data = encode(&WireMessage::Good { seq, payload: vec![seq as u8] });
+        publisher.cmd.send(NodeCommand::Publish { data }).await.unwrap();
+    }
+    // one the receiver can't decode
+    let junk = vec![0u8; 10];
+    publisher.cmd.send(NodeCommand::Publish { data: junk }).await.unwrap();
+    sleep(Duration::from_millis(500)).await;
+
+    let (reply, rx) = oneshot::channel();
+    r.cmd.send(NodeCommand::GetCounters { reply }).await.unwrap();
+    let c = rx.await.unwrap();
+    assert_eq!(c.accepted + c.rejected + c.ignored, 4);
+    assert_eq!(c.accepted, 3);
+    assert_eq!(c.by_reason.values().sum::<u64>(), 4);
+}
+
+#[tokio::test]
+async fn reset_metrics_zeroes_counters_but_keeps_scores() {
+    let ((publisher, _prx), (r, _rrx)) = connected_pair(node_config(0, 0), node_config(1, 0)).await;
+
+    let data = encode(&WireMessage::Good { seq: 1, payload: vec![1] });
+    publisher.cmd.send(NodeCommand::Publish { data }).await.unwrap();
+    publisher.cmd.send(NodeCommand::Publish { data: vec![0u8; 10] }).await.unwrap();
+    sleep(Duration::from_millis(500)).await;
+
+    r.cmd.send(NodeCommand::ResetMetrics).await.unwrap();
+
+    let (reply, rx) = oneshot::channel();
+    r.cmd.send(NodeCommand::GetCounters { reply }).await.unwrap();
+    let c = rx.await.unwrap();
+    assert_eq!(c.accepted + c.rejected + c.ignored, 0);
+    assert!(c.by_reason.is_empty());
+
+    let (reply, rx) = oneshot::channel();
+    r.cmd.send(NodeCommand::QueryState { reply }).await.unwrap();
+    let state = rx.await.unwrap();
+    let score = state.peer_scores.iter().find(|(p, _, _)| *p == publisher.peer_id).map(|(_, s, _)| *s);
+    assert!(score.is_some_and(|s| s < 0.0), "{:?}", state.peer_scores);
+}
+
+#[tokio::test]
+async fn offloaded_validation_keeps_decisions_and_order() {
+    let receiver = NodeConfig { validation_worker: true, ..node_config(1, 0) };
+    let ((publisher, _prx), (r, _rrx)) = connected_pair(node_config(0, 0), receiver).await;
+
+    for seq in 1..=5 {
+        let data = encode(&WireMessage::Good { seq, payload: vec![seq as u8] });
+        publisher.cmd.send(NodeCommand::Publish { data }).await.unwrap();
+    }
+    // an older sequence number after the newer ones is only caught if order is kept
+    let replay = encode(&WireMessage::Good { seq: 2, payload: vec![99] });
+    publisher.cmd.send(NodeCommand::Publish { data: replay }).await.unwrap();
+    publisher.cmd.send(NodeCommand::Publish { data: vec![0u8; 10] }).await.unwrap();
+    sleep(Duration::from_millis(500)).await;
+
+    let (reply, rx) = oneshot::channel();
+    r.cmd.send(NodeCommand::GetCounters { reply }).await.unwrap();
+    let c = rx.await.unwrap();
+    assert_eq!(c.accepted, 5, "{c:?}");
+    assert_eq!(c.by_reason.get("replay_or_old_seq"), Some(&1), "{c:?}");
+    assert_eq!(c.by_reason.get("decode_error"), Some(&1), "{c:?}");
+}
+
+#[tokio::test]
+async fn burst_past_pending_cap_is_shed_and_counted() {
+    // no validation slots at all: every message of the burst is shed rather than queued
+    let receiver = NodeConfig { validation_worker: true, max_pending_validations: Some(0), ..node_config(1, 0) };
+    let ((publisher, _prx), (r, _rrx)) = connected_pair(node_config(0, 0), receiver).await;
+
+    for seq in 1..=5 {
+        let data = encode(&WireMessage::Good { seq, payload: vec![seq as u8] });
+        publisher.cmd.send(NodeCommand::Publish { data }).await.unwrap();
+    }
+    sleep(Duration::from_millis(500)).await;
+
+    let (reply, rx) = oneshot::channel();
+    r.cmd.send(NodeCommand::GetCounters { reply }).await.unwrap();
+    let c = rx.await.unwrap();
+    assert_eq!(c.shed_validations, 5, "{c:?}");
+    assert_eq!(c.ignored, 5, "{c:?}");
+    assert_eq!(c.accepted, 0, "{c:?}");
+    assert!(c.by_reason.is_empty(), "shed messages are never validated: {c:?}");
+}
+
+#[tokio::test]
+async fn pending_cap_sheds_only_the_overflow_of_a_burst() {
+    let receiver = NodeConfig { validation_worker: true, max_pending_validations: Some(2), ..node_config(1, 0) };
+    let ((publisher, _prx), (r, _rrx)) = connected_pair(node_config(0, 0), receiver).await;
+
+    // park the worker so the burst backs up behind it: two fill the slots, three are shed
+    let (release, hold) = oneshot::channel();
+    r.cmd.send(NodeCommand::HoldValidation { release: hold }).await.unwrap();
+    for seq in 1..=5 {
+        let data = encode(&WireMessage::Good { seq, payload: vec![seq as u8] });
+        publisher.cmd.send(NodeCommand::Publish { data }).await.unwrap();
+    }
+    sleep(Duration::from_millis(500)).await;
+    release.send(()).unwrap();
+    sleep(Duration::from_millis(200)).await;
+
+    let (reply, rx) = oneshot::channel();
+    r.cmd.send(NodeCommand::GetCounters { reply }).await.unwrap();
+    let c = rx.await.unwrap();
+    assert_eq!(c.shed_validations, 3, "{c:?}");
+    assert_eq!(c.ignored, 3, "{c:?}");
+    assert_eq!(c.accepted, 2, "{c:?}");
+}
+
+#[tokio::test]
+async fn shadow_validator_sees_every_message_and_is_tallied_apart() {
+    // the shadow thinks every message is oversize; the primary accepts them all
+    let shadow = ValidatorConfig { max_message_bytes: 8, ..Default::default() };
+    let receiver = NodeConfig { shadow: Some(shadow), ..node_config(1, 0) };
+    let ((publisher, _prx), (r, mut rrx)) = connected_pair(node_config(0, 0), receiver).await;
+
+    for seq in 1..=5 {
+        let data = encode(&WireMessage::Good { seq, payload: vec![seq as u8; 16] });
+        publisher.cmd.send(NodeCommand::Publish { data }).await.unwrap();
+    }
+    sleep(Duration::from_millis(500)).await;
+
+    let s = summary(&r, &mut rrx).await;
+    assert_eq!(s.accepted, 5, "{s:?}");
+    assert_eq!(s.quarantined_peers, 0, "{s:?}");
+    let shadow = s.shadow.expect("shadow tallies");
+    let c = &shadow.counters;
+    assert_eq!(c.accepted + c.rejected + c.ignored, 5, "{shadow:?}");
+    assert_eq!(c.accepted, 0, "{shadow:?}");
+    // the first oversize message quarantines the publisher, in the shadow only
+    assert_eq!(c.by_reason.get("oversize"), Some(&1), "{shadow:?}");
+    assert_eq!(shadow.quarantined_peers, 1, "{shadow:?}");
+}
+
+#[tokio::test]
+async fn repeated_subscribe_is_reported_as_an_event() {
+    let (h, mut rx) = spawn_node(node_config(0, 0), vec![], None).unwrap();
+    listen_addr(&mut rx).await;
+    h.cmd.send(NodeCommand::Subscribe).await.unwrap();
+    h.cmd.send(NodeCommand::Subscribe).await.unwrap();
+
+    let error = timeout(Duration::from_secs(5), async {
+        loop {
+            match rx.recv().await {
+                Some(NodeEvent::SubscribeFailed(error)) => break error,
+                Some(_) => continue,
+                None => panic!("node stopped"),
+            }
+        }
+    })
+    .await
+    .expect("subscribe failure event");
+    assert!(matches!(error, SubscribeError::AlreadySubscribed), "{error:?}");
+}
+
+#[tokio::test]
+async fn peer_without_gossipsub_is_reported_unsupported() {
+    use futures::StreamExt;
+    use libp2p::swarm::{dummy, SwarmEvent};
+
+    // a bare libp2p peer that speaks no protocols at all
+    let mut mock = libp2p::SwarmBuilder::with_new_identity()
+        .with_tokio()
+        .with_tcp(libp2p::tcp::Config::new(), libp2p::noise::Config::new, libp2p::yamux::Config::default)
+        .unwrap()
+        .with_behaviour(|_| dummy::Behaviour)
+        .unwrap()
+        .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(30)))
+        .build();
+    let mock_id = *mock.local_peer_id();
+    mock.listen_on("/ip4/127.0.0.1/tcp/0".parse().unwrap()).unwrap();
+    let addr = loop {
+        if let SwarmEvent::NewListenAddr { address, .. } = mock.select_next_some().await {
+            break address;
+        }
+    };
+    tokio::spawn(async move {
+        loop {
+            mock.select_next_some().await;
+        }
+    });
+
+    let (h, mut rx) = spawn_node(node_config(0, 0), vec![], None).unwrap();
+    listen_addr(&mut rx).await;
+    h.cmd.send(NodeCommand::Subscribe).await.unwrap();
+    h.cmd.send(NodeCommand::Dial { addr }).await.unwrap();
+
+    let peer = timeout(Duration::from_secs(10), async {
+        loop {
+            match rx.recv().await {
+                Some(NodeEvent::PeerUnsupported(peer)) => break peer,
+                Some(_) => continue,
+                None => panic!("node stopped"),
+            }
+        }
+    })
+    .await
+    .expect("unsupported peer event");
+    assert_eq!(peer, mock_id);
+}
+
+#[tokio::test]
+async fn honesty_follows_a_large_bad_peer_set() {
+    let ((publisher, _prx), (receiver, mut rrx)) = connected_pair(node_config(0, 0), node_config(1, 0)).await;
+    let strangers: Vec<PeerId> = (0..5000).map(|_| PeerId::random()).collect();
+    let publish = |seqs: std::ops::RangeInclusive<u64>| {
+        let cmd = publisher.cmd.clone();
+        async move {
+            for seq in seqs {
+                let data = encode(&WireMessage::Good { seq, payload: vec![seq as u8; 16] });
+                cmd.send(NodeCommand::Publish { data }).await.unwrap();
+            }
+            sleep(Duration::from_millis(500)).await;
+        }
+    };
+
+    // the publisher buried in a big set counts as bad...
+    let mut bad = strangers.clone();
+    bad.insert(2500, publisher.peer_id);
+    receiver.cmd.send(NodeCommand::SetBadPeers { bad_peer_ids: bad }).await.unwrap();
+    publish(1..=3).await;
+
+    // ...and as honest again once the set no longer names it
+    receiver.cmd.send(NodeCommand::SetBadPeers { bad_peer_ids: strangers }).await.unwrap();
+    publish(4..=6).await;
+
+    let s = summary(&receiver, &mut rrx).await;
+    assert_eq!(s.accepted, 6);
+    assert_eq!(s.honest_accepted, 3);
+}
+
+#[tokio::test]
+async fn subscribe_after_connect_waits_for_the_first_connection() {
+    let (ready_tx, mut ready_rx) = mpsc::unbounded_channel();
+    let (a, mut arx) = spawn_node(node_config(0, 0), vec![], Some(ready_tx.clone())).unwrap();
+    let addr = listen_addr(&mut arx).await;
+    a.cmd.send(NodeCommand::SubscribeAfterConnect { fallback: Duration::from_secs(60) }).await.unwrap();
+    // nobody to talk to yet, so no subscription
+    assert!(timeout(Duration::from_millis(500), ready_rx.recv()).await.is_err());
+
+    // an inbound connection counts: `a` never dials
+    let (b, mut brx) = spawn_node(node_config(1, 0), vec![], None).unwrap();
+    listen_addr(&mut brx).await;
+    b.cmd.send(NodeCommand::Dial { addr }).await.unwrap();
+    let ready = timeout(Duration::from_secs(5), ready_rx.recv()).await.expect("subscribed once connected");
+    assert_eq!(ready, Some(0));
+
+    // a node that never connects subscribes when the fallback runs out
+    let (c, mut crx) = spawn_node(node_config(2, 0), vec![], Some(ready_tx)).unwrap();
+    listen_addr(&mut crx).await;
+    c.cmd.send(NodeCommand::SubscribeAfterConnect { fallback: Duration::from_millis(200) }).await.unwrap();
+    let ready = timeout(Duration::from_secs(5), ready_rx.recv()).await.expect("subscribed after the fallback");
+    assert_eq!(ready, Some(2));
+}
@@ -0,0 +1,657 @@
+use clap::Parser;
+use gossipsub_score_sim::cli::Cli;
+use gossipsub_score_sim::sim;
+
+mod common;
+
+fn cli(args: &[&str]) -> Cli {
+    Cli::parse_from(std::iter::once("gossipsub-score-sim").chain(args.iter().copied()))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn summary_interval_emits_progress() {
+    let report = sim::run(cli(&[
+        "--peers", "3",
+        "--bad-peers", "0",
+        "--duration-secs", "2",
+        "--summary-interval", "1",
+    ]))
+    .await
+    .unwrap();
+
+    assert!(!report.progress.is_empty());
+    assert_eq!(report.progress[0].nodes_responding, 3);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn bounded_dialing_still_connects_every_node() {
+    let report = sim::run(cli(&[
+        "--peers", "12",
+        "--bad-peers", "0",
+        "--duration-secs", "1",
+        "--dial-concurrency", "2",
+    ]))
+    .await
+    .unwrap();
+
+    assert_eq!(report.failed_dials, 0);
+    for (i, s) in &report.summaries {
+        assert!(s.connected_peer_count > 0, "node {i} never connected");
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn strict_ready_fails_on_barrier_timeout() {
+    let args = ["--peers", "2", "--bad-peers", "0", "--duration-secs", "1", "--ready-timeout-secs", "0"];
+
+    let strict: Vec<&str> = args.iter().copied().chain(["--strict-ready"]).collect();
+    let err = sim::run(cli(&strict)).await.expect_err("strict mode aborts");
+    assert!(err.to_string().contains("ready barrier"), "{err}");
+
+    let report = sim::run(cli(&args)).await.expect("lenient mode proceeds");
+    assert_eq!(report.summaries.len(), 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn audit_log_has_one_line_per_decision() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.jsonl");
+    let report = sim::run(cli(&[
+        "--peers", "3",
+        "--bad-peers", "1",
+        "--duration-secs", "1",
+        "--spam-per-sec", "10",
+        "--audit-log", path.to_str().unwrap(),
+    ]))
+    .await
+    .unwrap();
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    let lines: Vec<serde_json::Value> = contents
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert!(report.total_messages() > 0);
+    assert_eq!(lines.len() as u64, report.total_messages());
+    for field in ["timestamp_ms", "propagation_source", "author", "reason", "acceptance", "score_delta", "score"] {
+        assert!(lines[0].get(field).is_some(), "missing {field}");
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn dump_node_writes_that_nodes_validator_state_as_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("node0.json");
+    sim::run(cli(&[
+        "--peers", "3",
+        "--bad-peers", "1",
+        "--duration-secs", "1",
+        "--spam-per-sec", "10",
+        "--dump-node", "0",
+        "--dump-node-out", path.to_str().unwrap(),
+    ]))
+    .await
+    .unwrap();
+
+    let dump: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(dump["node"], 0);
+    let peers = dump["peers"].as_array().unwrap();
+    assert!(!peers.is_empty());
+    for peer in peers {
+        assert!(peer["peer"].as_str().unwrap().parse::<libp2p::PeerId>().is_ok());
+        for field in ["score", "quarantined", "offences"] {
+            assert!(peer.get(field).is_some(), "missing {field}");
+        }
+    }
+    assert!(dump["authors"].as_array().unwrap().iter().all(|a| a["last_seq"].as_u64().is_some()));
+
+    // an out-of-range node is refused up front
+    assert!(sim::run(cli(&["--no-network", "--peers", "2", "--dump-node", "2"])).await.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn drop_rate_loses_publishes_but_the_run_still_reports() {
+    let args = ["--peers", "3", "--bad-peers", "0", "--duration-secs", "2", "--publish-per-sec", "20"];
+    let lossless = sim::run(cli(&args)).await.unwrap();
+    let lossy = sim::run(cli(&[&args[..], &["--drop-rate", "0.9"]].concat())).await.unwrap();
+
+    assert_eq!(lossless.dropped_publishes, 0);
+    assert!(lossy.dropped_publishes > 0);
+    assert_eq!(lossy.summaries.len(), 3);
+    assert!(lossy.honest_published < lossless.honest_published / 2, "{} vs {}", lossy.honest_published, lossless.honest_published);
+    assert!(lossy.total_messages() < lossless.total_messages() / 2);
+}
+
+#[test]
+fn reputation_gossip_brings_honest_views_of_an_attacker_together() {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use gossipsub_score_sim::codec::{encode, WireMessage};
+    use gossipsub_score_sim::testing::deterministic_peer_id;
+    use gossipsub_score_sim::validator::{Validator, ValidatorConfig};
+
+    // three honest nodes, only the first of which sees the attacker first hand
+    let ids: Vec<_> = (0..3).map(deterministic_peer_id).collect();
+    let attacker = deterministic_peer_id(9);
+    let views = |nodes: &[Validator]| -> Vec<sim::ScoreView> {
+        ids.iter()
+            .zip(nodes)
+            .map(|(id, v)| (*id, v.dump_peer_states().into_iter().map(|(p, score, _)| (p, score)).collect()))
+            .collect()
+    };
+    // three rounds, relaying the planned reports only when `gossip` is on
+    let run = |gossip: bool| {
+        let mut nodes: Vec<Validator> = ids.iter().map(|_| Validator::new(ValidatorConfig::default())).collect();
+        nodes[0].validate(&attacker, Some(&attacker), &encode(&WireMessage::Malicious));
+        // the others have heard enough from the witness to weigh its reports in full
+        for node in &mut nodes[1..] {
+            for seq in 1..=5 {
+                node.validate(&ids[0], Some(&ids[0]), &encode(&WireMessage::Good { seq, payload: vec![seq as u8] }));
+            }
+        }
+        let mut agreement = sim::ScoreAgreement::default();
+        let mut reported = HashMap::new();
+        for round in 0..3 {
+            let views = views(&nodes);
+            agreement.samples.push((Duration::from_secs(round), sim::score_variance(&views, &[attacker]).unwrap()));
+            for (to, reporter, victim, delta) in sim::plan_reputation_reports(&views, &mut reported) {
+                assert_ne!(to, reporter);
+                if gossip {
+                    let i = ids.iter().position(|id| *id == to).unwrap();
+                    nodes[i].apply_reputation_report(&reporter, &victim, delta);
+                }
+            }
+        }
+        (nodes, agreement, reported)
+    };
+
+    let (nodes, with, mut reported) = run(true);
+    let (_, without, _) = run(false);
+    let first = with.samples[0].1;
+    assert!(first > 0.0, "only one node saw the attacker");
+    assert_eq!(without.samples[0].1, first);
+    assert!(with.final_variance().unwrap() < first, "{:?}", with.samples);
+    assert!(
+        with.final_variance().unwrap() < without.final_variance().unwrap(),
+        "gossip {:?} vs none {:?}",
+        with.samples,
+        without.samples
+    );
+    // a settled round reports nothing new
+    assert!(sim::plan_reputation_reports(&views(&nodes), &mut reported).is_empty());
+}
+
+#[test]
+fn non_bootstrap_nodes_dial_a_seeded_half_of_the_bootstraps() {
+    let dials = sim::bootstrap_dials(10, 4, 7);
+    assert_eq!(dials.len(), 10);
+
+    // bootstraps link up with the ones before them
+    for (i, targets) in dials.iter().take(4).enumerate() {
+        assert_eq!(targets, &(0..i).collect::<Vec<_>>());
+    }
+    for targets in &dials[4..] {
+        assert_eq!(targets.len(), 2);
+        assert!(targets.iter().all(|&j| j < 4));
+        assert!(targets.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    assert_eq!(dials, sim::bootstrap_dials(10, 4, 7));
+    // the default single bootstrap matches the old everyone-dials-node-0 topology
+    assert!(sim::bootstrap_dials(5, 1, 7)[1..].iter().all(|t| t == &[0]));
+}
+
+#[test]
+fn random_bad_selection_is_reproducible_and_not_a_prefix() {
+    use sim::BadSelection;
+
+    assert_eq!(sim::select_bad_peers(10, 3, BadSelection::Prefix, 0, 1), [0, 1, 2]);
+
+    let bad = sim::select_bad_peers(20, 5, BadSelection::Random, 0, 42);
+    assert_eq!(bad.len(), 5);
+    assert!(bad.windows(2).all(|w| w[0] < w[1]) && bad.iter().all(|&i| i < 20));
+    assert_ne!(bad, [0, 1, 2, 3, 4]);
+    assert_eq!(bad, sim::select_bad_peers(20, 5, BadSelection::Random, 0, 42));
+
+    let spared = sim::select_bad_peers(20, 5, BadSelection::Random, 3, 42);
+    assert!(spared.iter().all(|&i| i >= 3));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn honest_messages_survive_a_spam_storm() {
+    let report = sim::run(cli(&[
+        "--peers", "8",
+        "--bad-peers", "3",
+        "--duration-secs", "4",
+        "--publish-per-sec", "5",
+        "--spam-per-sec", "50",
+        "--seed", "7",
+    ]))
+    .await
+    .unwrap();
+
+    assert!(report.honest_published > 0);
+    let honest_total = report.honest_accepted + report.honest_rejected;
+    assert!(honest_total > 0, "no honest messages were delivered");
+    let success = report.honest_accepted as f64 / honest_total as f64;
+    assert!(success > 0.9, "honest success rate {success:.2}");
+    assert!(report.total_rejected > 0, "spam was not rejected");
+    assert!(report.total_quarantined > 0, "no bad peer was quarantined");
+}
+
+#[test]
+fn quarantine_consensus_counts_honest_votes_per_attacker() {
+    use gossipsub_score_sim::p2p::NodeSummary;
+    use libp2p::PeerId;
+
+    let (attacker, other) = (PeerId::random(), PeerId::random());
+    let quarantining = |ids: &[PeerId]| NodeSummary { quarantined_ids: ids.iter().copied().collect(), ..Default::default() };
+    let summaries = vec![
+        // the attackers' own views don't count
+        (0, quarantining(&[])),
+        (1, quarantining(&[attacker, other])),
+        (2, quarantining(&[attacker])),
+        (3, quarantining(&[attacker])),
+        (4, quarantining(&[])),
+    ];
+    let consensus = sim::QuarantineConsensus::compute(&summaries, &[(0, attacker), (4, other)]);
+    assert_eq!(consensus.honest_nodes, 3);
+    assert_eq!(consensus.votes, vec![(attacker, 3), (other, 1)]);
+    assert_eq!(consensus.percent(), Some(100.0 * 4.0 / 6.0));
+
+    assert_eq!(sim::QuarantineConsensus::compute(&summaries, &[]).percent(), None);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn recovery_scenario_scores_climb_back_with_decay() {
+    let report = sim::run(cli(&[
+        "--peers", "4",
+        "--bad-peers", "1",
+        "--duration-secs", "6",
+        "--spam-per-sec", "20",
+        "--scenario", "recovery",
+        "--score-decay", "0.7",
+    ]))
+    .await
+    .unwrap();
+
+    let r = report.recovery.expect("recovery scenario reports recovery");
+    assert!(r.peak_score < 0.0, "attack never hurt the bad peer: {r:?}");
+    assert!(r.final_score > r.peak_score + 1.0, "{r:?}");
+}
+
+#[tokio::test]
+async fn no_network_mode_reports_without_libp2p() {
+    let started = std::time::Instant::now();
+    let report = sim::run(cli(&[
+        "--no-network",
+        "--peers", "5",
+        "--bad-peers", "1",
+        "--duration-secs", "10",
+        "--publish-per-sec", "5",
+        "--spam-per-sec", "20",
+    ]))
+    .await
+    .unwrap();
+    assert!(started.elapsed() < std::time::Duration::from_secs(5), "simulated time should not be waited out");
+
+    assert_eq!(report.summaries.len(), 5);
+    assert_eq!(report.honest_published, 4 * 5 * 10);
+    // every honest message reaches the four other nodes exactly once
+    assert_eq!(report.honest_accepted + report.honest_rejected, 4 * 5 * 10 * 4);
+    assert_eq!(report.unique_messages as u64, report.honest_published);
+    assert!(report.total_rejected > 0);
+    assert_eq!(report.quarantine_consensus.percent(), Some(100.0));
+}
+
+#[tokio::test]
+async fn shadow_policy_is_reported_next_to_the_primary() {
+    let report = sim::run(cli(&[
+        "--no-network",
+        "--peers", "4",
+        "--bad-peers", "1",
+        "--duration-secs", "5",
+        "--shadow-penalty-scale", "0.01",
+    ]))
+    .await
+    .unwrap();
+
+    let shadow = report.shadow.as_ref().expect("shadow tallies");
+    let c = &shadow.counters;
+    // both policies judged the same deliveries
+    assert_eq!(c.accepted + c.rejected + c.ignored, report.total_messages());
+    // with tiny penalties only repeated offences quarantine the spammer, so the shadow
+    // rejects more of its messages before ignoring the rest
+    assert!(c.rejected > report.total_rejected, "{c:?}");
+}
+
+#[test]
+fn throttling_summary_reports_bad_peers_throttled_fraction() {
+    use gossipsub_score_sim::codec::{encode, WireMessage};
+    use gossipsub_score_sim::p2p::NodeSummary;
+    use gossipsub_score_sim::validator::{ThrottleStats, Validator, ValidatorConfig};
+    use libp2p::PeerId;
+
+    let mut v = Validator::new(ValidatorConfig { burst_capacity: 10, ..Default::default() });
+    v.freeze_clock();
+    let attacker = PeerId::random();
+    for seq in 1..=20 {
+        v.validate(&attacker, Some(&attacker), &encode(&WireMessage::Good { seq, payload: vec![1] }));
+    }
+    // 10 fit the bucket, 4 are throttled before the penalties quarantine the attacker
+    let stats = v.throttle_stats(&attacker);
+    assert_eq!(stats, ThrottleStats { attempts: 20, throttled: 4, passed: 10 });
+
+    let summaries = vec![
+        (0, NodeSummary::default()),
+        (1, NodeSummary { throttle_stats: v.throttle_stats_all(), ..Default::default() }),
+    ];
+    let throttling = sim::ThrottlingSummary::compute(&summaries, &[(0, attacker)]);
+    assert_eq!(throttling.total_throttled, 4);
+    assert_eq!(throttling.bad_fractions(), Some((20.0, 50.0)));
+}
+
+#[tokio::test]
+async fn summary_collection_records_placeholders_for_dead_and_stuck_nodes() {
+    use gossipsub_score_sim::p2p::{NodeEvent, NodeSummary};
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    let (alive_tx, alive_rx) = mpsc::channel(4);
+    let (killed_tx, killed_rx) = mpsc::channel::<NodeEvent>(4);
+    let (_stuck_tx, stuck_rx) = mpsc::channel::<NodeEvent>(4);
+    alive_tx.send(NodeEvent::Summary(Box::new(NodeSummary { accepted: 3, ..Default::default() }))).await.unwrap();
+    drop(killed_tx);
+
+    let summaries = sim::collect_summaries(vec![alive_rx, killed_rx, stuck_rx], Duration::from_millis(200)).await;
+    let missing: Vec<(usize, bool)> = summaries.iter().map(|(i, s)| (*i, s.missing)).collect();
+    assert_eq!(missing, vec![(0, false), (1, true), (2, true)]);
+
+    let report = sim::build_report(summaries, 3, 0);
+    assert_eq!(report.missing_summaries, 2);
+    assert_eq!(report.total_accepted, 3);
+}
+
+#[tokio::test]
+async fn subscribe_failures_are_attached_to_the_node_summary() {
+    use gossipsub_score_sim::p2p::{NodeEvent, SubscribeError};
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    let (tx, rx) = mpsc::channel(4);
+    tx.send(NodeEvent::SubscribeFailed(SubscribeError::AlreadySubscribed)).await.unwrap();
+    tx.send(NodeEvent::Summary(Box::default())).await.unwrap();
+
+    let summaries = sim::collect_summaries(vec![rx], Duration::from_millis(200)).await;
+    assert_eq!(summaries[0].1.subscribe_failures, 1);
+    assert_eq!(sim::build_report(summaries, 1, 0).subscribe_failures, 1);
+}
+
+#[test]
+fn adaptive_attacker_turns_stealthy_after_the_trigger() {
+    use gossipsub_score_sim::codec::{decode, WireMessage};
+    use gossipsub_score_sim::sim::AttackerState;
+    use gossipsub_score_sim::validator::{Validator, ValidatorConfig};
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::time::Duration;
+
+    let adapt_after = Duration::from_secs(10);
+    let state = AttackerState::Spamming.step(Duration::from_secs(9), adapt_after);
+    assert_eq!(state, AttackerState::Spamming);
+    let state = state.step(adapt_after, adapt_after);
+    assert_eq!(state, AttackerState::Stealth);
+    // there's no way back
+    assert_eq!(state.step(Duration::ZERO, adapt_after), AttackerState::Stealth);
+
+    let mut rng = StdRng::seed_from_u64(1);
+    let spam: Vec<Vec<u8>> = (1..=20).map(|seq| AttackerState::Spamming.message(&mut rng, 0, seq, 1024)).collect();
+    assert!(spam.iter().any(|m| !matches!(decode(m), Ok(WireMessage::Good { .. }))));
+
+    // stealth traffic is well-formed, small and slow enough to pass a default validator
+    let mut v = Validator::new(ValidatorConfig::default());
+    v.freeze_clock();
+    let attacker = libp2p::PeerId::random();
+    for seq in 1..=500 {
+        let m = state.message(&mut rng, 0, seq, 1024);
+        assert!(matches!(decode(&m), Ok(WireMessage::Good { payload, .. }) if payload.len() <= 64));
+        assert_eq!(v.validate(&attacker, Some(&attacker), &m).reason, "ok");
+        v.advance_clock(state.interval(1000));
+    }
+    assert!(state.interval(1000) > AttackerState::Spamming.interval(1000));
+}
+
+#[tokio::test]
+async fn each_attack_type_is_caught_by_its_own_check() {
+    use gossipsub_score_sim::sim::{attack_message, AttackType, AttackerState};
+    use gossipsub_score_sim::validator::{Validator, ValidatorConfig};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut seen = std::collections::HashSet::new();
+    for seq in 1..=200 {
+        let (attack, m) = if seq % 10 == 0 {
+            AttackerState::Stealth.attack(&mut rng, seq, 1024)
+        } else {
+            attack_message(&mut rng, 1024)
+        };
+        // a fresh validator each time, so quarantine and dedupe don't mask the check
+        let mut v = Validator::new(ValidatorConfig { max_message_bytes: 1024, ..Default::default() });
+        let peer = libp2p::PeerId::random();
+        let reason = v.validate(&peer, Some(&peer), &m).reason;
+        assert!(attack.expected_reasons().contains(&reason), "{attack:?} caught by {reason}");
+        seen.insert(attack);
+    }
+    assert_eq!(seen.len(), 5);
+
+    let report = sim::run(cli(&[
+        "--no-network",
+        "--peers", "3",
+        "--bad-peers", "1",
+        "--duration-secs", "2",
+    ]))
+    .await
+    .unwrap();
+    let first_catches: u64 = report
+        .attack_reasons
+        .iter()
+        .filter(|((attack, reason), _)| attack.expected_reasons().iter().any(|r| r.as_str() == *reason))
+        .map(|(_, n)| n)
+        .sum();
+    assert!(first_catches > 0);
+    assert!(report.attack_reasons.keys().all(|(attack, _)| *attack != AttackType::Stealth));
+}
+
+#[test]
+fn unique_delivery_counts_each_honest_message_once() {
+    use gossipsub_score_sim::p2p::NodeSummary;
+    use libp2p::gossipsub::MessageId;
+
+    let id = MessageId::new(b"only message");
+    let bad = libp2p::PeerId::random();
+    let summaries = vec![
+        (0, NodeSummary { published_ids: [id.clone()].into(), ..Default::default() }),
+        (1, NodeSummary { accepted_ids: [id.clone()].into(), ..Default::default() }),
+        (2, NodeSummary::default()),
+        // the attacker accepting it doesn't help it reach a quorum
+        (3, NodeSummary { accepted_ids: [id.clone()].into(), ..Default::default() }),
+    ];
+    let bad_nodes = [(3, bad)];
+
+    let one = sim::UniqueDelivery::compute(&summaries, &bad_nodes, 1);
+    assert_eq!((one.published, one.delivered), (1, 1));
+    assert_eq!(one.percent(), Some(100.0));
+    let two = sim::UniqueDelivery::compute(&summaries, &bad_nodes, 2);
+    assert_eq!(two.percent(), Some(0.0));
+    assert_eq!(sim::UniqueDelivery::compute(&[], &[], 1).percent(), None);
+}
+
+#[tokio::test]
+async fn random_seed_is_fresh_and_logged_per_run() {
+    let (_guard, logged) = common::capture_field("seed");
+    let args = ["--no-network", "--peers", "3", "--bad-peers", "1", "--duration-secs", "1", "--random-seed"];
+
+    let first = sim::run(cli(&args)).await.unwrap();
+    let second = sim::run(cli(&args)).await.unwrap();
+    assert_ne!(first.seed, second.seed);
+    assert_eq!(*logged.lock().unwrap(), [first.seed.to_string(), second.seed.to_string()]);
+}
+
+#[test]
+fn sla_reports_each_criterion_separately() {
+    use sim::{BlindDetection, QuarantineConsensus, SimReport, Sla};
+
+    // 95% honest success, 60% rejected, one honest quarantine, 1 of 10 honest peers flagged
+    let report = SimReport {
+        bad_peers: 2,
+        total_accepted: 40,
+        total_rejected: 60,
+        honest_accepted: 19,
+        honest_rejected: 1,
+        quarantine_consensus: QuarantineConsensus { honest_quarantines: 1, ..Default::default() },
+        blind_detection: BlindDetection { false_positives: 1, true_negatives: 9, ..Default::default() },
+        ..Default::default()
+    };
+    let sla = Sla {
+        min_honest_success: 90.0,
+        min_rejection_rate: 70.0,
+        max_honest_quarantines: 1,
+        max_false_positive_rate: 5.0,
+    };
+    let verdicts = |report: &SimReport| -> Vec<(&str, Option<f64>, bool)> {
+        sla.check(report).iter().map(|c| (c.name, c.actual, c.passed)).collect()
+    };
+    assert_eq!(
+        verdicts(&report),
+        [
+            ("honest_success", Some(95.0), true),
+            ("rejection_rate", Some(60.0), false),
+            ("honest_quarantines", Some(1.0), true),
+            ("false_positive_rate", Some(10.0), false),
+        ]
+    );
+    // with no bad peers there is nothing to reject, so the rate doesn't apply
+    let honest_only = SimReport { bad_peers: 0, ..report.clone() };
+    assert_eq!(verdicts(&honest_only)[1], ("rejection_rate", None, true));
+    assert_eq!(Sla::from_cli(&cli(&[])), Sla::default());
+
+    let args = [
+        "--sla-min-rejection-rate", "50",
+        "--sla-max-honest-quarantines", "1",
+        "--sla-max-false-positive-rate", "10",
+    ];
+    let report = SimReport { sla_checks: Sla::from_cli(&cli(&args)).check(&report), ..report };
+    assert!(report.sla_passed());
+}
+
+#[tokio::test]
+async fn final_scores_separate_honest_from_bad_peers() {
+    let report = sim::run(cli(&[
+        "--no-network",
+        "--peers", "6",
+        "--bad-peers", "2",
+        "--duration-secs", "5",
+        "--spam-per-sec", "20",
+    ]))
+    .await
+    .unwrap();
+
+    let sep = &report.score_separation;
+    assert!(!sep.honest.is_empty() && !sep.bad.is_empty());
+    let (honest, bad) = (sep.honest_mean().unwrap(), sep.bad_mean().unwrap());
+    assert!(sep.gap().unwrap() > 25.0, "honest {honest} vs bad {bad}");
+    assert!(sep.auc().unwrap() > 0.9, "auc {:?}", sep.auc());
+}
+
+#[test]
+fn shadow_at_scale_one_decides_and_scores_like_the_primary() {
+    use gossipsub_score_sim::codec::{encode, WireMessage};
+    use gossipsub_score_sim::testing::deterministic_peer_id;
+    use gossipsub_score_sim::validator::{Validator, ValidatorConfig};
+
+    // everything but the penalties must carry over, so start from a non-default primary
+    let primary = ValidatorConfig {
+        max_message_bytes: 64,
+        dedupe_enabled: false,
+        ignore_penalty: -1.0,
+        app_score_floor: Some(-50.0),
+        ..Default::default()
+    };
+    let mut a = Validator::new(primary.clone());
+    let mut b = Validator::new(sim::shadow_config(&primary, 1.0));
+    let peers: Vec<_> = (0..3).map(deterministic_peer_id).collect();
+    let messages = [
+        encode(&WireMessage::Good { seq: 1, payload: vec![1; 8] }),
+        encode(&WireMessage::Good { seq: 1, payload: vec![1; 8] }),
+        encode(&WireMessage::Good { seq: 2, payload: vec![2; 100] }),
+        encode(&WireMessage::Good { seq: 3, payload: vec![] }),
+        encode(&WireMessage::Malicious),
+        vec![0xff; 10],
+    ];
+    for (i, bytes) in messages.iter().enumerate() {
+        let peer = &peers[i % peers.len()];
+        let (da, db) = (a.validate(peer, Some(peer), bytes), b.validate(peer, Some(peer), bytes));
+        assert_eq!((da.reason, da.score_delta), (db.reason, db.score_delta), "message {i}");
+    }
+    for peer in &peers {
+        assert_eq!(a.get_peer_score(peer), b.get_peer_score(peer));
+        assert_eq!(a.is_quarantined(peer), b.is_quarantined(peer));
+    }
+}
+
+#[test]
+fn detection_latency_covers_a_flood_only_attacker() {
+    use std::time::Duration;
+
+    use gossipsub_score_sim::codec::{encode, WireMessage};
+    use gossipsub_score_sim::p2p::NodeSummary;
+    use gossipsub_score_sim::testing::deterministic_peer_id;
+    use gossipsub_score_sim::validator::{Validator, ValidatorConfig, WellFormedFloodConfig};
+
+    // every message is well-formed and accepted; only the flood penalty ever bites
+    let flood = WellFormedFloodConfig { max_per_window: 2, penalty: -5.0, ..Default::default() };
+    let mut v = Validator::new(ValidatorConfig { well_formed_flood: Some(flood), ..Default::default() });
+    v.freeze_clock();
+    let attacker = deterministic_peer_id(1);
+    for seq in 1..=20 {
+        v.validate(&attacker, Some(&attacker), &encode(&WireMessage::Good { seq, payload: vec![1] }));
+        v.advance_clock(Duration::from_millis(10));
+    }
+    assert!(v.is_quarantined(&attacker));
+
+    let summary = NodeSummary { time_to_quarantine: v.time_to_quarantine_all(), ..Default::default() };
+    let latency = sim::DetectionLatency::compute(&[(0, summary)], &[(1, attacker)]);
+    assert_eq!(latency.per_peer.len(), 1, "{latency:?}");
+    let (peer, took, nodes) = latency.per_peer[0];
+    assert_eq!((peer, nodes), (attacker, 1));
+    assert!(took > Duration::ZERO && took < Duration::from_secs(1), "{took:?}");
+}
+
+#[test]
+fn report_as_refuses_unknown_and_never_refusing_reasons() {
+    use gossipsub_score_sim::p2p::ReportAs;
+    use gossipsub_score_sim::validator::RejectReason;
+
+    let parse = |arg: &str| Cli::try_parse_from(["gossipsub-score-sim", "--report-as", arg]);
+    let cli = parse("oversize=ignore").unwrap();
+    assert_eq!(cli.report_as, vec![(RejectReason::Oversize, ReportAs::Ignore)]);
+    assert!(parse("oversized=ignore").is_err());
+    assert!(parse("ok=reject").is_err());
+    assert!(parse("well_formed_flood=reject").is_err());
+    assert!(parse("oversize=drop").is_err());
+}
+
+#[tokio::test]
+async fn seed_fixes_node_identities() {
+    let id = |seed, idx| sim::node_keypair(seed, idx).public().to_peer_id();
+    assert_eq!(id(7, 0), id(7, 0));
+    assert_ne!(id(7, 0), id(7, 1));
+    assert_ne!(id(7, 0), id(8, 0));
+
+    let args = ["--no-network", "--peers", "3", "--bad-peers", "1", "--duration-secs", "1", "--seed", "7"];
+    let first = sim::run(cli(&args)).await.unwrap();
+    let second = sim::run(cli(&args)).await.unwrap();
+    // the first node is the attacker, under the same peer id both times
+    assert_eq!(first.quarantine_consensus.votes, second.quarantine_consensus.votes);
+    assert_eq!(first.quarantine_consensus.votes.first().map(|(p, _)| *p), Some(id(7, 0)));
+}
@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use gossipsub_score_sim::codec::{encode, WireMessage};
+use gossipsub_score_sim::validator::{Validator, ValidatorConfig};
+use libp2p::PeerId;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::Layer;
+
+// span id -> (name, recorded fields)
+type Spans = Arc<Mutex<HashMap<u64, (&'static str, HashMap<String, String>)>>>;
+
+struct Capture(Spans);
+
+struct Fields<'a>(&'a mut HashMap<String, String>);
+
+impl Visit for Fields<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.insert(field.name().to_string(), format!("{value:?}"));
+    }
+}
+
+impl<S: Subscriber> Layer<S> for Capture {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, _ctx: Context<'_, S>) {
+        let mut fields = HashMap::new();
+        attrs.record(&mut Fields(&mut fields));
+        self.0.lock().unwrap().insert(id.into_u64(), (attrs.metadata().name(), fields));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        if let Some((_, fields)) = self.0.lock().unwrap().get_mut(&id.into_u64()) {
+            values.record(&mut Fields(fields));
+        }
+    }
+}
+
+#[test]
+fn validate_records_a_span_with_peer_and_outcome() {
+    let spans = Spans::default();
+    let subscriber = tracing_subscriber::registry().with(Capture(spans.clone()));
+    let p = PeerId::random();
+    tracing::subscriber::with_default(subscriber, || {
+        let mut v = Validator::new(ValidatorConfig::default());
+        v.validate(&p, Some(&p), &encode(&WireMessage::Good { seq: 1, payload: vec![1] }));
+    });
+
+    let spans = spans.lock().unwrap();
+    let (_, fields) = spans.values().find(|(name, _)| *name == "validate").expect("validate span");
+    assert_eq!(fields["peer"], p.to_string());
+    assert_eq!(fields["author"], p.to_string());
+    assert_eq!(fields["reason"], "ok");
+    assert_eq!(fields["acceptance"], "accept");
+}
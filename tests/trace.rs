@@ -0,0 +1,33 @@
+use gossipsub_score_sim::codec::{encode, WireMessage};
+use gossipsub_score_sim::testing::deterministic_peer_id;
+use gossipsub_score_sim::trace::{read_trace, replay, write_trace, TraceRecord};
+use gossipsub_score_sim::validator::{Validator, ValidatorConfig};
+
+#[test]
+fn records_round_trip_and_replay_in_timestamp_order() {
+    let (relay, author) = (deterministic_peer_id(1), deterministic_peer_id(2));
+    let good = |seq| encode(&WireMessage::Good { seq, payload: vec![seq as u8; 8] });
+    // recorded out of order, and one without an author
+    let records = vec![
+        TraceRecord { propagation_source: relay, author: Some(author), timestamp_ms: 2_000, bytes: good(2) },
+        TraceRecord { propagation_source: relay, author: Some(author), timestamp_ms: 1_000, bytes: good(1) },
+        TraceRecord { propagation_source: relay, author: None, timestamp_ms: 3_000, bytes: encode(&WireMessage::Malicious) },
+        TraceRecord { propagation_source: author, author: Some(author), timestamp_ms: 1_500, bytes: vec![] },
+    ];
+    let mut file = Vec::new();
+    write_trace(&mut file, &records).unwrap();
+    assert_eq!(read_trace(&file[..]).unwrap(), records);
+
+    let mut v = Validator::new(ValidatorConfig::default());
+    let log = replay(&mut v, records);
+    let summary: Vec<(u128, &str)> = log.iter().map(|r| (r.timestamp_ms, r.reason.as_str())).collect();
+    assert_eq!(summary, [(1_000, "ok"), (1_500, "truncated"), (2_000, "ok"), (3_000, "malicious_payload")]);
+    // the anonymous malicious message is blamed on its relay
+    assert_eq!(log[3].author, None);
+    assert_eq!(log[3].score, v.get_peer_score(&relay));
+    assert!(v.get_peer_score(&relay) < 0.0);
+
+    // a cut-off file or a foreign one is refused rather than half-read
+    assert!(read_trace(&file[..file.len() - 1]).is_err());
+    assert!(read_trace(&b"not a trace"[..]).is_err());
+}
@@ -2,12 +2,15 @@ use libp2p::PeerId;
 use proptest::prelude::*;
 
 use gossipsub_score_sim::codec::{encode, WireMessage};
+use gossipsub_score_sim::testing::deterministic_peer_id;
 use gossipsub_score_sim::validator::{Validator, ValidatorConfig};
 
+mod common;
+
 proptest! {
     #[test]
     fn oversized_messages_are_rejected(payload_len in 16385usize..40000usize) {
-        let mut v = Validator::new(ValidatorConfig { max_message_bytes: 16384 });
+        let mut v = Validator::new(ValidatorConfig { max_message_bytes: 16384, ..Default::default() });
         let msg = WireMessage::Good { seq: 1, payload: vec![0u8; payload_len] };
         let bytes = encode(&msg);
 
@@ -18,7 +21,7 @@ proptest! {
 
     #[test]
     fn empty_payloads_rejected(seq in 1u64..1000u64) {
-        let mut v = Validator::new(ValidatorConfig { max_message_bytes: 16384 });
+        let mut v = Validator::new(ValidatorConfig { max_message_bytes: 16384, ..Default::default() });
         let msg = WireMessage::Good { seq, payload: vec![] };
         let bytes = encode(&msg);
         let p = PeerId::random();
@@ -29,7 +32,7 @@ proptest! {
 
     #[test]
     fn decode_errors_make_reject(_seq in 1u64..1000u64) {
-        let mut v = Validator::new(ValidatorConfig { max_message_bytes: 16384 });
+        let mut v = Validator::new(ValidatorConfig { max_message_bytes: 16384, ..Default::default() });
         let bytes = vec![0u8; 10]; // invalid bincode
         let decision = v.validate(&PeerId::random(), Some(&PeerId::random()), &bytes);
         prop_assert!(matches!(decision.acceptance, libp2p::gossipsub::MessageAcceptance::Reject));
@@ -38,7 +41,7 @@ proptest! {
 
     #[test]
     fn replay_detection(seq in 1u64..1000u64) {
-        let mut v = Validator::new(ValidatorConfig { max_message_bytes: 16384 });
+        let mut v = Validator::new(ValidatorConfig { max_message_bytes: 16384, ..Default::default() });
         let peer = PeerId::random();
 
         // Send later sequence first
@@ -54,11 +57,37 @@ proptest! {
         prop_assert!(matches!(decision2.acceptance, libp2p::gossipsub::MessageAcceptance::Ignore));
         prop_assert_eq!(decision2.reason, "replay_or_old_seq");
     }
+
+    #[test]
+    fn internal_state_stays_consistent(ops in proptest::collection::vec((0u64..12, 0u8..6, 0u64..40), 1..300)) {
+        // small tables so eviction and dedupe trimming happen along the way
+        let mut v = Validator::new(ValidatorConfig {
+            max_message_bytes: 64,
+            max_peers: 8,
+            max_dedupe_entries: 16,
+            ..Default::default()
+        });
+        for (peer, kind, seq) in ops {
+            let (fwd, author) = (deterministic_peer_id(peer), deterministic_peer_id(peer / 2));
+            let bytes = match kind {
+                0 | 1 => encode(&WireMessage::Good { seq, payload: vec![seq as u8; 8] }),
+                2 => encode(&WireMessage::Good { seq, payload: vec![] }),
+                3 => encode(&WireMessage::Malicious),
+                4 => vec![seq as u8; 3],
+                _ => {
+                    v.decay_scores(0.5);
+                    continue;
+                }
+            };
+            v.validate(&fwd, Some(&author), &bytes);
+            prop_assert_eq!(v.check_invariants(), Ok(()));
+        }
+    }
 }
 
 #[test]
 fn bad_peer_quarantines_after_multiple_offences() {
-    let mut v = Validator::new(ValidatorConfig { max_message_bytes: 16384 });
+    let mut v = Validator::new(ValidatorConfig { max_message_bytes: 16384, quarantine_threshold: -150.0, ..Default::default() });
     let bad = PeerId::random();
     
     // Simulate multiple offences that should trigger quarantine
@@ -68,3 +97,1247 @@ fn bad_peer_quarantines_after_multiple_offences() {
     v.record_offence_and_update(&bad, -80.0); // second offense (scaled, total crosses -150)
     assert!(v.is_quarantined(&bad)); // Should be quarantined now
 }
+
+#[test]
+fn reputation_reports_are_weighted_by_reporter_and_capped() {
+    let mut v = Validator::new(ValidatorConfig { max_message_bytes: 16384, ..Default::default() });
+    let victim = PeerId::random();
+    let trusted = PeerId::random();
+    let shady = PeerId::random();
+
+    // Make both reporters known; the shady one has a bad local record.
+    for seq in 1..=5 {
+        let ok = encode(&WireMessage::Good { seq, payload: vec![seq as u8; 10] });
+        v.validate(&trusted, Some(&trusted), &ok);
+    }
+    v.record_offence_and_update(&shady, -15.0);
+    assert!(v.get_peer_score(&shady) < -10.0);
+
+    // Reports from a low-scored reporter have no effect.
+    for _ in 0..10 {
+        assert_eq!(v.apply_reputation_report(&shady, &victim, -50.0), 0.0);
+    }
+    assert_eq!(v.get_peer_score(&victim), 0.0);
+
+    // A trusted reporter moves the score, but only up to the external influence cap.
+    let applied = v.apply_reputation_report(&trusted, &victim, -5.0);
+    assert_eq!(applied, -5.0);
+    for _ in 0..10 {
+        v.apply_reputation_report(&trusted, &victim, -50.0);
+    }
+    assert_eq!(v.get_peer_score(&victim), -20.0);
+    assert!(!v.is_quarantined(&victim));
+}
+
+#[test]
+fn fresh_reporters_count_for_nothing_until_they_deliver() {
+    let mut v = Validator::new(ValidatorConfig::default());
+    let victim = deterministic_peer_id(0);
+
+    // a swarm of brand-new identities in good standing, known only for relaying a duplicate
+    let origin = deterministic_peer_id(99);
+    let msg = encode(&WireMessage::Good { seq: 1, payload: vec![1] });
+    v.validate(&origin, Some(&origin), &msg);
+    for i in 1..=50 {
+        let sybil = deterministic_peer_id(i);
+        v.validate(&sybil, Some(&origin), &msg);
+        assert_eq!(v.get_peer_score(&sybil), 0.0);
+        assert!(!v.is_quarantined(&sybil));
+        assert_eq!(v.apply_reputation_report(&sybil, &victim, -50.0), 0.0);
+    }
+    assert_eq!(v.get_peer_score(&victim), 0.0);
+
+    // the weight ramps up with the reporter's accepted messages
+    let reporter = deterministic_peer_id(100);
+    let ok = |seq: u64| encode(&WireMessage::Good { seq, payload: vec![seq as u8; 4] });
+    v.validate(&reporter, Some(&reporter), &ok(1));
+    assert_eq!(v.apply_reputation_report(&reporter, &victim, -5.0), -1.0);
+    for seq in 2..=5 {
+        v.validate(&reporter, Some(&reporter), &ok(seq));
+    }
+    assert_eq!(v.apply_reputation_report(&reporter, &victim, -5.0), -5.0);
+}
+
+#[test]
+fn colluding_reporters_cannot_whitewash_a_quarantined_attacker() {
+    let mut v = Validator::new(ValidatorConfig::default());
+    let attacker = deterministic_peer_id(0);
+    v.record_offence_and_update(&attacker, -40.0);
+    assert!(v.is_quarantined(&attacker));
+
+    // established, well-behaved reporters vouching for the attacker over and over
+    for i in 1..=10 {
+        let friend = deterministic_peer_id(i);
+        for seq in 1..=5 {
+            v.validate(&friend, Some(&friend), &encode(&WireMessage::Good { seq, payload: vec![seq as u8] }));
+        }
+        for _ in 0..10 {
+            assert_eq!(v.apply_reputation_report(&friend, &attacker, 20.0), 0.0);
+        }
+    }
+    assert_eq!(v.get_peer_score(&attacker), -40.0);
+    assert!(v.is_quarantined(&attacker));
+}
+
+#[test]
+fn ignore_penalty_charges_duplicate_heavy_forwarder() {
+    let mut v = Validator::new(ValidatorConfig {
+        max_message_bytes: 16384,
+        ignore_penalty: 0.5,
+        ..Default::default()
+    });
+    let author = PeerId::random();
+    let forwarder = PeerId::random();
+    let bytes = encode(&WireMessage::Good { seq: 1, payload: vec![1u8; 10] });
+
+    let first = v.validate(&author, Some(&author), &bytes);
+    assert!(matches!(first.acceptance, libp2p::gossipsub::MessageAcceptance::Accept));
+
+    let mut last = 0.0;
+    for _ in 0..10 {
+        let d = v.validate(&forwarder, Some(&author), &bytes);
+        assert_eq!(d.reason, "duplicate");
+        assert_eq!(d.score_delta, -0.5);
+        let score = v.get_peer_score(&forwarder);
+        assert!(score < last);
+        last = score;
+    }
+    assert_eq!(v.get_peer_score(&forwarder), -5.0);
+    assert_eq!(v.get_peer_score(&author), 0.0);
+}
+
+#[test]
+fn ignore_penalty_spares_a_quarantined_forwarder() {
+    let mut v = Validator::new(ValidatorConfig { ignore_penalty: 0.5, ..Default::default() });
+    let forwarder = PeerId::random();
+    v.validate(&forwarder, Some(&forwarder), &encode(&WireMessage::Malicious));
+    assert!(v.is_quarantined(&forwarder));
+    let score = v.get_peer_score(&forwarder);
+
+    for seq in 1..=5 {
+        let d = v.validate(&forwarder, Some(&forwarder), &encode(&WireMessage::Good { seq, payload: vec![1] }));
+        assert_eq!(d.reason, "forwarder_quarantined");
+        assert_eq!(d.score_delta, 0.0);
+    }
+    assert_eq!(v.get_peer_score(&forwarder), score);
+}
+
+#[test]
+fn forwarder_churn_keeps_author_sequence_state() {
+    let mut v = Validator::new(ValidatorConfig { max_message_bytes: 16384, ..Default::default() });
+    let author = PeerId::random();
+    let first = encode(&WireMessage::Good { seq: 100, payload: vec![1u8; 10] });
+    assert!(matches!(v.validate(&author, Some(&author), &first).acceptance, libp2p::gossipsub::MessageAcceptance::Accept));
+
+    // Far more forwarders than the forwarder table holds.
+    for i in 0..2_000u64 {
+        let fwd = PeerId::random();
+        let junk = vec![0u8; 10 + (i % 7) as usize];
+        v.validate(&fwd, Some(&fwd), &junk);
+    }
+
+    let replay = encode(&WireMessage::Good { seq: 50, payload: vec![2u8; 10] });
+    let d = v.validate(&PeerId::random(), Some(&author), &replay);
+    assert_eq!(d.reason, "replay_or_old_seq");
+}
+
+#[test]
+fn full_author_table_evicts_least_recently_active_author() {
+    let mut v = Validator::new(ValidatorConfig { max_tracked_authors: 2, ..Default::default() });
+    let (a, b, c) = (deterministic_peer_id(1), deterministic_peer_id(2), deterministic_peer_id(3));
+    let good = |seq, byte| encode(&WireMessage::Good { seq, payload: vec![byte; 10] });
+
+    v.validate(&a, Some(&a), &good(10, 1));
+    v.validate(&b, Some(&b), &good(10, 2));
+    // `a` was added first but has been active since; `b` is now the stalest
+    v.validate(&a, Some(&a), &good(11, 3));
+    v.validate(&c, Some(&c), &good(10, 4));
+
+    assert_eq!(v.validate(&a, Some(&a), &good(5, 5)).reason, "replay_or_old_seq");
+    assert_eq!(v.validate(&b, Some(&b), &good(5, 6)).reason, "ok", "b's seq state was evicted");
+}
+
+#[test]
+fn should_forward_matches_acceptance() {
+    let mut v = Validator::new(ValidatorConfig { max_message_bytes: 16384, ..Default::default() });
+    let author = PeerId::random();
+    let good = encode(&WireMessage::Good { seq: 5, payload: vec![1u8; 10] });
+    let cases: Vec<(PeerId, Vec<u8>, &str, bool)> = vec![
+        (author, good.clone(), "ok", true),
+        (author, good, "duplicate", false),
+        (author, encode(&WireMessage::Good { seq: 1, payload: vec![2u8; 10] }), "replay_or_old_seq", false),
+        (PeerId::random(), encode(&WireMessage::Good { seq: 6, payload: vec![] }), "empty_payload", false),
+        (PeerId::random(), vec![0u8; 10], "decode_error", false),
+        (PeerId::random(), vec![0u8; 20_000], "oversize", false),
+        (PeerId::random(), encode(&WireMessage::Malicious), "malicious_payload", false),
+    ];
+    for (peer, bytes, reason, expected) in cases {
+        let (forward, d) = v.should_forward(&peer, Some(&peer), &bytes);
+        assert_eq!(d.reason, reason);
+        assert_eq!(forward, expected, "{reason}");
+        assert_eq!(forward, matches!(d.acceptance, libp2p::gossipsub::MessageAcceptance::Accept));
+    }
+
+    // The malicious peer above is now quarantined as a forwarder.
+    let quarantined = v.dump_peer_states().into_iter().find(|(_, _, q)| *q).unwrap().0;
+    let (forward, d) = v.should_forward(&quarantined, Some(&author), &[0u8; 10]);
+    assert_eq!(d.reason, "forwarder_quarantined");
+    assert!(!forward);
+}
+
+#[test]
+fn prune_idle_removes_only_idle_peers() {
+    let mut v = Validator::new(ValidatorConfig { max_message_bytes: 16384, ..Default::default() });
+    let active = PeerId::random();
+    let idle = PeerId::random();
+    v.validate(&active, Some(&active), &encode(&WireMessage::Good { seq: 1, payload: vec![1u8; 10] }));
+    v.validate(&idle, Some(&idle), &encode(&WireMessage::Good { seq: 1, payload: vec![2u8; 10] }));
+    assert_eq!(v.dump_peer_states().len(), 2);
+
+    v.advance_clock(std::time::Duration::from_secs(120));
+    v.validate(&active, Some(&active), &encode(&WireMessage::Good { seq: 2, payload: vec![3u8; 10] }));
+
+    assert_eq!(v.prune_idle(std::time::Duration::from_secs(60)), 1);
+    let remaining: Vec<PeerId> = v.dump_peer_states().into_iter().map(|(p, _, _)| p).collect();
+    assert_eq!(remaining, vec![active]);
+}
+
+#[test]
+fn decisions_serialize_reason_and_acceptance_as_strings() {
+    let mut v = Validator::new(ValidatorConfig { max_message_bytes: 16384, ..Default::default() });
+    let p = PeerId::random();
+    let d = v.validate(&p, Some(&p), &[0u8; 10]);
+
+    let json = serde_json::to_value(&d).unwrap();
+    assert_eq!(json["reason"], "decode_error");
+    assert_eq!(json["acceptance"], "reject");
+    assert_eq!(json["score_delta"], -30.0);
+}
+
+#[test]
+fn trusted_author_junk_is_rejected_without_penalty() {
+    let mut v = Validator::new(ValidatorConfig { max_message_bytes: 16384, ..Default::default() });
+    let beacon = PeerId::random();
+    let forwarder = PeerId::random();
+    v.add_trusted_author(beacon);
+
+    for bytes in [vec![0u8; 10], vec![0u8; 20_000], encode(&WireMessage::Malicious)] {
+        let d = v.validate(&forwarder, Some(&beacon), &bytes);
+        assert!(matches!(d.acceptance, libp2p::gossipsub::MessageAcceptance::Reject));
+        assert_eq!(d.score_delta, 0.0);
+    }
+    assert_eq!(v.get_peer_score(&beacon), 0.0);
+    assert!(!v.is_quarantined(&beacon));
+
+    assert!(v.remove_trusted_author(&beacon));
+    let d = v.validate(&forwarder, Some(&beacon), &[0u8; 11]);
+    assert_eq!(d.reason, "decode_error");
+    assert!(v.get_peer_score(&beacon) < 0.0);
+}
+
+#[test]
+fn duplicates_from_low_scored_peers_are_rejected() {
+    let mut v = Validator::new(ValidatorConfig {
+        max_message_bytes: 16384,
+        reject_duplicates_below: Some(-10.0),
+        ..Default::default()
+    });
+    let author = PeerId::random();
+    let healthy = PeerId::random();
+    let shady = PeerId::random();
+    v.record_offence_and_update(&shady, -15.0);
+
+    let bytes = encode(&WireMessage::Good { seq: 1, payload: vec![1u8; 10] });
+    assert_eq!(v.validate(&author, Some(&author), &bytes).reason, "ok");
+
+    let d = v.validate(&healthy, Some(&author), &bytes);
+    assert!(matches!(d.acceptance, libp2p::gossipsub::MessageAcceptance::Ignore));
+    assert_eq!(d.reason, "duplicate");
+    assert_eq!(v.get_peer_score(&healthy), 0.0);
+
+    let before = v.get_peer_score(&shady);
+    let d = v.validate(&shady, Some(&author), &bytes);
+    assert!(matches!(d.acceptance, libp2p::gossipsub::MessageAcceptance::Reject));
+    assert_eq!(d.reason, "duplicate");
+    assert!(v.get_peer_score(&shady) < before);
+}
+
+#[test]
+fn payload_validator_rejects_bad_magic() {
+    let mut v = Validator::new(ValidatorConfig { max_message_bytes: 16384, ..Default::default() });
+    v.set_payload_validator(Box::new(|payload: &[u8]| {
+        if payload.first() == Some(&0xAB) {
+            Ok(())
+        } else {
+            Err("bad_magic")
+        }
+    }));
+    let author = PeerId::random();
+
+    let good = encode(&WireMessage::Good { seq: 1, payload: vec![0xAB, 1, 2, 3] });
+    assert_eq!(v.validate(&author, Some(&author), &good).reason, "ok");
+
+    let bad = encode(&WireMessage::Good { seq: 2, payload: vec![0x00, 1, 2, 3] });
+    let d = v.validate(&author, Some(&author), &bad);
+    assert!(matches!(d.acceptance, libp2p::gossipsub::MessageAcceptance::Reject));
+    assert_eq!(d.reason, "bad_magic");
+    assert_eq!(d.score_delta, -30.0);
+    assert_eq!(serde_json::to_value(&d).unwrap()["reason"], "bad_magic");
+}
+
+#[test]
+fn validate_survives_peer_churn_past_capacity() {
+    let mut v = Validator::new(ValidatorConfig { max_message_bytes: 64, ..Default::default() });
+    let authors: Vec<PeerId> = (0..50).map(deterministic_peer_id).collect();
+    for i in 0..5_000u64 {
+        let fwd = deterministic_peer_id(50 + i);
+        let author = &authors[(i % 50) as usize];
+        let bytes = match i % 5 {
+            0 => encode(&WireMessage::Good { seq: i, payload: vec![(i % 251) as u8; 8] }),
+            1 => encode(&WireMessage::Good { seq: i, payload: vec![] }),
+            2 => encode(&WireMessage::Malicious),
+            3 => vec![0xFFu8; 100],
+            _ => vec![i as u8; 3],
+        };
+        v.validate(&fwd, Some(author), &bytes);
+        v.apply_reputation_report(&fwd, author, -1.0);
+    }
+    assert!(v.dump_peer_states().len() <= 1000);
+}
+
+#[test]
+fn latency_histogram_counts_every_validation() {
+    let mut v = Validator::new(ValidatorConfig { max_message_bytes: 16384, ..Default::default() });
+    let author = PeerId::random();
+    for seq in 1..=250u64 {
+        let bytes = encode(&WireMessage::Good { seq, payload: vec![(seq % 255) as u8 + 1; 32] });
+        v.validate(&author, Some(&author), &bytes);
+    }
+    v.should_forward(&author, Some(&author), &[0u8; 10]);
+
+    let h = v.latency_histogram();
+    assert_eq!(h.count(), 251);
+    assert_eq!(h.buckets().iter().map(|(_, c)| c).sum::<u64>(), 251);
+    assert!(h.percentile_us(0.5) <= h.percentile_us(0.99));
+}
+
+#[test]
+fn trailing_bytes_rejected_only_in_strict_mode() {
+    let mut bytes = encode(&WireMessage::Good { seq: 1, payload: vec![1, 2, 3] });
+    bytes.extend_from_slice(b"smuggled");
+    let p = PeerId::random();
+
+    let mut lenient = Validator::new(ValidatorConfig::default());
+    let decision = lenient.validate(&p, Some(&p), &bytes);
+    assert!(matches!(decision.acceptance, libp2p::gossipsub::MessageAcceptance::Accept));
+
+    let mut strict = Validator::new(ValidatorConfig { strict_decode: true, ..Default::default() });
+    let decision = strict.validate(&p, Some(&p), &bytes);
+    assert!(matches!(decision.acceptance, libp2p::gossipsub::MessageAcceptance::Reject));
+    assert_eq!(decision.reason, "trailing_bytes");
+
+    // exact encodings and plain junk are unaffected by strict mode
+    let exact = encode(&WireMessage::Good { seq: 2, payload: vec![1] });
+    let q = PeerId::random();
+    assert_eq!(strict.validate(&q, Some(&q), &exact).reason, "ok");
+    let r = PeerId::random();
+    assert_eq!(strict.validate(&r, Some(&r), &[0xff; 3]).reason, "decode_error");
+}
+
+#[test]
+fn rate_limit_escalation_quarantines_flooder_sooner() {
+    use gossipsub_score_sim::validator::RateLimitEscalation;
+
+    // messages a single forwarder gets through before it is quarantined
+    fn flood_until_quarantined(cfg: ValidatorConfig) -> u64 {
+        let mut v = Validator::new(cfg);
+        let p = PeerId::random();
+        for seq in 1..=1000u64 {
+            let bytes = encode(&WireMessage::Good { seq, payload: vec![1] });
+            v.validate(&p, Some(&p), &bytes);
+            if v.is_quarantined(&p) {
+                return seq;
+            }
+        }
+        panic!("flooder never quarantined");
+    }
+
+    let linear = flood_until_quarantined(ValidatorConfig::default());
+    let escalating = flood_until_quarantined(ValidatorConfig {
+        rate_limit_escalation: Some(RateLimitEscalation { hits: 2, ..Default::default() }),
+        ..Default::default()
+    });
+    assert!(escalating < linear, "escalating {escalating} vs linear {linear}");
+}
+
+#[test]
+fn update_config_requarantines_under_new_threshold() {
+    let mut v = Validator::new(ValidatorConfig::default());
+    let p = PeerId::random();
+    v.record_offence_and_update(&p, -10.0);
+    assert!(!v.is_quarantined(&p));
+
+    let bytes = encode(&WireMessage::Good { seq: 1, payload: vec![1] });
+    let q = PeerId::random();
+    v.validate(&q, Some(&q), &bytes);
+
+    v.update_config(ValidatorConfig { quarantine_threshold: -5.0, max_dedupe_entries: 0, ..Default::default() });
+    assert!(v.is_quarantined(&p));
+    assert_eq!(v.get_peer_score(&p), -10.0);
+    // the shrunken dedupe cache forgot the message, so it is no longer a duplicate
+    let r = PeerId::random();
+    assert_eq!(v.validate(&r, None, &bytes).reason, "ok");
+}
+
+#[test]
+fn codec_errors_carry_their_own_reason_and_penalty() {
+    let mut v = Validator::new(ValidatorConfig::default());
+    let good = encode(&WireMessage::Good { seq: 1, payload: vec![1; 8] });
+    let mut huge = good[..13].to_vec();
+    huge.extend_from_slice(&u64::MAX.to_le_bytes());
+
+    let cases = [
+        (good[..good.len() - 2].to_vec(), "truncated", -10.0),
+        (vec![0u8; 10], "decode_error", -30.0),
+        (huge, "too_large", -60.0),
+    ];
+    for (bytes, reason, penalty) in cases {
+        let p = PeerId::random();
+        let d = v.validate(&p, Some(&p), &bytes);
+        assert_eq!(d.reason, reason);
+        assert_eq!(d.score_delta, penalty);
+    }
+}
+
+#[test]
+fn compressed_frame_expanding_past_the_limit_is_rejected_before_decode() {
+    use gossipsub_score_sim::codec::encode_compressed;
+
+    let mut v = Validator::new(ValidatorConfig { max_message_bytes: 16384, ..Default::default() });
+    // a few hundred bytes on the wire, a megabyte once expanded
+    let bomb = encode_compressed(&WireMessage::Good { seq: 1, payload: vec![0; 1 << 20] });
+    assert!(bomb.len() < 16384);
+
+    let p = PeerId::random();
+    let d = v.validate(&p, Some(&p), &bomb);
+    assert_eq!(d.reason, "oversize");
+    assert_eq!(d.score_delta, -60.0);
+
+    let q = PeerId::random();
+    let small = encode_compressed(&WireMessage::Good { seq: 1, payload: vec![7; 1000] });
+    assert_eq!(v.validate(&q, Some(&q), &small).reason, "ok");
+}
+
+#[test]
+fn malicious_marker_round_trips_and_is_rejected() {
+    use gossipsub_score_sim::codec::decode;
+
+    let bytes = encode(&WireMessage::Malicious);
+    assert!(matches!(decode(&bytes), Ok(WireMessage::Malicious)));
+
+    let mut v = Validator::new(ValidatorConfig::default());
+    let p = PeerId::random();
+    let decision = v.validate(&p, Some(&p), &bytes);
+    assert!(matches!(decision.acceptance, libp2p::gossipsub::MessageAcceptance::Reject));
+    assert_eq!(decision.reason, "malicious_payload");
+}
+
+#[test]
+fn eviction_of_equally_idle_peers_is_deterministic() {
+    let mut v = Validator::new(ValidatorConfig::default());
+    v.freeze_clock();
+    let mut peers: Vec<PeerId> = (0..1000).map(deterministic_peer_id).collect();
+    for p in &peers {
+        v.record_offence_and_update(p, -1.0);
+    }
+
+    let newcomer = deterministic_peer_id(1000);
+    v.record_offence_and_update(&newcomer, -1.0);
+
+    peers.sort();
+    let tracked: std::collections::HashSet<PeerId> = v.dump_peer_states().into_iter().map(|(p, _, _)| p).collect();
+    assert!(!tracked.contains(&peers[0]), "lowest peer id is evicted");
+    assert!(peers[1..].iter().all(|p| tracked.contains(p)));
+    assert!(tracked.contains(&newcomer));
+}
+
+#[test]
+fn burst_allowed_but_sustained_excess_throttled() {
+    let mut v = Validator::new(ValidatorConfig { burst_capacity: 10, sustained_rate: 2.0, ..Default::default() });
+    v.freeze_clock();
+    let p = PeerId::random();
+    let mut seq = 0u64;
+    let mut send = |v: &mut Validator| {
+        seq += 1;
+        v.validate(&p, Some(&p), &encode(&WireMessage::Good { seq, payload: vec![1] })).reason
+    };
+
+    // a burst up to the allowance goes through at once
+    for _ in 0..10 {
+        assert_eq!(send(&mut v), "ok");
+    }
+    assert_eq!(send(&mut v), "rate_limited");
+
+    // afterwards only the sustained rate gets through: 2/s, not 4/s
+    v.advance_clock(std::time::Duration::from_secs(1));
+    let outcomes: Vec<_> = (0..4).map(|_| send(&mut v)).collect();
+    assert_eq!(outcomes, ["ok", "ok", "rate_limited", "rate_limited"]);
+}
+
+#[test]
+fn disabled_dedupe_passes_repeats_to_content_checks() {
+    let bytes = encode(&WireMessage::Good { seq: 1, payload: vec![1] });
+    let p = PeerId::random();
+
+    let mut deduping = Validator::new(ValidatorConfig::default());
+    deduping.validate(&p, Some(&p), &bytes);
+    assert_eq!(deduping.validate(&p, Some(&p), &bytes).reason, "duplicate");
+
+    let mut v = Validator::new(ValidatorConfig { dedupe_enabled: false, ..Default::default() });
+    assert_eq!(v.validate(&p, Some(&p), &bytes).reason, "ok");
+    // the repeat now reaches the replay check instead
+    assert_eq!(v.validate(&p, Some(&p), &bytes).reason, "replay_or_old_seq");
+}
+
+#[test]
+fn uniform_fast_publishing_is_penalized_but_jitter_is_not() {
+    use gossipsub_score_sim::validator::IntervalAnomalyConfig;
+    use std::time::Duration;
+
+    // score of an author after 40 messages sent at the given gaps (in microseconds)
+    fn score_after(gaps_us: impl Fn(u64) -> u64) -> f64 {
+        let mut v = Validator::new(ValidatorConfig {
+            interval_anomaly: Some(IntervalAnomalyConfig::default()),
+            ..Default::default()
+        });
+        v.freeze_clock();
+        let p = PeerId::random();
+        let mut charged = 0.0;
+        for seq in 1..=40u64 {
+            v.advance_clock(Duration::from_micros(gaps_us(seq)));
+            let bytes = encode(&WireMessage::Good { seq, payload: vec![1] });
+            let decision = v.validate(&p, Some(&p), &bytes);
+            assert_eq!(decision.reason, "ok");
+            charged += decision.score_delta;
+        }
+        // the decisions report every nudge they applied
+        assert_eq!(charged, v.get_peer_score(&p));
+        v.get_peer_score(&p)
+    }
+
+    assert!(score_after(|_| 500) < 0.0, "metronomic bot is nudged down");
+    assert_eq!(score_after(|seq| if seq % 2 == 0 { 200 } else { 1_500 }), 0.0);
+}
+
+#[test]
+fn restored_validator_remembers_recent_duplicates() {
+    use gossipsub_score_sim::validator::ValidatorSnapshot;
+
+    let cfg = || ValidatorConfig { max_persisted_dedupe: 100, ..Default::default() };
+    let bytes = encode(&WireMessage::Good { seq: 1, payload: vec![1] });
+    let p = PeerId::random();
+    let mut v = Validator::new(cfg());
+    assert_eq!(v.validate(&p, Some(&p), &bytes).reason, "ok");
+
+    // round-trip through serialization, as a restarting node would
+    let json = serde_json::to_string(&v.snapshot()).unwrap();
+    let snapshot: ValidatorSnapshot = serde_json::from_str(&json).unwrap();
+
+    let q = PeerId::random();
+    let mut restored = Validator::restore(cfg(), snapshot.clone());
+    assert_eq!(restored.validate(&q, None, &bytes).reason, "duplicate");
+
+    // past the TTL the key is gone; replay protection still knows the author's seq
+    let mut expired = Validator::restore(ValidatorConfig { dedupe_ttl: std::time::Duration::ZERO, ..cfg() }, snapshot);
+    assert_eq!(expired.validate(&q, Some(&p), &bytes).reason, "replay_or_old_seq");
+}
+
+#[test]
+fn explain_lists_recent_penalties_and_final_score() {
+    let mut v = Validator::new(ValidatorConfig::default());
+    let p = PeerId::random();
+    let q = PeerId::random();
+    v.validate(&q, Some(&p), &encode(&WireMessage::Good { seq: 1, payload: vec![] }));
+    v.validate(&q, Some(&p), &[0xff; 3]);
+
+    let e = v.explain(&p);
+    assert!(e.tracked);
+    assert_eq!(e.offences, 2);
+    let reasons: Vec<&str> = e.recent_penalties.iter().map(|(r, _)| r.as_str()).collect();
+    assert_eq!(reasons, ["empty_payload", "decode_error"]);
+    // second offence is scaled by 1.5
+    assert_eq!(e.recent_penalties[1].1, -45.0);
+    assert_eq!(e.score, -75.0);
+    assert!(e.quarantined);
+    assert!(e.to_string().contains("recent: empty_payload (-30.0), decode_error (-45.0)"), "{e}");
+
+    assert!(!v.explain(&PeerId::random()).tracked);
+}
+
+#[test]
+fn replay_bursts_escalate_to_reject() {
+    use gossipsub_score_sim::validator::ReplayPolicy;
+    use libp2p::gossipsub::MessageAcceptance;
+
+    let mut v = Validator::new(ValidatorConfig {
+        replay_policy: Some(ReplayPolicy { max_replays: 3, ..Default::default() }),
+        ..Default::default()
+    });
+    v.freeze_clock();
+    let p = PeerId::random();
+    let replay = |i: u8| encode(&WireMessage::Good { seq: 1, payload: vec![i] });
+    v.validate(&p, Some(&p), &encode(&WireMessage::Good { seq: 5, payload: vec![0] }));
+
+    // an occasional retransmit stays free
+    let d = v.validate(&p, Some(&p), &replay(1));
+    assert!(matches!(d.acceptance, MessageAcceptance::Ignore));
+    assert_eq!(v.get_peer_score(&p), 0.0);
+
+    let decisions: Vec<_> = (2..=5).map(|i| v.validate(&p, Some(&p), &replay(i)).acceptance).collect();
+    assert!(matches!(decisions[..], [MessageAcceptance::Ignore, MessageAcceptance::Ignore, MessageAcceptance::Reject, _]));
+    assert!(v.get_peer_score(&p) < 0.0);
+
+    // a fresh window forgives the burst
+    let q = PeerId::random();
+    v.validate(&q, Some(&q), &encode(&WireMessage::Good { seq: 5, payload: vec![9] }));
+    for i in 0..5u8 {
+        v.advance_clock(std::time::Duration::from_secs(11));
+        let d = v.validate(&q, Some(&q), &encode(&WireMessage::Good { seq: 1, payload: vec![10 + i] }));
+        assert!(matches!(d.acceptance, MessageAcceptance::Ignore));
+    }
+}
+
+#[test]
+fn peers_where_filters_by_status_and_score() {
+    use gossipsub_score_sim::validator::PeerStatus;
+    use std::collections::HashSet;
+
+    let mut v = Validator::new(ValidatorConfig::default());
+    let healthy = PeerId::random();
+    v.validate(&healthy, Some(&healthy), &encode(&WireMessage::Good { seq: 1, payload: vec![1] }));
+    let degraded = PeerId::random();
+    v.record_offence_and_update(&degraded, -10.0);
+    let mild = PeerId::random();
+    v.record_offence_and_update(&mild, -30.0);
+    let severe = PeerId::random();
+    v.record_offence_and_update(&severe, -80.0);
+
+    let deep: HashSet<PeerId> = v
+        .peers_where(|_, score, status| status == PeerStatus::Quarantined && score < -50.0)
+        .into_iter()
+        .collect();
+    assert_eq!(deep, HashSet::from([severe]));
+
+    assert_eq!(v.peers_where(|_, _, status| status == PeerStatus::Degraded), [degraded]);
+    assert_eq!(v.peers_where(|_, _, status| status == PeerStatus::Healthy), [healthy]);
+    assert_eq!(v.peers_where(|_, _, _| true).len(), 4);
+}
+
+#[test]
+fn honest_message_via_quarantined_relay_can_be_accepted() {
+    let relay = PeerId::random();
+    let author = PeerId::random();
+    let bytes = encode(&WireMessage::Good { seq: 1, payload: vec![1] });
+
+    let mut strict = Validator::new(ValidatorConfig::default());
+    strict.record_offence_and_update(&relay, -50.0);
+    assert_eq!(strict.validate(&relay, Some(&author), &bytes).reason, "forwarder_quarantined");
+
+    let mut v = Validator::new(ValidatorConfig { accept_via_quarantined_forwarder: true, ..Default::default() });
+    v.record_offence_and_update(&relay, -50.0);
+    let d = v.validate(&relay, Some(&author), &bytes);
+    assert!(matches!(d.acceptance, libp2p::gossipsub::MessageAcceptance::Accept));
+    assert!(v.is_quarantined(&relay));
+    assert_eq!(v.get_peer_score(&relay), -50.0);
+
+    // the relay's own messages are still ignored
+    let own = encode(&WireMessage::Good { seq: 1, payload: vec![2] });
+    assert_eq!(v.validate(&relay, Some(&relay), &own).reason, "forwarder_quarantined");
+}
+
+#[test]
+fn new_peers_are_not_quarantined_during_grace_period() {
+    use std::time::Duration;
+
+    let mut v = Validator::new(ValidatorConfig {
+        min_age_before_quarantine: Duration::from_secs(30),
+        ..Default::default()
+    });
+    v.freeze_clock();
+    let p = PeerId::random();
+    v.validate(&p, Some(&p), &[0xff; 3]);
+    v.validate(&p, Some(&p), &[0xfe; 3]);
+    assert!(v.get_peer_score(&p) < -25.0);
+    assert!(!v.is_quarantined(&p));
+
+    v.advance_clock(Duration::from_secs(31));
+    let bytes = encode(&WireMessage::Good { seq: 1, payload: vec![1] });
+    assert_eq!(v.validate(&p, Some(&p), &bytes).reason, "forwarder_quarantined");
+    assert!(v.is_quarantined(&p));
+}
+
+#[test]
+fn local_peer_traffic_is_never_rate_limited() {
+    let me = PeerId::random();
+    let mut v = Validator::new(ValidatorConfig { local_peer_id: Some(me), burst_capacity: 10, ..Default::default() });
+    v.freeze_clock();
+
+    // far past the burst, with no time to refill
+    for seq in 1..=200u64 {
+        let bytes = encode(&WireMessage::Good { seq, payload: vec![1; 10] });
+        assert_eq!(v.validate(&me, Some(&me), &bytes).reason, "ok", "message {seq}");
+    }
+    assert_eq!(v.get_peer_score(&me), 0.0);
+
+    // anyone else is cut off once the burst is spent
+    let other = PeerId::random();
+    let accepted = (1..=20u64)
+        .map(|seq| v.validate(&other, Some(&other), &encode(&WireMessage::Good { seq, payload: vec![2; 10] })))
+        .filter(|d| d.reason == "ok")
+        .count();
+    assert_eq!(accepted, 10);
+}
+
+#[test]
+fn acceptance_controller_softens_penalties_below_target() {
+    use gossipsub_score_sim::validator::{AcceptanceController, PenaltyConfig};
+
+    let base = PenaltyConfig::default();
+    let mut c = AcceptanceController::new(0.95, base.clone());
+
+    let softer = c.step(50, 50).expect("honest traffic seen");
+    assert!(softer.decode_error.abs() < base.decode_error.abs());
+    assert!(softer.malicious_payload.abs() < base.malicious_payload.abs());
+    assert!(c.scale() < 1.0);
+
+    // no honest traffic: nothing to go on
+    assert!(c.step(0, 0).is_none());
+
+    // above target the penalties recover
+    let before = c.scale();
+    c.step(100, 0).unwrap();
+    assert!(c.scale() > before);
+
+    // and never leave the configured band
+    for _ in 0..100 {
+        c.step(0, 10);
+    }
+    assert_eq!(c.scale(), c.min_scale);
+}
+
+#[test]
+fn quarantine_hysteresis_band_prevents_flapping() {
+    let mut v = Validator::new(ValidatorConfig {
+        quarantine_exit_threshold: Some(-10.0),
+        ignore_penalty: 2.0,
+        ..Default::default()
+    });
+    v.freeze_clock();
+    let (fwd, author) = (PeerId::random(), PeerId::random());
+    let bytes = encode(&WireMessage::Good { seq: 1, payload: vec![1; 10] });
+    v.validate(&fwd, Some(&author), &bytes);
+
+    // duplicates charge the forwarder 2 each until it crosses the entry threshold
+    while !v.is_quarantined(&fwd) {
+        v.validate(&fwd, Some(&author), &bytes);
+    }
+
+    // decay lifts it back over -25, but inside the band it stays quarantined (and its
+    // further duplicates, ignored, no longer cost it anything)
+    let mut crossed = false;
+    while v.get_peer_score(&fwd) <= -10.0 {
+        crossed |= v.get_peer_score(&fwd) > -25.0;
+        assert!(v.is_quarantined(&fwd), "released at {}", v.get_peer_score(&fwd));
+        let before = v.get_peer_score(&fwd);
+        v.validate(&fwd, Some(&author), &bytes);
+        assert_eq!(v.get_peer_score(&fwd), before);
+        v.decay_scores(0.9);
+    }
+    assert!(crossed, "score never rose past the entry threshold");
+
+    // only a real recovery past the exit threshold releases it
+    while v.get_peer_score(&fwd) <= -10.0 {
+        assert!(v.is_quarantined(&fwd));
+        v.decay_scores(0.9);
+    }
+    assert!(!v.is_quarantined(&fwd));
+}
+
+#[test]
+fn accepted_messages_earn_back_offences() {
+    use gossipsub_score_sim::validator::PenaltyConfig;
+
+    let mut v = Validator::new(ValidatorConfig {
+        penalties: PenaltyConfig { empty_payload: -2.0, ..Default::default() },
+        offences_forgiven_per_accept: 0.25,
+        ..Default::default()
+    });
+    let p = PeerId::random();
+    let empty = |seq| encode(&WireMessage::Good { seq, payload: vec![] });
+    v.validate(&p, Some(&p), &empty(1));
+    v.validate(&p, Some(&p), &empty(2));
+    assert_eq!(v.explain(&p).offences, 2);
+
+    for seq in 3..=6 {
+        assert_eq!(v.validate(&p, Some(&p), &encode(&WireMessage::Good { seq, payload: vec![1] })).reason, "ok");
+    }
+    assert_eq!(v.explain(&p).offences, 1);
+    for seq in 7..=10 {
+        v.validate(&p, Some(&p), &encode(&WireMessage::Good { seq, payload: vec![1] }));
+    }
+    assert_eq!(v.explain(&p).offences, 0);
+
+    // a fresh offence is charged at the base rate again
+    v.validate(&p, Some(&p), &empty(11));
+    assert_eq!(v.explain(&p).recent_penalties.last().unwrap().1, -2.0);
+}
+
+#[test]
+fn app_score_handed_to_gossipsub_is_clamped_to_floor() {
+    let mut v = Validator::new(ValidatorConfig { app_score_floor: Some(-20.0), ..Default::default() });
+    let p = PeerId::random();
+    v.record_offence_and_update(&p, -5.0);
+    assert_eq!(v.get_app_score_option(&p), Some(-5.0));
+
+    for _ in 0..10 {
+        v.record_offence_and_update(&p, -50.0);
+    }
+    assert!(v.get_peer_score(&p) < -100.0);
+    assert!(v.is_quarantined(&p));
+    assert_eq!(v.get_app_score_option(&p), Some(-20.0));
+}
+
+#[test]
+fn classify_tells_malicious_from_honest_by_behaviour() {
+    use gossipsub_score_sim::validator::Classification;
+
+    let mut v = Validator::new(ValidatorConfig::default());
+    let (bad, good) = (PeerId::random(), PeerId::random());
+    for seq in 1..=10 {
+        v.validate(&good, Some(&good), &encode(&WireMessage::Good { seq, payload: vec![1] }));
+    }
+    for _ in 0..3 {
+        v.validate(&bad, Some(&bad), &encode(&WireMessage::Malicious));
+    }
+    assert_eq!(v.classify(&bad), Classification::Malicious);
+    assert_eq!(v.classify(&good), Classification::Honest);
+    assert_eq!(v.classify(&PeerId::random()), Classification::Honest);
+}
+
+#[test]
+fn classify_does_not_blame_authors_for_a_relay_being_rate_limited() {
+    use gossipsub_score_sim::validator::Classification;
+
+    let mut v = Validator::new(ValidatorConfig { burst_capacity: 2, sustained_rate: 0.001, ..Default::default() });
+    v.freeze_clock();
+    let (relay, author) = (deterministic_peer_id(0), deterministic_peer_id(1));
+    for seq in 1..=10 {
+        v.validate(&relay, Some(&author), &encode(&WireMessage::Good { seq, payload: vec![1] }));
+    }
+    // eight of the ten were the relay's rate-limit rejections, not the author's
+    assert_eq!(v.classify(&author), Classification::Honest);
+}
+
+#[test]
+fn well_formed_flooder_accrues_penalty_when_enabled() {
+    use gossipsub_score_sim::validator::WellFormedFloodConfig;
+    use std::time::Duration;
+
+    // score of an author sending 40 tiny, valid messages at the given gap
+    fn score_after(policy: Option<WellFormedFloodConfig>, gap: Duration) -> f64 {
+        let mut v = Validator::new(ValidatorConfig { well_formed_flood: policy, ..Default::default() });
+        v.freeze_clock();
+        let p = PeerId::random();
+        for seq in 1..=40u64 {
+            v.advance_clock(gap);
+            let bytes = encode(&WireMessage::Good { seq, payload: vec![1] });
+            assert_eq!(v.validate(&p, Some(&p), &bytes).reason, "ok");
+        }
+        v.get_peer_score(&p)
+    }
+
+    let flood = Duration::from_millis(20);
+    assert_eq!(score_after(None, flood), 0.0, "off by default");
+    let flooded = score_after(Some(WellFormedFloodConfig::default()), flood);
+    assert!(flooded < 0.0 && flooded > -25.0, "mild penalty, got {flooded}");
+    assert_eq!(score_after(Some(WellFormedFloodConfig::default()), Duration::from_millis(200)), 0.0);
+}
+
+#[test]
+fn deterministic_peer_ids_are_stable_per_seed() {
+    assert_eq!(deterministic_peer_id(7), deterministic_peer_id(7));
+    assert_ne!(deterministic_peer_id(7), deterministic_peer_id(8));
+}
+
+#[test]
+fn decode_errors_from_new_peers_are_softened() {
+    use gossipsub_score_sim::validator::DecodeErrorGrace;
+    use std::time::Duration;
+
+    let mut v = Validator::new(ValidatorConfig { decode_error_grace: Some(DecodeErrorGrace::default()), ..Default::default() });
+    v.freeze_clock();
+    // a peer speaking a newer wire version
+    let skewed = [2u8, 0, 0, 0];
+    let fresh = deterministic_peer_id(1);
+    for _ in 0..3 {
+        let d = v.validate(&fresh, Some(&fresh), &skewed);
+        assert_eq!(d.reason, "decode_error");
+        assert_eq!(d.score_delta, -7.5);
+    }
+    assert_eq!(v.explain(&fresh).offences, 0);
+    assert!(!v.is_quarantined(&fresh));
+    // grace is used up
+    assert_eq!(v.validate(&fresh, Some(&fresh), &skewed).score_delta, -30.0);
+
+    let settled = deterministic_peer_id(2);
+    v.validate(&settled, Some(&settled), &encode(&WireMessage::Good { seq: 1, payload: vec![1] }));
+    v.advance_clock(Duration::from_secs(31));
+    assert_eq!(v.validate(&settled, Some(&settled), &skewed).score_delta, -30.0);
+}
+
+#[test]
+fn author_bucket_throttles_an_author_across_forwarders() {
+    use gossipsub_score_sim::validator::AuthorRateLimit;
+
+    let mut v = Validator::new(ValidatorConfig {
+        author_rate_limit: Some(AuthorRateLimit { burst_capacity: 20, sustained_rate: 1.0 }),
+        ..Default::default()
+    });
+    v.freeze_clock();
+    let author = deterministic_peer_id(0);
+    let relays: Vec<PeerId> = (1..=5).map(deterministic_peer_id).collect();
+    let mut reasons = Vec::new();
+    for seq in 1..=25u64 {
+        let relay = &relays[(seq % 5) as usize];
+        let bytes = encode(&WireMessage::Good { seq, payload: vec![1] });
+        reasons.push(v.validate(relay, Some(&author), &bytes).reason.as_str());
+    }
+    // no single relay comes near its own bucket, but the author drains theirs
+    assert!(reasons[..20].iter().all(|r| *r == "ok"));
+    assert_eq!(reasons[20], "rate_limited");
+    assert!(v.get_peer_score(&author) < 0.0);
+    assert!(relays.iter().all(|r| v.get_peer_score(r) == 0.0));
+}
+
+#[test]
+fn observe_only_reports_reasons_but_never_quarantines() {
+    let mut v = Validator::new(ValidatorConfig { observe_only: true, dedupe_enabled: false, ..Default::default() });
+    let p = deterministic_peer_id(0);
+    for _ in 0..10 {
+        let d = v.validate(&p, Some(&p), &encode(&WireMessage::Malicious));
+        assert_eq!(d.reason, "malicious_payload");
+        assert!(d.score_delta < 0.0);
+        assert!(matches!(d.acceptance, libp2p::gossipsub::MessageAcceptance::Accept));
+    }
+    assert!(!v.is_quarantined(&p));
+    assert_eq!(v.get_peer_score(&p), 0.0);
+
+    // enforcing again applies the very next offence
+    let cfg = ValidatorConfig { observe_only: false, ..v.config().clone() };
+    v.update_config(cfg);
+    let d = v.validate(&p, Some(&p), &encode(&WireMessage::Malicious));
+    assert!(matches!(d.acceptance, libp2p::gossipsub::MessageAcceptance::Reject));
+    assert!(v.is_quarantined(&p));
+}
+
+#[test]
+fn dedupe_cache_never_outgrows_its_capacity() {
+    let mut v = Validator::new(ValidatorConfig { max_dedupe_entries: 10, ..Default::default() });
+    assert_eq!(v.dedupe_len(), 0);
+    let p = deterministic_peer_id(0);
+    for seq in 1..=50u64 {
+        v.validate(&p, Some(&p), &encode(&WireMessage::Good { seq, payload: vec![1] }));
+        assert_eq!(v.dedupe_len(), (seq as usize).min(10));
+    }
+
+    // shrinking the capacity prunes the oldest entries straight away
+    let cfg = ValidatorConfig { max_dedupe_entries: 4, ..v.config().clone() };
+    v.update_config(cfg);
+    assert_eq!(v.dedupe_len(), 4);
+}
+
+#[test]
+fn time_to_quarantine_runs_from_first_offence() {
+    use std::time::Duration;
+
+    let mut v = Validator::new(ValidatorConfig { burst_capacity: 1, ..Default::default() });
+    v.freeze_clock();
+    let p = deterministic_peer_id(0);
+    v.validate(&p, Some(&p), &encode(&WireMessage::Good { seq: 1, payload: vec![1] }));
+    // the second message overdraws the bucket: a small offence, far from quarantine
+    assert_eq!(v.validate(&p, Some(&p), &encode(&WireMessage::Good { seq: 2, payload: vec![2] })).reason, "rate_limited");
+    assert!(!v.is_quarantined(&p));
+    assert_eq!(v.time_to_quarantine(&p), None);
+
+    v.advance_clock(Duration::from_secs(3));
+    v.validate(&p, Some(&p), &encode(&WireMessage::Malicious));
+    assert!(v.is_quarantined(&p));
+    assert_eq!(v.time_to_quarantine(&p), Some(Duration::from_secs(3)));
+    assert_eq!(v.time_to_quarantine_all(), vec![(p, Duration::from_secs(3))]);
+}
+
+#[test]
+fn effective_rate_follows_the_binding_bucket() {
+    use gossipsub_score_sim::validator::AuthorRateLimit;
+
+    let mut v = Validator::new(ValidatorConfig { sustained_rate: 40.0, ..Default::default() });
+    let spammer = deterministic_peer_id(0);
+    let quiet = deterministic_peer_id(1);
+    for seq in 1..=200u64 {
+        v.validate(&spammer, Some(&spammer), &encode(&WireMessage::Good { seq, payload: vec![1] }));
+    }
+    v.validate(&quiet, Some(&quiet), &encode(&WireMessage::Good { seq: 1, payload: vec![1] }));
+    // no adaptive refill: throttling doesn't slow a peer's refill
+    assert_eq!(v.effective_rate(&spammer), 40.0);
+    assert_eq!(v.effective_rate(&quiet), 40.0);
+
+    let cfg = ValidatorConfig {
+        author_rate_limit: Some(AuthorRateLimit { burst_capacity: 10, sustained_rate: 5.0 }),
+        ..v.config().clone()
+    };
+    v.update_config(cfg);
+    assert_eq!(v.effective_rate(&quiet), 5.0);
+    assert_eq!(v.effective_rate(&deterministic_peer_id(2)), 5.0);
+}
+
+#[test]
+fn memory_budget_scales_every_capacity() {
+    let sized = |budget| Validator::new(ValidatorConfig { memory_budget_bytes: Some(budget), ..Default::default() });
+    let defaults = ValidatorConfig::default();
+    let small = sized(21_120);
+    let large = sized(21_120_000);
+    let (s, l) = (small.config(), large.config());
+    assert_eq!((s.max_peers, s.max_dedupe_entries, s.max_tracked_authors), (10, 100, 100));
+    assert_eq!((l.max_peers, l.max_dedupe_entries, l.max_tracked_authors), (10_000, 100_000, 100_000));
+    assert!(s.max_peers < defaults.max_peers && defaults.max_peers < l.max_peers);
+
+    // the small budget really bounds what the validator holds
+    let mut v = small;
+    for seed in 0..50 {
+        let p = deterministic_peer_id(seed);
+        v.validate(&p, Some(&p), &encode(&WireMessage::Good { seq: 1, payload: vec![seed as u8] }));
+    }
+    assert_eq!(v.peers_where(|_, _, _| true).len(), 10);
+    assert_eq!(v.dedupe_len(), 50);
+
+    // a tiny budget still leaves room for one of each
+    let tiny = sized(1);
+    assert_eq!((tiny.config().max_peers, tiny.config().max_dedupe_entries), (1, 1));
+}
+
+#[test]
+fn probation_shrinks_the_bucket_until_the_score_recovers() {
+    use gossipsub_score_sim::validator::{PeerStatus, ProbationConfig};
+
+    let mut v = Validator::new(ValidatorConfig { probation: Some(ProbationConfig::default()), ..Default::default() });
+    let p = deterministic_peer_id(0);
+    let on_probation = |v: &Validator| v.peers_where(|_, _, status| status == PeerStatus::Probation).contains(&p);
+
+    v.validate(&p, Some(&p), &encode(&WireMessage::Good { seq: 1, payload: vec![1] }));
+    assert!(!on_probation(&v));
+    assert_eq!(v.bucket_capacity(&p), 100);
+
+    // one truncated message costs exactly the -10 threshold
+    let bytes = encode(&WireMessage::Good { seq: 2, payload: vec![1; 16] });
+    let d = v.validate(&p, Some(&p), &bytes[..bytes.len() - 4]);
+    assert_eq!(d.reason, "truncated");
+    assert!(on_probation(&v));
+    assert!(!v.is_quarantined(&p));
+    assert_eq!(v.bucket_capacity(&p), 25);
+    assert_eq!(v.get_app_score_option(&p), Some(-20.0));
+
+    // halving the score lifts it back out, restoring the full bucket and app score
+    v.decay_scores(0.5);
+    assert!(!on_probation(&v));
+    assert_eq!(v.bucket_capacity(&p), 100);
+    assert_eq!(v.get_app_score_option(&p), Some(-5.0));
+}
+
+#[test]
+fn offence_forced_quarantine_survives_decay_and_a_later_report() {
+    let mut v = Validator::new(ValidatorConfig::default());
+    let (bad, reporter) = (deterministic_peer_id(0), deterministic_peer_id(1));
+    for _ in 0..5 {
+        v.record_offence_and_update(&bad, -1.0);
+    }
+    assert!(v.is_quarantined(&bad));
+
+    // decay lifts the score over the line, but the offence count still holds it
+    v.decay_scores(0.0);
+    assert_eq!(v.get_peer_score(&bad), 0.0);
+    assert!(v.is_quarantined(&bad));
+
+    // a small score change from a trusted reporter mustn't free it either
+    for seq in 1..=5 {
+        v.validate(&reporter, Some(&reporter), &encode(&WireMessage::Good { seq, payload: vec![seq as u8] }));
+    }
+    assert_eq!(v.apply_reputation_report(&reporter, &bad, -1.0), -1.0);
+    assert!(v.is_quarantined(&bad));
+}
+
+#[test]
+fn an_evicted_peer_comes_back_without_its_offences() {
+    use std::time::Duration;
+
+    let offences = |v: &Validator, peer: &PeerId| {
+        v.snapshot().peers.into_iter().find(|s| s.peer == peer.to_bytes()).map(|s| s.offences)
+    };
+    let mut v = Validator::new(ValidatorConfig { max_peers: 2, ..Default::default() });
+    v.freeze_clock();
+    let ids: Vec<PeerId> = (0..3).map(deterministic_peer_id).collect();
+    for _ in 0..3 {
+        v.record_offence_and_update(&ids[0], -1.0);
+    }
+    assert_eq!(offences(&v, &ids[0]), Some(3));
+
+    // two fresher peers push the first out of the table
+    for (seq, p) in (1..).zip(&ids[1..]) {
+        v.advance_clock(Duration::from_secs(1));
+        v.validate(p, Some(p), &encode(&WireMessage::Good { seq, payload: vec![seq as u8] }));
+    }
+    assert_eq!(offences(&v, &ids[0]), None);
+    assert_eq!(v.check_invariants(), Ok(()));
+
+    // back again, its next offence is a first offence
+    assert_eq!(v.record_offence_and_update(&ids[0], -1.0), -1.0);
+    assert_eq!(offences(&v, &ids[0]), Some(1));
+}
+
+#[test]
+fn first_accept_is_recorded_once() {
+    use std::time::Duration;
+
+    let mut v = Validator::new(ValidatorConfig::default());
+    v.freeze_clock();
+    let (p, late) = (deterministic_peer_id(0), deterministic_peer_id(1));
+    assert_eq!(v.time_to_first_accept(&p), None);
+
+    // a rejection doesn't count
+    v.advance_clock(Duration::from_secs(1));
+    let bytes = encode(&WireMessage::Good { seq: 1, payload: vec![1; 8] });
+    v.validate(&p, Some(&p), &bytes[..bytes.len() - 2]);
+    assert_eq!(v.time_to_first_accept(&p), None);
+
+    v.advance_clock(Duration::from_secs(1));
+    v.validate(&p, Some(&p), &encode(&WireMessage::Good { seq: 2, payload: vec![1] }));
+    let first = v.time_to_first_accept(&p).unwrap();
+    assert!(first >= Duration::from_secs(2) && first < Duration::from_secs(3), "{first:?}");
+
+    // later accepts leave it alone, and each peer has its own
+    v.advance_clock(Duration::from_secs(5));
+    v.validate(&p, Some(&p), &encode(&WireMessage::Good { seq: 3, payload: vec![1] }));
+    v.validate(&late, Some(&late), &encode(&WireMessage::Good { seq: 1, payload: vec![2] }));
+    assert_eq!(v.time_to_first_accept(&p), Some(first));
+    assert!(v.time_to_first_accept(&late).unwrap() >= Duration::from_secs(7));
+    assert_eq!(v.time_to_first_accept_all().len(), 2);
+}
+
+#[test]
+fn accept_despite_accepts_listed_reasons_but_logs_them() {
+    use gossipsub_score_sim::validator::RejectReason;
+
+    let (_guard, logged) = common::capture_field("reason");
+
+    let mut v = Validator::new(ValidatorConfig {
+        accept_despite: [RejectReason::DecodeError].into(),
+        ..Default::default()
+    });
+    let p = deterministic_peer_id(0);
+    let d = v.validate(&p, Some(&p), &[0u8; 10]);
+    assert!(matches!(d.acceptance, libp2p::gossipsub::MessageAcceptance::Accept));
+    assert_eq!(d.reason, "decode_error");
+    assert!(d.score_delta < 0.0);
+    assert_eq!(*logged.lock().unwrap(), ["decode_error"]);
+
+    // reasons not in the set are still enforced
+    let q = deterministic_peer_id(1);
+    let d = v.validate(&q, Some(&q), &encode(&WireMessage::Malicious));
+    assert!(matches!(d.acceptance, libp2p::gossipsub::MessageAcceptance::Reject));
+}
+
+#[test]
+fn peers_from_one_address_share_reputation_when_bound() {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    let (old_key, new_key, elsewhere) = (deterministic_peer_id(0), deterministic_peer_id(1), deterministic_peer_id(2));
+    for bind in [false, true] {
+        let mut v = Validator::new(ValidatorConfig { bind_reputation_to_address: bind, ..Default::default() });
+        v.note_peer_address(&old_key, ip);
+        v.validate(&old_key, Some(&old_key), &encode(&WireMessage::Malicious));
+        assert!(v.is_quarantined(&old_key));
+
+        // the attacker comes back under a fresh key from the same address
+        v.note_peer_address(&new_key, ip);
+        v.note_peer_address(&elsewhere, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)));
+        assert_eq!(v.get_peer_score(&elsewhere), 0.0);
+        if bind {
+            assert_eq!(v.get_peer_score(&new_key), v.get_peer_score(&old_key));
+            assert!(v.is_quarantined(&new_key));
+        } else {
+            assert_eq!(v.get_peer_score(&new_key), 0.0);
+            assert!(!v.is_quarantined(&new_key));
+        }
+    }
+}
+
+#[test]
+fn a_second_key_on_an_address_cannot_reset_its_reputation() {
+    use gossipsub_score_sim::validator::RejectReason;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    let (attacker, neighbour, fresh) = (deterministic_peer_id(0), deterministic_peer_id(1), deterministic_peer_id(2));
+    let mut v = Validator::new(ValidatorConfig { bind_reputation_to_address: true, ..Default::default() });
+    v.note_peer_address(&attacker, ip);
+    v.note_peer_address(&neighbour, ip);
+    v.validate(&attacker, Some(&attacker), &encode(&WireMessage::Malicious));
+    assert!(v.is_quarantined(&attacker));
+
+    // a key that was on the address before the offence takes a small penalty of its own;
+    // its milder score must not replace the address's
+    let good = encode(&WireMessage::Good { seq: 1, payload: vec![1; 8] });
+    let truncated = v.validate(&neighbour, Some(&neighbour), &good[..good.len() - 2]);
+    assert_ne!(truncated.reason, RejectReason::Ok);
+    assert!(v.get_peer_score(&neighbour) > v.get_peer_score(&attacker));
+
+    v.note_peer_address(&fresh, ip);
+    assert_eq!(v.get_peer_score(&fresh), v.get_peer_score(&attacker));
+    assert!(v.is_quarantined(&fresh));
+}
+
+#[test]
+fn validate_decoded_matches_validate() {
+    use gossipsub_score_sim::codec::encode_compressed;
+
+    let max = 64;
+    let mut by_bytes = Validator::new(ValidatorConfig { max_message_bytes: max, ..Default::default() });
+    let mut by_msg = Validator::new(ValidatorConfig { max_message_bytes: max, ..Default::default() });
+    let (fwd, author) = (deterministic_peer_id(0), deterministic_peer_id(1));
+    let good = |seq, payload: Vec<u8>| WireMessage::Good { seq, payload };
+    let frames = [
+        (good(1, vec![1; 8]), false),
+        (good(1, vec![1; 8]), false), // duplicate
+        (good(1, vec![1; 8]), true),  // same message, different frame: not a duplicate
+        (good(1, vec![1; 8]), true),  // duplicate of the compressed frame
+        (good(1, vec![2; 8]), false), // replay
+        (good(2, vec![]), false),
+        (good(3, vec![3; 2 * max]), false), // oversize
+        (good(4, vec![4; 2 * max]), true),  // small frame, oversize once expanded
+        (good(5, vec![5; 8]), false),
+        (WireMessage::Malicious, false),
+        (good(6, vec![6; 8]), false), // author now quarantined
+    ];
+    for (msg, compressed) in &frames {
+        let bytes = if *compressed { encode_compressed(msg) } else { encode(msg) };
+        let a = by_bytes.validate(&fwd, Some(&author), &bytes);
+        let b = by_msg.validate_decoded(&fwd, Some(&author), msg, &bytes);
+        assert_eq!(
+            (format!("{:?}", a.acceptance), a.reason, a.score_delta),
+            (format!("{:?}", b.acceptance), b.reason, b.score_delta),
+            "{msg:?} compressed={compressed}"
+        );
+    }
+    assert_eq!(by_bytes.get_peer_score(&author), by_msg.get_peer_score(&author));
+    assert_eq!(by_bytes.dedupe_len(), by_msg.dedupe_len());
+}